@@ -485,7 +485,9 @@ impl Block {
                                 let orig_loc = val.loc;
                                 val.loc = token.loc;
                                 val.loc.column -= 1; // point at the $, it looks better
-                                val.loc.link_idx = Some(MACRO_MAP.get_or_insert_loc(orig_loc));
+                                let arg_index = MACRO_MAP.get_or_insert_loc(orig_loc);
+                                MACRO_MAP.record_arg(arg_index, link_index);
+                                val.loc.link_idx = Some(arg_index);
                                 content.push(val);
                                 break;
                             }
@@ -0,0 +1,376 @@
+//! Recording which events, `on_actions`, and scripted effects call each other.
+//!
+//! The calls are recorded while validating, at the places where the references to events,
+//! `on_actions`, and scripted effects are checked. The caller is tracked on a per-thread stack,
+//! because validation of one item happens entirely on one thread.
+//!
+//! The uses of the items checked by `--unused` are recorded the same way, so that items that are
+//! only used by other unused items can be found.
+//!
+//! Recording is off unless enabled with
+//! [`Everything::enable_call_graph`](crate::Everything::enable_call_graph), because every call
+//! and use would otherwise take a lock.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use crate::block::{Block, BlockItem, Field, BV};
+use crate::helpers::{TigerHashMap, TigerHashSet};
+use crate::item::Item;
+use crate::token::{Loc, Token};
+
+thread_local! {
+    /// The items currently being validated on this thread, innermost last.
+    static CALLERS: RefCell<Vec<CallNode>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A node in the call graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CallNode {
+    /// An item of the given type, such as an event, an `on_action`, or a decision.
+    Item(Item, &'static str),
+    /// A file whose contents are not validated as part of a single item, such as history files.
+    File(&'static Path),
+}
+
+impl CallNode {
+    fn kind(self) -> &'static str {
+        match self {
+            CallNode::Item(itype, _) => itype.into(),
+            CallNode::File(_) => "file",
+        }
+    }
+
+    fn key(self) -> String {
+        match self {
+            CallNode::Item(_, key) => key.to_owned(),
+            CallNode::File(path) => path.to_string_lossy().into_owned(),
+        }
+    }
+
+    /// A unique name for this node, such as `event:my_mod.0001`.
+    pub fn id(self) -> String {
+        format!("{}:{}", self.kind(), self.key())
+    }
+}
+
+/// A call from one node to another.
+#[derive(Clone, Debug)]
+pub struct CallEdge {
+    pub from: CallNode,
+    pub to: CallNode,
+    /// Where the call is made.
+    pub loc: Loc,
+    /// The field that makes the call, such as `trigger_event` or `random_events`.
+    pub context: String,
+    /// The delay before the call takes effect, if any, such as `5 days` or `1-3 months`.
+    pub delay: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct CallGraphInner {
+    /// The nodes that were seen as callers, with the locations of their definitions.
+    defined: TigerHashMap<CallNode, Loc>,
+    edges: TigerHashMap<(CallNode, CallNode, Loc), CallEdge>,
+    /// The items checked by `--unused` that each node refers to. See [`Item::tracks_usage`].
+    uses: TigerHashMap<CallNode, TigerHashSet<(Item, String)>>,
+}
+
+/// The calls between events, `on_actions`, scripted effects and other items, as found during
+/// validation.
+#[derive(Debug, Default)]
+pub struct CallGraph {
+    enabled: bool,
+    inner: Mutex<CallGraphInner>,
+}
+
+impl CallGraph {
+    pub(crate) fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Run `f` with calls being recorded as made by the item `key` of type `itype`.
+    pub(crate) fn with_caller<R>(&self, itype: Item, key: &Token, f: impl FnOnce() -> R) -> R {
+        if !self.enabled {
+            return f();
+        }
+        let node = CallNode::Item(itype, key.as_str());
+        self.inner.lock().unwrap().defined.entry(node).or_insert(strip_link(key.loc));
+        CALLERS.with_borrow_mut(|callers| callers.push(node));
+        let result = f();
+        CALLERS.with_borrow_mut(Vec::pop);
+        result
+    }
+
+    /// Record a call from the current caller to the item `target` of type `itype`.
+    ///
+    /// Only the first call recorded from a given location is kept, so callers that know more
+    /// about the call, such as its delay, should record it before validating the target.
+    pub(crate) fn record(&self, itype: Item, target: &Token, context: &str, delay: Option<String>) {
+        if !self.enabled {
+            return;
+        }
+        let loc = strip_link(target.loc);
+        let from = CALLERS
+            .with_borrow(|callers| callers.last().copied())
+            .unwrap_or(CallNode::File(loc.pathname()));
+        let to = CallNode::Item(itype, target.as_str());
+        self.inner.lock().unwrap().edges.entry((from, to, loc)).or_insert_with(|| CallEdge {
+            from,
+            to,
+            loc,
+            context: context.to_owned(),
+            delay,
+        });
+    }
+
+    /// Record that the current caller, or else the file of `token`, refers to the item `key` of
+    /// type `itype`. This is used to find out which items can be reached from the roots of the
+    /// script, such as `on_actions` and decisions.
+    pub(crate) fn record_use(&self, itype: Item, key: &str, token: &Token) {
+        if !self.enabled {
+            return;
+        }
+        let from = CALLERS
+            .with_borrow(|callers| callers.last().copied())
+            .unwrap_or(CallNode::File(token.loc.pathname()));
+        let mut inner = self.inner.lock().unwrap();
+        inner.uses.entry(from).or_default().insert((itype, key.to_owned()));
+    }
+
+    /// Record calls to the items listed in `block`, which is a field such as `events` or
+    /// `random_on_action` in an `on_action`. A `delay` field applies to the items after it.
+    pub(crate) fn record_list(&self, itype: Item, block: &Block, context: &str) {
+        if !self.enabled {
+            return;
+        }
+        let mut delay = None;
+        for item in block.iter_items() {
+            match item {
+                BlockItem::Value(token) => self.record(itype, token, context, delay.clone()),
+                BlockItem::Field(Field(key, _, BV::Block(block))) if key.is("delay") => {
+                    delay = describe_delay(block);
+                }
+                // The weighted entries of `random_events` and `random_on_action`
+                BlockItem::Field(Field(key, _, BV::Value(token)))
+                    if key.is_integer() && !key.is("0") =>
+                {
+                    self.record(itype, token, context, delay.clone());
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Return all the recorded calls, sorted by caller and location.
+    #[allow(clippy::missing_panics_doc)] // only panics on poisoned mutex
+    pub fn edges(&self) -> Vec<CallEdge> {
+        let mut edges: Vec<_> = self.inner.lock().unwrap().edges.values().cloned().collect();
+        edges.sort_by(|a, b| a.from.cmp(&b.from).then(a.loc.cmp(&b.loc)).then(a.to.cmp(&b.to)));
+        edges
+    }
+
+    /// Return all the events, `on_actions`, and scripted effects, and all other items that make
+    /// calls, with the locations of their definitions if known.
+    /// Nodes that are called but were never validated, such as missing events, have no location.
+    #[allow(clippy::missing_panics_doc)] // only panics on poisoned mutex
+    pub fn nodes(&self) -> BTreeMap<CallNode, Option<Loc>> {
+        let inner = self.inner.lock().unwrap();
+        let mut nodes: BTreeMap<_, _> = inner
+            .defined
+            .iter()
+            .filter(|(node, _)| {
+                matches!(
+                    node,
+                    CallNode::Item(Item::Event | Item::OnAction | Item::ScriptedEffect, _)
+                )
+            })
+            .map(|(node, loc)| (*node, Some(*loc)))
+            .collect();
+        for edge in inner.edges.values() {
+            for node in [edge.from, edge.to] {
+                nodes.entry(node).or_insert_with(|| inner.defined.get(&node).copied());
+            }
+        }
+        nodes
+    }
+
+    /// Return the items checked by `--unused` that can be reached from the roots of the script.
+    ///
+    /// The roots are files and all items that are not themselves checked by `--unused`, such as
+    /// `on_actions`, decisions, and interactions. An item that is only used by unreachable items,
+    /// such as an event that is only fired by an event that nothing fires, is not reachable.
+    #[allow(clippy::missing_panics_doc)] // only panics on poisoned mutex
+    pub fn reachable_items(&self) -> TigerHashSet<(Item, String)> {
+        let inner = self.inner.lock().unwrap();
+        let mut used_by: TigerHashMap<(Item, &str), Vec<(Item, &str)>> = TigerHashMap::default();
+        let mut queue = Vec::new();
+        let uses = inner.uses.iter().flat_map(|(from, targets)| {
+            targets.iter().map(move |(itype, key)| (*from, (*itype, key.as_str())))
+        });
+        let calls = inner.edges.values().filter_map(|edge| match edge.to {
+            CallNode::Item(itype, key) => Some((edge.from, (itype, key))),
+            CallNode::File(_) => None,
+        });
+        for (from, to) in uses.chain(calls) {
+            match from {
+                CallNode::Item(itype, key) if itype.tracks_usage() => {
+                    used_by.entry((itype, key)).or_default().push(to);
+                }
+                _ => queue.push(to),
+            }
+        }
+        let mut reachable = TigerHashSet::default();
+        while let Some(item) = queue.pop() {
+            if reachable.insert(item) {
+                if let Some(next) = used_by.get(&item) {
+                    queue.extend(next);
+                }
+            }
+        }
+        reachable.into_iter().map(|(itype, key)| (itype, key.to_owned())).collect()
+    }
+
+    /// Return the defined events that can't be reached from the roots of the script.
+    /// See [`CallGraph::reachable_items`].
+    ///
+    /// Events can also be fired by the game engine or from interface files, which are not
+    /// recorded, so these are candidates for review rather than certain mistakes.
+    #[allow(clippy::missing_panics_doc)] // only panics on poisoned mutex
+    pub fn unreachable_events(&self) -> Vec<CallNode> {
+        let reachable = self.reachable_items();
+        let inner = self.inner.lock().unwrap();
+        let mut events: Vec<_> = inner
+            .defined
+            .keys()
+            .filter(|node| match node {
+                CallNode::Item(Item::Event, key) => {
+                    !reachable.contains(&(Item::Event, (*key).to_owned()))
+                }
+                _ => false,
+            })
+            .copied()
+            .collect();
+        events.sort();
+        events
+    }
+
+    /// Write the call graph in the DOT language of Graphviz.
+    pub fn write_dot<O: Write>(&self, output: &mut O) -> Result<()> {
+        let unreachable: TigerHashSet<_> = self.unreachable_events().into_iter().collect();
+        writeln!(output, "digraph calls {{")?;
+        for (node, loc) in self.nodes() {
+            let mut label = node.key();
+            if let Some(loc) = loc {
+                let _ = write!(label, "\n{}:{}", loc.pathname().display(), loc.line);
+            }
+            let shape = match node {
+                CallNode::Item(Item::Event, _) => "box",
+                CallNode::Item(Item::OnAction, _) => "ellipse",
+                CallNode::Item(Item::ScriptedEffect, _) => "hexagon",
+                CallNode::Item(..) => "octagon",
+                CallNode::File(_) => "note",
+            };
+            let style = if loc.is_none() && matches!(node, CallNode::Item(..)) {
+                ", style=dashed"
+            } else if unreachable.contains(&node) {
+                ", color=red"
+            } else {
+                ""
+            };
+            let (id, label) = (dot_quote(&node.id()), dot_quote(&label));
+            writeln!(output, "  {id} [label={label}, shape={shape}{style}];")?;
+        }
+        for edge in self.edges() {
+            let mut label = edge.context.clone();
+            if let Some(delay) = &edge.delay {
+                let _ = write!(label, "\n{delay}");
+            }
+            let (from, to) = (dot_quote(&edge.from.id()), dot_quote(&edge.to.id()));
+            writeln!(output, "  {from} -> {to} [label={}];", dot_quote(&label))?;
+        }
+        writeln!(output, "}}")?;
+        Ok(())
+    }
+
+    /// Write the call graph as a JSON object with `nodes` and `edges` arrays.
+    pub fn write_json<O: Write>(&self, output: &mut O) -> Result<()> {
+        let unreachable: TigerHashSet<_> = self.unreachable_events().into_iter().collect();
+        let nodes: Vec<Value> = self
+            .nodes()
+            .into_iter()
+            .map(|(node, loc)| {
+                let mut value = json!({
+                    "id": node.id(),
+                    "type": node.kind(),
+                    "key": node.key(),
+                    "file": loc.map(|loc| loc.pathname().to_string_lossy().into_owned()),
+                    "line": loc.map(|loc| loc.line),
+                });
+                if matches!(node, CallNode::Item(Item::Event, _)) {
+                    value["unreachable"] = json!(unreachable.contains(&node));
+                }
+                value
+            })
+            .collect();
+        let edges: Vec<Value> = self
+            .edges()
+            .into_iter()
+            .map(|edge| {
+                json!({
+                    "from": edge.from.id(),
+                    "to": edge.to.id(),
+                    "context": edge.context,
+                    "delay": edge.delay,
+                    "file": edge.loc.pathname().to_string_lossy(),
+                    "line": edge.loc.line,
+                    "column": edge.loc.column,
+                })
+            })
+            .collect();
+        serde_json::to_writer_pretty(&mut *output, &json!({ "nodes": nodes, "edges": edges }))?;
+        writeln!(output)?;
+        Ok(())
+    }
+}
+
+/// Describe the delay given by the `days`, `weeks`, `months`, and `years` fields in `block`.
+pub(crate) fn describe_delay(block: &Block) -> Option<String> {
+    let mut parts = Vec::new();
+    for unit in ["days", "weeks", "months", "years"] {
+        match block.get_field(unit) {
+            Some(BV::Value(token)) => parts.push(format!("{token} {unit}")),
+            Some(BV::Block(block)) => {
+                let values: Vec<_> = block.iter_values().map(Token::as_str).collect();
+                if let [min, max] = values[..] {
+                    parts.push(format!("{min}-{max} {unit}"));
+                } else {
+                    parts.push(format!("scripted {unit}"));
+                }
+            }
+            None => (),
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Calls made in macro expansions are recorded once, at the location in the macro's definition.
+fn strip_link(mut loc: Loc) -> Loc {
+    loc.link_idx = None;
+    loc
+}
+
+fn dot_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
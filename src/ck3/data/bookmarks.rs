@@ -70,6 +70,9 @@ impl DbKind for Bookmark {
         data.verify_exists_implied(Item::File, &pathname, key);
 
         let start_date = block.get_field_date("start_date");
+        if let (Some(date), Some(token)) = (start_date, block.get_field_value("start_date")) {
+            data.title_history.timeline().verify_bookmark_date(key, token, date, data);
+        }
         vd.multi_field_validated_block("character", |block, data| {
             if let Some(name) = block.get_field_value("name") {
                 let pathname = format!("gfx/interface/bookmarks/{key}_{name}.dds");
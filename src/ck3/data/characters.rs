@@ -132,6 +132,10 @@ impl Characters {
         }
     }
 
+    pub fn death_date(&self, item: &Token) -> Option<Date> {
+        self.characters.get(item.as_str()).and_then(Character::death_date)
+    }
+
     pub fn verify_alive(&self, item: &Token, date: Date) {
         if !self.is_alive(item, date) {
             let msg = format!("{item} is not alive on {date}");
@@ -317,6 +321,15 @@ impl Character {
             && self.block.get_field_at_date("death", date).is_none()
     }
 
+    /// Return the date of the first history entry that kills this character.
+    pub fn death_date(&self) -> Option<Date> {
+        self.block
+            .iter_definitions()
+            .filter(|(_, block)| block.has_key("death"))
+            .filter_map(|(key, _)| Date::try_from(key).ok())
+            .min()
+    }
+
     pub fn get_dynasty(&self, date: Date) -> Option<&Token> {
         self.block.get_field_value_at_date("dynasty", date)
     }
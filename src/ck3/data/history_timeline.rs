@@ -0,0 +1,259 @@
+//! Replays the title and character histories in date order, to find problems that only show up
+//! when looking at the history as a whole rather than one dated block at a time.
+
+use crate::block::Block;
+use crate::ck3::data::titles::Tier;
+use crate::date::Date;
+use crate::everything::Everything;
+use crate::helpers::{TigerHashMap, TigerHashSet};
+use crate::report::{err, warn, ErrorKey};
+use crate::token::Token;
+
+/// One change of a title's holder or liege. A `0` token means the holder or liege was removed.
+#[derive(Clone, Debug)]
+struct Change {
+    date: Date,
+    token: Token,
+}
+
+impl Change {
+    fn value(&self) -> Option<&Token> {
+        (!self.token.is("0")).then_some(&self.token)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TitleTimeline {
+    key: Token,
+    tier: Tier,
+    /// Holder changes, sorted by date.
+    holders: Vec<Change>,
+    /// Liege changes, sorted by date.
+    lieges: Vec<Change>,
+}
+
+impl TitleTimeline {
+    fn new(key: Token, tier: Tier, block: &Block) -> Self {
+        let mut holders = Vec::new();
+        let mut lieges = Vec::new();
+        for (date_token, block) in block.iter_definitions() {
+            if let Ok(date) = Date::try_from(date_token) {
+                for (field, token) in block.iter_assignments() {
+                    if field.is("holder") || field.is("holder_ignore_head_of_faith_requirement") {
+                        holders.push(Change { date, token: token.clone() });
+                    } else if field.is("liege") {
+                        lieges.push(Change { date, token: token.clone() });
+                    }
+                }
+            }
+        }
+        // The sort is stable, so later entries for the same date still override earlier ones.
+        holders.sort_by_key(|change| change.date);
+        lieges.sort_by_key(|change| change.date);
+        Self { key, tier, holders, lieges }
+    }
+
+    /// Return the index of the change that is in effect on `date`.
+    fn index_at(changes: &[Change], date: Date) -> Option<usize> {
+        changes.partition_point(|change| change.date <= date).checked_sub(1)
+    }
+
+    fn at(changes: &[Change], date: Date) -> Option<&Token> {
+        Self::index_at(changes, date).and_then(|idx| changes[idx].value())
+    }
+
+    fn holder_at(&self, date: Date) -> Option<&Token> {
+        Self::at(&self.holders, date)
+    }
+
+    fn liege_at(&self, date: Date) -> Option<&Token> {
+        Self::at(&self.lieges, date)
+    }
+
+    /// Warn about holders who die while the history still has them holding the title.
+    fn validate_tenures(&self, data: &Everything) {
+        for (i, change) in self.holders.iter().enumerate() {
+            let Some(holder) = change.value() else { continue };
+            let Some(next) = self.holders.get(i + 1) else { continue };
+            if let Some(death) = data.characters.death_date(holder) {
+                // Deaths before the assignment are reported by the title history itself.
+                if change.date <= death && death < next.date {
+                    let msg = format!(
+                        "{holder} holds {} until {}, but dies on {death}",
+                        self.key, next.date
+                    );
+                    let info = "the title will pass by succession when the holder dies, and then the history will override that";
+                    warn(ErrorKey::History)
+                        .weak()
+                        .msg(msg)
+                        .info(info)
+                        .loc(holder)
+                        .loc_msg(&next.token, "next holder")
+                        .push();
+                }
+            }
+        }
+    }
+}
+
+/// The holder and liege changes of all titles, in date order, with an index from characters to
+/// the titles they hold at some point.
+#[derive(Clone, Debug, Default)]
+pub struct HistoryTimeline {
+    titles: TigerHashMap<&'static str, TitleTimeline>,
+    held: TigerHashMap<&'static str, Vec<&'static str>>,
+}
+
+impl HistoryTimeline {
+    pub(crate) fn new<'a>(histories: impl Iterator<Item = (&'a Token, Tier, &'a Block)>) -> Self {
+        let mut titles = TigerHashMap::default();
+        let mut held: TigerHashMap<&'static str, Vec<&'static str>> = TigerHashMap::default();
+        for (key, tier, block) in histories {
+            let timeline = TitleTimeline::new(key.clone(), tier, block);
+            for change in &timeline.holders {
+                if let Some(holder) = change.value() {
+                    let titles = held.entry(holder.as_str()).or_default();
+                    if !titles.contains(&key.as_str()) {
+                        titles.push(key.as_str());
+                    }
+                }
+            }
+            titles.insert(key.as_str(), timeline);
+        }
+        Self { titles, held }
+    }
+
+    /// Return the character holding `title` on `date`, according to the title history.
+    pub fn holder_at(&self, title: &str, date: Date) -> Option<&Token> {
+        self.titles.get(title).and_then(|timeline| timeline.holder_at(date))
+    }
+
+    /// Return the liege title of `title` on `date`, according to the title history.
+    pub fn liege_at(&self, title: &str, date: Date) -> Option<&Token> {
+        self.titles.get(title).and_then(|timeline| timeline.liege_at(date))
+    }
+
+    /// Return the highest tier among the titles held by `character` on `date`.
+    pub fn highest_tier(&self, character: &str, date: Date) -> Option<Tier> {
+        self.held
+            .get(character)?
+            .iter()
+            .filter(|title| self.holder_at(title, date).is_some_and(|h| h.is(character)))
+            .filter_map(|title| self.titles.get(title).map(|timeline| timeline.tier))
+            .max()
+    }
+
+    pub fn validate(&self, data: &Everything) {
+        let mut loops = TigerHashSet::default();
+        // Go in key order, so that a liege loop is always reported from the same member.
+        let mut timelines: Vec<_> = self.titles.values().collect();
+        timelines.sort_unstable_by_key(|timeline| timeline.key.as_str());
+        for timeline in timelines {
+            timeline.validate_tenures(data);
+            for change in &timeline.lieges {
+                if let Some(liege) = change.value() {
+                    self.validate_liege_loop(timeline, liege, change.date, &mut loops);
+                    self.validate_vassal_tier(timeline, liege, change.date);
+                }
+            }
+        }
+    }
+
+    fn validate_liege_loop(
+        &self,
+        timeline: &TitleTimeline,
+        liege: &Token,
+        date: Date,
+        loops: &mut TigerHashSet<Vec<&'static str>>,
+    ) {
+        let mut chain = vec![timeline.key.as_str()];
+        let mut current = Some(liege);
+        while let Some(title) = current {
+            if title.is(timeline.key.as_str()) {
+                let mut members = chain.clone();
+                members.sort_unstable();
+                if loops.insert(members) {
+                    chain.push(title.as_str());
+                    let msg = format!("liege loop on {date}: {}", chain.join(" -> "));
+                    err(ErrorKey::History).msg(msg).loc(liege).push();
+                }
+                return;
+            }
+            if chain.contains(&title.as_str()) {
+                // A loop that doesn't include this title. It gets reported from one of its members.
+                return;
+            }
+            chain.push(title.as_str());
+            current = self.liege_at(title.as_str(), date);
+        }
+    }
+
+    fn validate_vassal_tier(&self, timeline: &TitleTimeline, liege: &Token, date: Date) {
+        let Some(vassal_holder) = timeline.holder_at(date) else { return };
+        let Some(liege_holder) = self.holder_at(liege.as_str(), date) else { return };
+        if vassal_holder == liege_holder {
+            return;
+        }
+        let vassal_tier = self.highest_tier(vassal_holder.as_str(), date);
+        let liege_tier = self.highest_tier(liege_holder.as_str(), date);
+        if let (Some(vassal_tier), Some(liege_tier)) = (vassal_tier, liege_tier) {
+            if vassal_tier > liege_tier {
+                let msg = format!(
+                    "on {date}, {vassal_holder} (highest tier {vassal_tier}) is a vassal of {liege_holder} (highest tier {liege_tier})"
+                );
+                warn(ErrorKey::History).msg(msg).loc(liege).push();
+            }
+        }
+    }
+
+    /// Check that on the start date of `bookmark`, every held title has a living holder and every
+    /// liege title that is set has a holder.
+    pub fn verify_bookmark_date(
+        &self,
+        bookmark: &Token,
+        start_date: &Token,
+        date: Date,
+        data: &Everything,
+    ) {
+        for timeline in self.titles.values() {
+            let Some(idx) = TitleTimeline::index_at(&timeline.holders, date) else { continue };
+            let change = &timeline.holders[idx];
+            let Some(holder) = change.value() else { continue };
+            if !data.characters.is_alive(holder, date) {
+                // Holders who aren't alive when they are assigned are reported by the title
+                // history itself, and deaths before the next holder is assigned are reported by
+                // validate_tenures.
+                let has_next = idx + 1 < timeline.holders.len();
+                let reported = !data.characters.is_alive(holder, change.date)
+                    || (has_next
+                        && data.characters.death_date(holder).is_some_and(|death| death <= date));
+                if !reported {
+                    let msg = format!(
+                        "holder {holder} of {} is not alive on the start date of bookmark {bookmark}",
+                        timeline.key
+                    );
+                    warn(ErrorKey::Bookmarks)
+                        .msg(msg)
+                        .loc(start_date)
+                        .loc_msg(holder, "holder")
+                        .push();
+                }
+            }
+            if let Some(liege) = timeline.liege_at(date) {
+                if self.titles.contains_key(liege.as_str())
+                    && self.holder_at(liege.as_str(), date).is_none()
+                {
+                    let msg = format!(
+                        "liege {liege} of {} has no holder on the start date of bookmark {bookmark}",
+                        timeline.key
+                    );
+                    warn(ErrorKey::Bookmarks)
+                        .msg(msg)
+                        .loc(start_date)
+                        .loc_msg(liege, "liege")
+                        .push();
+                }
+            }
+        }
+    }
+}
@@ -37,6 +37,7 @@ pub mod focus;
 pub mod gameconcepts;
 pub mod gamerules;
 pub mod government;
+pub mod history_timeline;
 pub mod holdings;
 pub mod holysites;
 pub mod hooks;
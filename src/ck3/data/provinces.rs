@@ -16,6 +16,7 @@ use crate::item::{Item, ItemLoader, LoadAsFile, Recursive};
 use crate::parse::csv::{parse_csv, read_csv};
 use crate::parse::ParserMemory;
 use crate::pdxfile::{PdxEncoding, PdxFile};
+use crate::province_map::ProvinceMap;
 use crate::report::{err, fatal, report, untidy, warn, ErrorKey, Severity};
 use crate::token::{Loc, Token};
 use crate::validator::Validator;
@@ -80,6 +81,15 @@ pub struct Ck3Provinces {
     impassable: TigerHashSet<ProvId>,
 
     sea_or_river: TigerHashSet<ProvId>,
+
+    /// Kept for looking up the names of the other map files.
+    default_map: Option<Block>,
+
+    /// Sea zones and lakes, which should be below the water level on the heightmap.
+    water: TigerHashSet<ProvId>,
+
+    /// Which provinces border each other in provinces.png.
+    map: Option<ProvinceMap>,
 }
 
 impl Ck3Provinces {
@@ -147,6 +157,9 @@ impl Ck3Provinces {
                             if key.is("sea_zones") || key.is("river_provinces") {
                                 self.sea_or_river.insert(provid);
                             }
+                            if key.is("sea_zones") || key.is("impassable_seas") || key.is("lakes") {
+                                self.water.insert(provid);
+                            }
                         }
                     }
                     expecting = Expecting::Nothing;
@@ -160,6 +173,12 @@ impl Ck3Provinces {
                                 if key.is("sea_zones") || key.is("river_provinces") {
                                     self.sea_or_river.insert(provid);
                                 }
+                                if key.is("sea_zones")
+                                    || key.is("impassable_seas")
+                                    || key.is("lakes")
+                                {
+                                    self.water.insert(provid);
+                                }
                             } else {
                                 err(ErrorKey::Validation)
                                     .msg("invalid LIST item")
@@ -207,6 +226,18 @@ impl Ck3Provinces {
         self.sea_or_river.contains(&provid)
     }
 
+    pub(crate) fn default_map(&self) -> Option<&Block> {
+        self.default_map.as_ref()
+    }
+
+    /// Return the ids and colors of the sea zones and lakes.
+    pub(crate) fn water_colors(&self) -> Vec<(ProvId, Rgb<u8>)> {
+        self.water
+            .iter()
+            .filter_map(|id| self.provinces.get(id).map(|province| (*id, province.color)))
+            .collect()
+    }
+
     pub fn iter_keys(&self) -> impl Iterator<Item = &Token> {
         self.provinces.values().map(|item| &item.key)
     }
@@ -329,9 +360,21 @@ impl FileHandler<FileContent> for Ck3Provinces {
                                 .commit(true);
                         }
                     }
+                    // adjacencies.csv sorts before provinces.png, so the adjacencies are known
+                    // here and only the colors at their coordinates need to be kept.
+                    let points = self
+                        .adjacencies
+                        .iter()
+                        .flat_map(|item| [item.start, item.stop])
+                        .map(|Coords { x, y }| (x, y))
+                        .collect::<Vec<_>>();
+                    self.map = Some(ProvinceMap::analyze(&img, entry, &points));
                 }
             }
-            FileContent::DefaultMap(block) => self.load_impassable(&block),
+            FileContent::DefaultMap(block) => {
+                self.load_impassable(&block);
+                self.default_map = Some(block);
+            }
         }
     }
 
@@ -370,10 +413,15 @@ impl FileHandler<FileContent> for Ck3Provinces {
                 untidy(ErrorKey::Colors).msg(msg).loc(definition_csv).push();
             }
         }
+
+        if let Some(map) = &self.map {
+            for item in &self.adjacencies {
+                item.validate_coords(&self.provinces, map);
+            }
+        }
     }
 }
 
-#[allow(dead_code)] // TODO
 #[derive(Copy, Clone, Debug, Default)]
 pub struct Coords {
     x: i32,
@@ -390,7 +438,7 @@ pub struct Adjacency {
     /// sea or `river_large`
     kind: Token,
     through: ProvId,
-    /// TODO: check start and stop are map coordinates and have the right color on province.png
+    /// Map coordinates of the start and stop of the crossing, with y counted from the bottom.
     /// They can be -1 -1 though.
     start: Coords,
     stop: Coords,
@@ -446,7 +494,62 @@ impl Adjacency {
                 fatal(ErrorKey::Crash).msg(msg).loc(self.line).push();
             }
         }
+        if let Some(map) = &provinces.map {
+            let Some(through) = color_of(&provinces.provinces, map, self.through) else {
+                return;
+            };
+            for prov in [self.from, self.to] {
+                if prov == self.through {
+                    continue;
+                }
+                if let Some(color) = color_of(&provinces.provinces, map, prov) {
+                    if !map.is_bordering(color, through) {
+                        let msg = format!(
+                            "province {prov} does not border province {} that this adjacency goes through",
+                            self.through
+                        );
+                        warn(ErrorKey::Validation).msg(msg).loc(self.line).push();
+                    }
+                }
+            }
+        }
     }
+
+    /// Check that the start and stop coordinates are at the provinces they belong to.
+    fn validate_coords(&self, provinces: &TigerHashMap<ProvId, Province>, map: &ProvinceMap) {
+        for (coords, prov, which) in
+            [(self.start, self.from, "start"), (self.stop, self.to, "stop")]
+        {
+            if coords.x == -1 && coords.y == -1 {
+                continue;
+            }
+            let Some(color) = color_of(provinces, map, prov) else {
+                continue;
+            };
+            let Coords { x, y } = coords;
+            if map.is_on_map(x, y) {
+                if map.colors_near(x, y).is_some_and(|colors| !colors.contains(&color)) {
+                    let msg = format!(
+                        "the {which} point ({x}, {y}) is not in or next to province {prov}"
+                    );
+                    let info = "coordinates are in pixels on provinces.png, with y counted from the bottom";
+                    warn(ErrorKey::Validation).weak().msg(msg).info(info).loc(self.line).push();
+                }
+            } else {
+                let msg = format!("the {which} point ({x}, {y}) is outside the map");
+                warn(ErrorKey::Validation).msg(msg).loc(self.line).push();
+            }
+        }
+    }
+}
+
+/// Return the color of a province, if it is defined and appears on the map.
+fn color_of(
+    provinces: &TigerHashMap<ProvId, Province>,
+    map: &ProvinceMap,
+    prov: ProvId,
+) -> Option<Rgb<u8>> {
+    provinces.get(&prov).map(|province| province.color).filter(|&color| map.contains(color))
 }
 
 #[derive(Clone, Debug)]
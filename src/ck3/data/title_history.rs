@@ -1,6 +1,8 @@
 use std::path::PathBuf;
+use std::sync::OnceLock;
 
 use crate::block::Block;
+use crate::ck3::data::history_timeline::HistoryTimeline;
 use crate::ck3::data::titles::Tier;
 use crate::date::Date;
 use crate::everything::Everything;
@@ -19,6 +21,8 @@ use crate::variables::Variables;
 #[derive(Clone, Debug, Default)]
 pub struct TitleHistories {
     histories: TigerHashMap<&'static str, TitleHistory>,
+    /// Built on first use, after all the histories are loaded.
+    timeline: OnceLock<HistoryTimeline>,
 }
 
 impl TitleHistories {
@@ -52,10 +56,19 @@ impl TitleHistories {
         self.histories.values().map(|item| &item.key)
     }
 
+    pub fn timeline(&self) -> &HistoryTimeline {
+        self.timeline.get_or_init(|| {
+            HistoryTimeline::new(
+                self.histories.values().map(|item| (&item.key, item.tier, &item.block)),
+            )
+        })
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.histories.values() {
             item.validate(data);
         }
+        self.timeline().validate(data);
     }
 
     pub fn verify_has_holder(&self, key: &Token, date: Date, data: &Everything) {
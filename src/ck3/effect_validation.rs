@@ -558,8 +558,8 @@ pub fn validate_create_character(
     _tooltipped: Tooltipped,
 ) {
     // docs say save_event_target instead of save_scope
-    vd.replaced_field("save_event_target_as", "save_scope_as");
-    vd.replaced_field("save_temporary_event_target_as", "save_temporary_scope_as");
+    vd.renamed_field("save_event_target_as", "save_scope_as");
+    vd.renamed_field("save_temporary_event_target_as", "save_temporary_scope_as");
     if let Some(name) = vd.field_identifier("save_scope_as", "scope name") {
         sc.define_name_token(name.as_str(), Scopes::Character, name);
     }
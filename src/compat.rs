@@ -0,0 +1,232 @@
+//! Analysis of how the mods in a playset override each other.
+//!
+//! The files that mods override in each other are tracked by the
+//! [`Fileset`](crate::fileset::Fileset). Items and localization keys that are defined by more
+//! than one mod are recorded while loading, at the places where duplicate definitions are checked,
+//! by the collection that holds them.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use crate::fileset::{FileKind, FileOverride, LoadedMod};
+use crate::item::Item;
+use crate::token::Token;
+
+/// An item or localization key that is defined by two mods, of which only one definition is used.
+#[derive(Clone, Debug)]
+pub struct ItemOverride {
+    pub itype: Item,
+    /// The definition that is used.
+    pub winner: Token,
+    /// The definition that is ignored.
+    pub loser: Token,
+}
+
+/// The overrides between mods found while loading one collection of items.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Overrides(Vec<ItemOverride>);
+
+impl Overrides {
+    /// Record that the definition `loser` of an item of type `itype` is overridden by `winner`.
+    /// Nothing is recorded unless they are from two different mods.
+    pub(crate) fn record(&mut self, itype: Item, loser: &Token, winner: &Token) {
+        let (loser_kind, winner_kind) = (loser.loc.kind, winner.loc.kind);
+        if loser_kind.is_mod() && winner_kind.is_mod() && loser_kind != winner_kind {
+            let winner = winner.clone();
+            let loser = loser.clone();
+            self.0.push(ItemOverride { itype, winner, loser });
+        }
+    }
+}
+
+/// The ways in which two mods in a playset conflict with each other.
+#[derive(Clone, Debug, Default)]
+pub struct ModConflicts {
+    /// Files of the earlier mod that are not loaded because the later mod has the same file.
+    pub files: Vec<PathBuf>,
+    /// Directories of the earlier mod that are not loaded because the later mod has them as
+    /// `replace_path`, with the number of files that are not loaded.
+    pub replaced_dirs: Vec<(PathBuf, usize)>,
+    /// Items defined in both mods.
+    pub items: Vec<ItemOverride>,
+    /// Localization keys defined in both mods.
+    pub localization: Vec<ItemOverride>,
+}
+
+/// The conflicts between each pair of mods in the playset, meaning the secondary mods loaded
+/// from the config or from a launcher playset together with the mod under scrutiny.
+#[derive(Clone, Debug, Default)]
+pub struct CompatReport {
+    /// The mods in load order, with their labels and root directories.
+    mods: Vec<(FileKind, String, PathBuf)>,
+    /// The conflicts, keyed by the earlier and the later mod in load order.
+    pairs: BTreeMap<(FileKind, FileKind), ModConflicts>,
+}
+
+impl CompatReport {
+    pub(crate) fn new<'a>(mods: impl Iterator<Item = &'a LoadedMod>) -> Self {
+        let mods = mods
+            .map(|loaded_mod| {
+                (loaded_mod.kind(), loaded_mod.label().to_owned(), loaded_mod.root().to_owned())
+            })
+            .collect();
+        Self { mods, pairs: BTreeMap::new() }
+    }
+
+    fn pair(&mut self, a: FileKind, b: FileKind) -> &mut ModConflicts {
+        self.pairs.entry((a.min(b), a.max(b))).or_default()
+    }
+
+    pub(crate) fn add_overridden_files(&mut self, overridden: &[FileOverride]) {
+        for file in overridden {
+            // Files in the mods' root directories, such as `descriptor.mod`, are not game files.
+            if file.path.parent().is_some_and(|dir| dir.as_os_str().is_empty()) {
+                continue;
+            }
+            self.pair(file.loser, file.winner).files.push(file.path.clone());
+        }
+    }
+
+    pub(crate) fn add_replaced_files(&mut self, replaced: &[FileOverride]) {
+        let mut dirs: BTreeMap<(FileKind, FileKind, &Path), usize> = BTreeMap::new();
+        for file in replaced {
+            let dir = file.path.parent().unwrap_or_else(|| Path::new(""));
+            *dirs.entry((file.loser, file.winner, dir)).or_default() += 1;
+        }
+        for ((loser, winner, dir), count) in dirs {
+            self.pair(loser, winner).replaced_dirs.push((dir.to_owned(), count));
+        }
+    }
+
+    /// Add the item and localization overrides that were recorded while loading.
+    pub(crate) fn add_overrides<'a>(&mut self, recorded: impl IntoIterator<Item = &'a Overrides>) {
+        let mut overrides: Vec<_> =
+            recorded.into_iter().flat_map(|o| o.0.iter().cloned()).collect();
+        overrides
+            .sort_by(|a, b| a.itype.cmp(&b.itype).then(a.winner.as_str().cmp(b.winner.as_str())));
+        for o in overrides {
+            let pair = self.pair(o.loser.loc.kind, o.winner.loc.kind);
+            if o.itype == Item::Localization {
+                pair.localization.push(o);
+            } else {
+                pair.items.push(o);
+            }
+        }
+    }
+
+    /// Return true iff no conflicts were found.
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Iterate over the pairs of mods that conflict, as (earlier mod, later mod, conflicts).
+    pub fn iter(&self) -> impl Iterator<Item = (FileKind, FileKind, &ModConflicts)> {
+        self.pairs.iter().map(|((a, b), conflicts)| (*a, *b, conflicts))
+    }
+
+    fn label(&self, kind: FileKind) -> &str {
+        self.mods.iter().find(|(k, _, _)| *k == kind).map_or("?", |(_, label, _)| label.as_str())
+    }
+
+    /// Write the conflicts as human-readable text.
+    pub fn write_text<O: Write>(&self, output: &mut O) -> Result<()> {
+        writeln!(output, "Playset, in load order:")?;
+        for (_, label, root) in &self.mods {
+            writeln!(output, "  {label}: {}", root.display())?;
+        }
+        if self.is_empty() {
+            writeln!(output, "\nNo conflicts found between the mods.")?;
+            return Ok(());
+        }
+        for (a, b, conflicts) in self.iter() {
+            let (a, b) = (self.label(a), self.label(b));
+            writeln!(output, "\n== {a} and {b} ==")?;
+            if !conflicts.files.is_empty() {
+                writeln!(output, "Files of {a} overridden by {b}:")?;
+                for path in &conflicts.files {
+                    writeln!(output, "  {}", path.display())?;
+                }
+            }
+            if !conflicts.replaced_dirs.is_empty() {
+                writeln!(output, "Directories of {a} replaced by {b} with replace_path:")?;
+                for (dir, count) in &conflicts.replaced_dirs {
+                    writeln!(output, "  {} (files not loaded: {count})", dir.display())?;
+                }
+            }
+            for (what, overrides) in
+                [("Items", &conflicts.items), ("Localization keys", &conflicts.localization)]
+            {
+                if overrides.is_empty() {
+                    continue;
+                }
+                writeln!(output, "{what} defined in both mods:")?;
+                for o in overrides {
+                    let winner = self.label(o.winner.loc.kind);
+                    writeln!(output, "  {} `{}`: {winner} wins", o.itype, o.winner)?;
+                    for token in [&o.winner, &o.loser] {
+                        let label = self.label(token.loc.kind);
+                        let path = token.loc.pathname().display();
+                        writeln!(output, "    {label}: {path}:{}", token.loc.line)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the conflicts as a JSON object with `mods` and `conflicts` arrays.
+    pub fn write_json<O: Write>(&self, output: &mut O) -> Result<()> {
+        let mods: Vec<Value> = self
+            .mods
+            .iter()
+            .map(|(_, label, root)| json!({ "label": label, "root": root.to_string_lossy() }))
+            .collect();
+        let location = |token: &Token| {
+            json!({
+                "mod": self.label(token.loc.kind),
+                "file": token.loc.pathname().to_string_lossy(),
+                "line": token.loc.line,
+            })
+        };
+        let overrides = |overrides: &[ItemOverride]| -> Vec<Value> {
+            overrides
+                .iter()
+                .map(|o| {
+                    json!({
+                        "type": <&str>::from(o.itype),
+                        "key": o.winner.as_str(),
+                        "winner": location(&o.winner),
+                        "loser": location(&o.loser),
+                    })
+                })
+                .collect()
+        };
+        let conflicts: Vec<Value> = self
+            .iter()
+            .map(|(a, b, conflicts)| {
+                json!({
+                    "earlier": self.label(a),
+                    "later": self.label(b),
+                    "files": conflicts.files,
+                    "replaced_dirs": conflicts
+                        .replaced_dirs
+                        .iter()
+                        .map(|(dir, count)| json!({ "path": dir, "files": count }))
+                        .collect::<Vec<_>>(),
+                    "items": overrides(&conflicts.items),
+                    "localization": overrides(&conflicts.localization),
+                })
+            })
+            .collect();
+        serde_json::to_writer_pretty(
+            &mut *output,
+            &json!({ "mods": mods, "conflicts": conflicts }),
+        )?;
+        writeln!(output)?;
+        Ok(())
+    }
+}
@@ -8,11 +8,13 @@ use std::path::PathBuf;
 use strum::IntoEnumIterator;
 
 use crate::block::{Block, BlockItem, Comparator, Eq::*, Field, BV};
-use crate::helpers::stringify_list;
+use crate::helpers::{stringify_list, TigerHashMap};
+use crate::item::Item;
 use crate::report::{
-    err, set_predicate, set_show_loaded_mods, set_show_vanilla, Confidence, ErrorKey, ErrorLoc,
-    FilterRule, PointedMessage, Severity,
+    err, set_predicate, set_severity_overrides, set_show_loaded_mods, set_show_vanilla, Confidence,
+    ErrorKey, ErrorLoc, FileSource, FilterRule, PointedMessage, Severity,
 };
+use crate::token::Token;
 
 /// Checks for legacy ignore blocks (that no longer work) and report an error if they are present.
 pub fn check_for_legacy_ignore(config: &Block) {
@@ -76,6 +78,99 @@ pub fn load_filter(config: &Block) {
     }
 }
 
+/// Load the `severity_override` block, which gives the reports of certain keys a different
+/// severity and optionally a different confidence. For example:
+/// ```text
+/// severity_override = {
+///     missing-localization = warning
+///     brace-placement = { severity = tips confidence = weak }
+/// }
+/// ```
+pub fn load_severity_overrides(config: &Block) {
+    assert_one_key("severity_override", config);
+    let mut overrides = TigerHashMap::default();
+    if let Some(block) = config.get_field_block("severity_override") {
+        for (key, bv) in block.iter_assignments_and_definitions_warn() {
+            let Ok(error_key) = key.as_str().parse::<ErrorKey>() else {
+                err(ErrorKey::Config)
+                    .msg("Invalid key. In the output, keys are listed between parentheses on the first line of each report. For example, in `Warning(missing-item)`, the key is `missing-item`.")
+                    .loc(key)
+                    .push();
+                continue;
+            };
+            if error_key == ErrorKey::Config {
+                err(ErrorKey::Config)
+                    .msg("the severity of `config` reports can't be changed")
+                    .loc(key)
+                    .push();
+                continue;
+            }
+            let entry = match bv {
+                BV::Value(token) => load_override_severity(token).map(|s| (Some(s), None)),
+                BV::Block(block) => {
+                    assert_one_key("severity", block);
+                    assert_one_key("confidence", block);
+                    for (key, _) in block.iter_assignments_and_definitions_warn() {
+                        if !key.is("severity") && !key.is("confidence") {
+                            err(ErrorKey::Config)
+                                .msg(format!("unknown field `{key}`"))
+                                .info("expected `severity` or `confidence`")
+                                .loc(key)
+                                .push();
+                        }
+                    }
+                    let severity = block.get_field_value("severity").map(load_override_severity);
+                    let confidence =
+                        block.get_field_value("confidence").map(load_override_confidence);
+                    match (severity, confidence) {
+                        (Some(None), _) | (_, Some(None)) => None,
+                        (None, None) => {
+                            err(ErrorKey::Config)
+                                .msg("expected `severity` or `confidence` here")
+                                .loc(block)
+                                .push();
+                            None
+                        }
+                        (severity, confidence) => Some((severity.flatten(), confidence.flatten())),
+                    }
+                }
+            };
+            if let Some(entry) = entry {
+                overrides.insert(error_key, entry);
+            }
+        }
+    }
+    set_severity_overrides(overrides);
+}
+
+fn load_override_severity(token: &Token) -> Option<Severity> {
+    let severity = token.as_str().to_ascii_lowercase().parse().ok();
+    if severity.is_none() {
+        err(ErrorKey::Config)
+            .msg(format!(
+                "Invalid Severity value. Valid values: {}",
+                stringify_list(&Severity::iter().map(Severity::into).collect::<Vec<_>>()),
+            ))
+            .loc(token)
+            .push();
+    }
+    severity
+}
+
+fn load_override_confidence(token: &Token) -> Option<Confidence> {
+    let confidence = token.as_str().to_ascii_lowercase().parse().ok();
+    if confidence.is_none() {
+        err(ErrorKey::Config)
+            .msg(format!(
+                "Invalid Confidence value. Valid values are {}",
+                stringify_list(&Confidence::iter().map(Confidence::into).collect::<Vec<_>>())
+            ))
+            .loc(token)
+            .push();
+    }
+    confidence
+}
+
 /// Load a vector of rules from the given block.
 fn load_rules(block: &Block) -> Vec<FilterRule> {
     block.iter_items().filter_map(BlockItem::expect_field).filter_map(load_rule).collect()
@@ -98,7 +193,9 @@ fn load_rules_from_bv(bv: &BV) -> Option<Vec<FilterRule>> {
 fn load_rule(field: &Field) -> Option<FilterRule> {
     let Field(key, cmp, bv) = field;
     let cmp = *cmp;
-    if !key.is("severity") && !key.is("confidence") && !matches!(cmp, Comparator::Equals(Single)) {
+    let allows_comparison =
+        key.is("severity") || key.is("confidence") || key.is("pointers") || key.is("line");
+    if !allows_comparison && !matches!(cmp, Comparator::Equals(Single)) {
         err(ErrorKey::Config)
             .msg(format!("Unexpected operator `{cmp}`, only `=` is valid here."))
             .loc(key)
@@ -111,6 +208,11 @@ fn load_rule(field: &Field) -> Option<FilterRule> {
         "key" => load_rule_key(bv),
         "file" => load_rule_file(bv),
         "text" => load_rule_text(bv),
+        "mod" => load_rule_mod(bv),
+        "from" => load_rule_from(bv),
+        "pointers" => load_rule_pointers(cmp, bv),
+        "line" => load_rule_line(cmp, bv),
+        "item_type" => load_rule_item_type(bv),
         "always" => load_rule_always(bv),
         "ignore_keys_in_files" => load_ignore_keys_in_files(bv),
         "NOT" => load_not(bv),
@@ -389,6 +491,119 @@ fn load_rule_text(bv: &BV) -> Option<FilterRule> {
     }
 }
 
+fn load_rule_mod(bv: &BV) -> Option<FilterRule> {
+    match bv {
+        BV::Block(_) => {
+            err(ErrorKey::Config)
+                .msg("`mod` can't open a block. Example usage: `mod = MOD1`")
+                .loc(bv)
+                .push();
+            None
+        }
+        BV::Value(token) => Some(FilterRule::Mod(token.to_string())),
+    }
+}
+
+fn load_rule_from(bv: &BV) -> Option<FilterRule> {
+    match bv {
+        BV::Block(_) => {
+            err(ErrorKey::Config)
+                .msg("`from` can't open a block. Example usage: `from = vanilla`")
+                .loc(bv)
+                .push();
+            None
+        }
+        BV::Value(token) => match token.as_str().to_ascii_lowercase().as_str() {
+            "vanilla" => Some(FilterRule::From(FileSource::Vanilla)),
+            "mod" => Some(FilterRule::From(FileSource::Mod)),
+            "dlc" => Some(FilterRule::From(FileSource::Dlc)),
+            _ => {
+                err(ErrorKey::Config)
+                    .msg("Invalid `from` value. Valid values are vanilla, mod, and dlc")
+                    .loc(token)
+                    .push();
+                None
+            }
+        },
+    }
+}
+
+fn load_rule_pointers(comparator: Comparator, bv: &BV) -> Option<FilterRule> {
+    match bv {
+        BV::Block(_) => {
+            err(ErrorKey::Config)
+                .msg("`pointers` can't open a block. Example usage: `pointers >= 2`")
+                .loc(bv)
+                .push();
+            None
+        }
+        BV::Value(token) => {
+            if let Ok(count) = token.as_str().parse() {
+                Some(FilterRule::Pointers(comparator, count))
+            } else {
+                err(ErrorKey::Config).msg("Expected a number of locations").loc(token).push();
+                None
+            }
+        }
+    }
+}
+
+/// Load a `line` rule. It's either a comparison like `line >= 100`, or a range like
+/// `line = 100-200` that includes both ends.
+fn load_rule_line(comparator: Comparator, bv: &BV) -> Option<FilterRule> {
+    match bv {
+        BV::Block(_) => {
+            err(ErrorKey::Config)
+                .msg("`line` can't open a block. Example usage: `line = 100-200`")
+                .loc(bv)
+                .push();
+            None
+        }
+        BV::Value(token) => {
+            if let Ok(line) = token.as_str().parse() {
+                return Some(FilterRule::Line(comparator, line));
+            }
+            if matches!(comparator, Comparator::Equals(Single)) {
+                if let Some((start, end)) = token.as_str().split_once('-') {
+                    if let (Ok(start), Ok(end)) = (start.parse(), end.parse()) {
+                        return Some(FilterRule::Conjunction(vec![
+                            FilterRule::Line(Comparator::AtLeast, start),
+                            FilterRule::Line(Comparator::AtMost, end),
+                        ]));
+                    }
+                }
+            }
+            err(ErrorKey::Config)
+                .msg("Expected a line number or a range of line numbers like `100-200`")
+                .loc(token)
+                .push();
+            None
+        }
+    }
+}
+
+fn load_rule_item_type(bv: &BV) -> Option<FilterRule> {
+    match bv {
+        BV::Block(_) => {
+            err(ErrorKey::Config)
+                .msg("`item_type` can't open a block. Example usage: `item_type = trait`")
+                .loc(bv)
+                .push();
+            None
+        }
+        BV::Value(token) => {
+            let item = Item::iter().find(|item| <&str>::from(item) == token.as_str());
+            if item.is_none() {
+                err(ErrorKey::Config)
+                    .msg("Unknown item type. Item types are written in lowercase with underscores, like `scripted_effect`")
+                    .loc(token)
+                    .push();
+            }
+            item.map(FilterRule::ItemType)
+        }
+    }
+}
+
 /// Assert that the given key occurs at most once within the given block.
 /// If the assertion fails, an error report will be created. No other action will be taken.
 pub fn assert_one_key(assert_key: &str, block: &Block) {
@@ -410,6 +625,8 @@ pub fn assert_one_key(assert_key: &str, block: &Block) {
                 loc: key.into_loc(),
                 length: 1,
                 msg: Some((if index == 0 { "It occurs here" } else { "and here" }).to_owned()),
+                fix: None,
+                fix_end: None,
             })
             .collect();
         err(ErrorKey::Config)
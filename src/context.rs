@@ -161,6 +161,17 @@ pub struct Signature {
     lists: Vec<(&'static str, Scopes)>,
 }
 
+/// What a `ScopeContext` expects of its caller, as inferred while validating with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expectations {
+    pub root: Scopes,
+    pub this: Scopes,
+    /// The named scopes that are expected to be set on entry, sorted by name.
+    pub named_scopes: Vec<(&'static str, Scopes)>,
+    /// The named lists that are expected to be set on entry, sorted by name.
+    pub lists: Vec<(&'static str, Scopes)>,
+}
+
 impl Reason {
     pub fn token(&self) -> &Token {
         match self {
@@ -585,6 +596,25 @@ impl ScopeContext {
         Signature { root, named_scopes, lists }
     }
 
+    /// Return what this `ScopeContext` expects of its caller, for documenting scripted items.
+    pub fn expectations(&self) -> Expectations {
+        let inputs = |names: &TigerHashMap<&'static str, usize>| {
+            let mut vec: Vec<(&'static str, Scopes)> = names
+                .iter()
+                .filter(|(_, &i)| self.is_input[i].is_some())
+                .map(|(&name, &i)| (name, self.resolve_named(i).0))
+                .collect();
+            vec.sort_by_key(|(name, _)| *name);
+            vec
+        };
+        Expectations {
+            root: self.resolve_root().0,
+            this: self.scopes(),
+            named_scopes: inputs(&self.names),
+            lists: inputs(&self.list_names),
+        }
+    }
+
     /// Replace the `this` in a temporary scope level with the given `scopes` type and record
     /// `token` as the reason for this type.
     ///
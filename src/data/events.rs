@@ -1,10 +1,11 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Mutex;
 
 use rayon::prelude::*;
 
 use crate::block::{Block, BlockItem, Field};
+use crate::compat::Overrides;
 use crate::context::{Reason, ScopeContext, Signature};
 use crate::data::scripted_effects::Effect;
 use crate::data::scripted_triggers::Trigger;
@@ -16,7 +17,7 @@ use crate::item::Item;
 use crate::parse::ParserMemory;
 use crate::pathtable::PathTableIndex;
 use crate::pdxfile::PdxFile;
-use crate::report::{err, warn, ErrorKey};
+use crate::report::{err, warn, with_validating_item, ErrorKey};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::variables::Variables;
@@ -28,13 +29,20 @@ pub struct Events {
     namespaces: TigerHashSet<Token>,
     triggers: TigerHashMap<(PathTableIndex, &'static str), Trigger>,
     effects: TigerHashMap<(PathTableIndex, &'static str), Effect>,
+    /// Definitions that override those of other mods, for the compatibility report.
+    overrides: Overrides,
 }
 
 impl Events {
+    pub(crate) fn overrides(&self) -> &Overrides {
+        &self.overrides
+    }
+
     fn load_event(&mut self, key: Token, block: Block) {
         if let Some((key_a, key_b)) = key.as_str().split_once('.') {
             if let Ok(id) = u16::from_str(key_b) {
                 if let Some(other) = self.get_event(key.as_str()) {
+                    let other_key = other.key.clone();
                     #[allow(clippy::redundant_else)]
                     if Game::is_vic3() {
                         // Earlier events override later ones in vic3.
@@ -42,11 +50,13 @@ impl Events {
                         if other.key.loc.kind <= key.loc.kind {
                             dup_error(&other.key, &key, "event");
                         }
+                        self.overrides.record(Item::Event, &key, &other_key);
                         return;
                     } else {
                         // In the other games, overriding events is always an error.
                         dup_error(&key, &other.key, "event");
                     }
+                    self.overrides.record(Item::Event, &other_key, &key);
                 }
                 self.events.insert((key_a, id), Event::new(key, block));
                 return;
@@ -135,6 +145,22 @@ impl Events {
         self.events.values().map(|item| &item.key)
     }
 
+    /// Forget which scope contexts the events in the given files were validated in, and the
+    /// cached validations of calls made from those files, so that they are validated again.
+    pub fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        for item in self.events.values() {
+            if files.contains(item.key.loc.pathname()) {
+                item.visited.lock().unwrap().clear();
+            }
+        }
+        for item in self.effects.values() {
+            item.forget_calls_in(files);
+        }
+        for item in self.triggers.values() {
+            item.forget_calls_in(files);
+        }
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.effects.values() {
             item.validate(data);
@@ -269,7 +295,7 @@ impl Event {
         sc.set_strict_scopes(false);
         sc.set_source(&self.key);
 
-        match Game::game() {
+        let validate = || match Game::game() {
             #[cfg(feature = "ck3")]
             Game::Ck3 => crate::ck3::events::validate_event(self, data, &mut sc),
             #[cfg(feature = "vic3")]
@@ -278,7 +304,10 @@ impl Event {
             Game::Imperator => crate::imperator::events::validate_event(self, data, &mut sc),
             #[cfg(feature = "hoi4")]
             Game::Hoi4 => unimplemented!(),
-        }
+        };
+        with_validating_item(Item::Event, || {
+            data.call_graph.with_caller(Item::Event, &self.key, validate);
+        });
     }
 
     pub fn validate_call(&self, data: &Everything, sc: &mut ScopeContext) {
@@ -286,7 +315,7 @@ impl Event {
             // The event was already visited with an equivalent sc
             return;
         }
-        match Game::game() {
+        let validate = || match Game::game() {
             #[cfg(feature = "ck3")]
             Game::Ck3 => crate::ck3::events::validate_event(self, data, sc),
             #[cfg(feature = "vic3")]
@@ -295,6 +324,9 @@ impl Event {
             Game::Imperator => crate::imperator::events::validate_event(self, data, sc),
             #[cfg(feature = "hoi4")]
             Game::Hoi4 => unimplemented!(),
-        }
+        };
+        with_validating_item(Item::Event, || {
+            data.call_graph.with_caller(Item::Event, &self.key, validate);
+        });
     }
 }
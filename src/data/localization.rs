@@ -23,6 +23,7 @@ use strum_macros::{Display, EnumCount, EnumIter, EnumString, FromRepr, IntoStati
 use crate::block::Block;
 #[cfg(feature = "ck3")]
 use crate::ck3::tables::localization::{BUILTIN_MACROS_CK3, COMPLEX_TOOLTIPS_CK3};
+use crate::compat::Overrides;
 use crate::context::ScopeContext;
 use crate::datatype::{validate_datatypes, CodeChain, Datatype};
 use crate::everything::Everything;
@@ -34,6 +35,7 @@ use crate::hoi4::tables::localization::BUILTIN_MACROS_HOI4;
 #[cfg(feature = "imperator")]
 use crate::imperator::tables::localization::BUILTIN_MACROS_IMPERATOR;
 use crate::item::Item;
+use crate::lowercase::Lowercase;
 use crate::macros::{MacroMapIndex, MACRO_MAP};
 use crate::parse::localization::{parse_loca, ValueParser};
 use crate::parse::ParserMemory;
@@ -54,6 +56,11 @@ pub struct Localization {
     mod_langs: BitArr!(for Language::COUNT, in u16),
     /// Database of all localizations, indexed first by language and then by localization key.
     locas: Box<[TigerHashMap<&'static str, LocaEntry>; Language::COUNT]>,
+    /// All localization keys, indexed by their lowercase form.
+    /// This is used to suggest the right capitalization for keys that are not found.
+    lowercase_keys: TigerHashMap<Lowercase<'static>, &'static str>,
+    /// Keys that override those of other mods, for the compatibility report.
+    overrides: Overrides,
 }
 
 /// List of languages that are supported by the game engine.
@@ -308,6 +315,10 @@ impl Localization {
         result
     }
 
+    pub(crate) fn overrides(&self) -> &Overrides {
+        &self.overrides
+    }
+
     pub fn iter_keys(&self) -> impl Iterator<Item = &Token> {
         self.iter_lang_idx()
             .map(|i| &self.locas[i])
@@ -321,14 +332,25 @@ impl Localization {
         let langs_missing = self.mark_used_return_missing(key);
         if !langs_missing.is_empty() {
             let msg = format!("missing {} localization key {key}", stringify_list(&langs_missing));
+            // Only suggest another key if this one is missing everywhere.
+            let similar =
+                self.similar_key(key).filter(|_| langs_missing.len() == self.iter_lang().count());
+            let info = similar.map(|similar| format!("did you mean `{similar}`?"));
             // TODO: get confidence level from caller
             report(ErrorKey::MissingLocalization, Item::Localization.severity().at_most(max_sev))
                 .msg(msg)
+                .opt_info(info)
                 .loc(token)
+                .opt_fix(similar.filter(|_| token.is(key)))
                 .push();
         }
     }
 
+    /// Return a defined key that differs from `key` only in capitalization, if there is one.
+    fn similar_key(&self, key: &str) -> Option<&'static str> {
+        self.lowercase_keys.get(Lowercase::new(key).as_str()).copied().filter(|k| *k != key)
+    }
+
     #[cfg(feature = "ck3")]
     pub fn verify_name_exists(&self, name: &Token, max_sev: Severity) {
         if name.as_str().is_empty() {
@@ -385,8 +407,16 @@ impl Localization {
         if let Some(lang) = lang {
             if !self.mark_used_lang_return_exists(key, lang) {
                 let msg = format!("missing {lang} localization key {key}");
+                let similar =
+                    self.similar_key(key).filter(|similar| self.exists_lang(similar, lang));
+                let info = similar.map(|similar| format!("did you mean `{similar}`?"));
                 // TODO: get confidence level from caller
-                warn(ErrorKey::MissingLocalization).msg(msg).loc(token).push();
+                warn(ErrorKey::MissingLocalization)
+                    .msg(msg)
+                    .opt_info(info)
+                    .loc(token)
+                    .opt_fix(similar.filter(|_| token.is(key)))
+                    .push();
             }
         } else {
             self.verify_exists_implied(key, token, Severity::Warning);
@@ -806,12 +836,18 @@ impl FileHandler<(Language, Vec<LocaEntry>)> for Localization {
                     // other.key and loca.key are in the other order than usual here,
                     // because in loca the older definition overrides the later one.
                     if is_replace_path(entry.path()) {
+                        self.overrides.record(Item::Localization, &other.key, &loca.key);
                         occupied_entry.insert(loca);
-                    } else if other.key.loc.kind == entry.kind() && other.orig != loca.orig {
-                        dup_error(&other.key, &loca.key, "localization");
+                    } else {
+                        if other.key.loc.kind == entry.kind() && other.orig != loca.orig {
+                            dup_error(&other.key, &loca.key, "localization");
+                        }
+                        self.overrides.record(Item::Localization, &loca.key, &other.key);
                     }
                 }
                 Entry::Vacant(vacant_entry) => {
+                    let key = loca.key.as_str();
+                    self.lowercase_keys.entry(Lowercase::new(key)).or_insert(key);
                     vacant_entry.insert(loca);
                 }
             }
@@ -825,6 +861,8 @@ impl Default for Localization {
             check_langs: bitarr![u16, Lsb0; 1; Language::COUNT],
             mod_langs: bitarr![u16, Lsb0; 0; Language::COUNT],
             locas: Box::new(std::array::from_fn(|_| TigerHashMap::default())),
+            lowercase_keys: TigerHashMap::default(),
+            overrides: Overrides::default(),
         }
     }
 }
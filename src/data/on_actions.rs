@@ -13,6 +13,7 @@ use crate::parse::ParserMemory;
 use crate::pdxfile::PdxFile;
 #[cfg(feature = "ck3")]
 use crate::report::warn;
+use crate::report::with_validating_item;
 #[cfg(any(feature = "ck3", feature = "hoi4"))]
 use crate::report::{err, ErrorKey};
 use crate::scopes::Scopes;
@@ -146,7 +147,17 @@ impl OnAction {
                 generated_sc.set_strict_scopes(false);
                 generated_sc
             };
-            validate_on_action_internal(block, data, &mut sc, &mut seen_trigger, &mut seen_effect);
+            with_validating_item(Item::OnAction, || {
+                data.call_graph.with_caller(Item::OnAction, &self.actions[0].0, || {
+                    validate_on_action_internal(
+                        block,
+                        data,
+                        &mut sc,
+                        &mut seen_trigger,
+                        &mut seen_effect,
+                    );
+                });
+            });
         }
     }
 
@@ -157,9 +168,19 @@ impl OnAction {
     pub fn validate_call(&self, data: &Everything, sc: &mut ScopeContext) {
         let mut seen_trigger = false;
         let mut seen_effect = false;
-        for (_, block) in self.actions.iter().rev() {
-            validate_on_action_internal(block, data, sc, &mut seen_trigger, &mut seen_effect);
-        }
+        with_validating_item(Item::OnAction, || {
+            data.call_graph.with_caller(Item::OnAction, &self.actions[0].0, || {
+                for (_, block) in self.actions.iter().rev() {
+                    validate_on_action_internal(
+                        block,
+                        data,
+                        sc,
+                        &mut seen_trigger,
+                        &mut seen_effect,
+                    );
+                }
+            });
+        });
     }
 }
 
@@ -203,6 +224,7 @@ fn validate_on_action_internal(
     if Game::is_jomini() {
         #[allow(unused_variables)] // vic3 doesn't use `key`
         vd.multi_field_validated_key_block("events", |key, b, data| {
+            data.call_graph.record_list(Item::Event, b, "events");
             let mut vd = Validator::new(b, data);
             #[cfg(feature = "jomini")]
             if Game::is_jomini() {
@@ -228,6 +250,7 @@ fn validate_on_action_internal(
     count = 0;
     #[allow(unused_variables)] // vic3 doesn't use `key`
     vd.multi_field_validated_key_block("random_events", |key, b, data| {
+        data.call_graph.record_list(Item::Event, b, "random_events");
         let mut vd = Validator::new(b, data);
         #[cfg(feature = "jomini")]
         if Game::is_jomini() {
@@ -260,6 +283,7 @@ fn validate_on_action_internal(
         count = 0;
         #[allow(unused_variables)] // vic3 doesn't use `key`
         vd.multi_field_validated_key_block("first_valid", |key, b, data| {
+            data.call_graph.record_list(Item::Event, b, "first_valid");
             let mut vd = Validator::new(b, data);
             for token in vd.values() {
                 data.verify_exists(Item::Event, token);
@@ -280,6 +304,7 @@ fn validate_on_action_internal(
         count = 0;
         #[allow(unused_variables)] // vic3 doesn't use `key`
         vd.multi_field_validated_key_block("on_actions", |key, b, data| {
+            data.call_graph.record_list(Item::OnAction, b, "on_actions");
             let mut vd = Validator::new(b, data);
             #[cfg(feature = "jomini")]
             if Game::is_jomini() {
@@ -303,6 +328,7 @@ fn validate_on_action_internal(
         count = 0;
         #[allow(unused_variables)] // vic3 doesn't use `key`
         vd.multi_field_validated_key_block("random_on_action", |key, b, data| {
+            data.call_graph.record_list(Item::OnAction, b, "random_on_action");
             let mut vd = Validator::new(b, data);
             #[cfg(feature = "jomini")]
             if Game::is_jomini() {
@@ -330,6 +356,7 @@ fn validate_on_action_internal(
         count = 0;
         #[allow(unused_variables)] // vic3 doesn't use `key`
         vd.multi_field_validated_key_block("first_valid_on_action", |key, b, data| {
+            data.call_graph.record_list(Item::OnAction, b, "first_valid_on_action");
             let mut vd = Validator::new(b, data);
             for token in vd.values() {
                 data.verify_exists(Item::OnAction, token);
@@ -1,11 +1,13 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 
 use crate::block::{Block, BV};
-use crate::context::ScopeContext;
+use crate::compat::Overrides;
+use crate::context::{Expectations, ScopeContext};
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
-use crate::helpers::{dup_error, exact_dup_error, TigerHashMap, BANNED_NAMES};
+use crate::helpers::{dup_error, exact_dup_error, TigerHashMap, TigerHashSet, BANNED_NAMES};
+use crate::item::Item;
 use crate::parse::ParserMemory;
 use crate::pdxfile::PdxFile;
 use crate::report::{err, warn, ErrorKey};
@@ -15,12 +17,19 @@ use crate::token::{Loc, Token};
 use crate::variables::Variables;
 
 #[derive(Debug, Default)]
+#[allow(clippy::struct_field_names)]
 pub struct ScriptValues {
     scope_overrides: TigerHashMap<&'static str, Scopes>,
     script_values: TigerHashMap<&'static str, ScriptValue>,
+    /// Definitions that override those of other mods, for the compatibility report.
+    overrides: Overrides,
 }
 
 impl ScriptValues {
+    pub(crate) fn overrides(&self) -> &Overrides {
+        &self.overrides
+    }
+
     fn load_item(&mut self, key: &Token, bv: &BV) {
         if let Some(other) = self.script_values.get(key.as_str()) {
             if other.key.loc.kind >= key.loc.kind {
@@ -30,6 +39,7 @@ impl ScriptValues {
                     dup_error(key, &other.key, "script value");
                 }
             }
+            self.overrides.record(Item::ScriptValue, &other.key, key);
         }
         if BANNED_NAMES.contains(&key.as_str()) {
             let msg = "scriptedvalue has the same name as an important builtin";
@@ -53,10 +63,22 @@ impl ScriptValues {
         self.script_values.contains_key(key)
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &ScriptValue> {
+        self.script_values.values()
+    }
+
     pub fn iter_keys(&self) -> impl Iterator<Item = &Token> {
         self.script_values.values().map(|item| &item.key)
     }
 
+    /// Forget the cached validations of calls made from the given files, so that they are
+    /// validated again.
+    pub fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        for item in self.script_values.values() {
+            item.forget_calls_in(files);
+        }
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.script_values.values() {
             item.validate(data);
@@ -139,6 +161,11 @@ impl ScriptValue {
         }
     }
 
+    /// Forget the cached validations of calls made from the given files.
+    pub fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        self.cache.write().unwrap().retain(|loc, _| !files.contains(loc.pathname()));
+    }
+
     pub fn validate(&self, data: &Everything) {
         // For some reason, script values can be set to bools as well
         if let Some(token) = self.bv.get_value() {
@@ -162,7 +189,9 @@ impl ScriptValue {
                 our_sc.set_no_warn(true);
             }
             self.cache.write().unwrap().insert(key.loc, our_sc.clone());
-            validate_script_value(&self.bv, data, &mut our_sc);
+            data.call_graph.with_caller(Item::ScriptValue, &self.key, || {
+                validate_script_value(&self.bv, data, &mut our_sc);
+            });
             if let Some(scopes) = self.scope_override {
                 our_sc = ScopeContext::new_unrooted(scopes, key);
                 our_sc.set_strict_scopes(false);
@@ -173,6 +202,23 @@ impl ScriptValue {
     }
 
     pub fn validate_non_dynamic_call(&self, data: &Everything) {
-        validate_non_dynamic_script_value(&self.bv, data);
+        data.call_graph.with_caller(Item::ScriptValue, &self.key, || {
+            validate_non_dynamic_script_value(&self.bv, data);
+        });
+    }
+
+    pub fn key(&self) -> &Token {
+        &self.key
+    }
+
+    /// Return what this script value expects of its caller, as inferred when it was validated on
+    /// its own. Returns `None` for script values that are simply `yes` or `no`.
+    pub fn expectations(&self) -> Option<Expectations> {
+        let cache = self.cache.read().unwrap();
+        Some(cache.get(&self.key.loc)?.expectations())
+    }
+
+    pub fn has_scope_override(&self) -> bool {
+        self.scope_override.is_some()
     }
 }
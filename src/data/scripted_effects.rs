@@ -1,30 +1,39 @@
 use std::fmt::Debug;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::block::Block;
-use crate::context::ScopeContext;
+use crate::compat::Overrides;
+use crate::context::{Expectations, ScopeContext};
 use crate::effect::validate_effect;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
 #[cfg(feature = "hoi4")]
 use crate::game::Game;
-use crate::helpers::{dup_error, exact_dup_error, TigerHashMap, BANNED_NAMES};
+use crate::helpers::{dup_error, exact_dup_error, TigerHashMap, TigerHashSet, BANNED_NAMES};
+use crate::item::Item;
 use crate::macros::{MacroCache, MACRO_MAP};
 use crate::parse::ParserMemory;
 use crate::pdxfile::PdxFile;
-use crate::report::{err, warn, ErrorKey};
+use crate::report::{err, warn, with_validating_item, ErrorKey};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
 use crate::variables::Variables;
 
 #[derive(Debug, Default)]
+#[allow(clippy::struct_field_names)]
 pub struct Effects {
     scope_overrides: TigerHashMap<&'static str, Scopes>,
     effects: TigerHashMap<&'static str, Effect>,
+    /// Definitions that override those of other mods, for the compatibility report.
+    overrides: Overrides,
 }
 
 impl Effects {
+    pub(crate) fn overrides(&self) -> &Overrides {
+        &self.overrides
+    }
+
     fn load_item(&mut self, key: Token, block: Block) {
         if let Some(other) = self.effects.get(key.as_str()) {
             if other.key.loc.kind >= key.loc.kind {
@@ -34,6 +43,7 @@ impl Effects {
                     dup_error(&key, &other.key, "scripted effect");
                 }
             }
+            self.overrides.record(Item::ScriptedEffect, &other.key, &key);
         }
         if BANNED_NAMES.contains(&key.as_str()) {
             let msg = "scripted effect has the same name as an important builtin";
@@ -61,10 +71,22 @@ impl Effects {
         self.effects.values().map(|item| &item.key)
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &Effect> {
+        self.effects.values()
+    }
+
     pub fn get(&self, key: &str) -> Option<&Effect> {
         self.effects.get(key)
     }
 
+    /// Forget the cached validations of calls made from the given files, so that they are
+    /// validated again.
+    pub fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        for item in self.effects.values() {
+            item.forget_calls_in(files);
+        }
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.effects.values() {
             item.validate(data);
@@ -155,7 +177,11 @@ impl Effect {
                 our_sc.set_no_warn(true);
             }
             self.cache.insert(key, &[], tooltipped, false, our_sc.clone());
-            validate_effect(&self.block, data, &mut our_sc, tooltipped);
+            with_validating_item(Item::ScriptedEffect, || {
+                data.call_graph.with_caller(Item::ScriptedEffect, &self.key, || {
+                    validate_effect(&self.block, data, &mut our_sc, tooltipped);
+                });
+            });
             if let Some(scopes) = self.scope_override {
                 our_sc = ScopeContext::new_unrooted(scopes, key);
                 our_sc.set_strict_scopes(false);
@@ -165,10 +191,26 @@ impl Effect {
         }
     }
 
+    /// Forget the cached validations of calls made from the given files.
+    pub fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        self.cache.forget_calls_in(files);
+    }
+
     pub fn macro_parms(&self) -> Vec<&'static str> {
         self.block.macro_parms()
     }
 
+    /// Return what this scripted effect expects of its caller, as inferred when it was validated on its
+    /// own. Returns `None` if it has macro parameters, because then it is only validated per call.
+    pub fn expectations(&self) -> Option<Expectations> {
+        let sc = self.cache.get(&self.key, &[], Tooltipped::No, false)?;
+        Some(sc.expectations())
+    }
+
+    pub fn has_scope_override(&self) -> bool {
+        self.scope_override.is_some()
+    }
+
     pub fn cached_compat(
         &self,
         key: &Token,
@@ -192,6 +234,7 @@ impl Effect {
         // Every invocation is treated as different even if the args are the same,
         // because we want to point to the correct one when reporting errors.
         if !self.cached_compat(key, args, tooltipped, sc) {
+            MACRO_MAP.record_frame(key.loc, Item::ScriptedEffect, &self.key, args);
             if let Some(block) = self.block.expand_macro(args, key.loc, &data.parser.pdxfile) {
                 let mut our_sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
                 our_sc.set_strict_scopes(false);
@@ -201,7 +244,11 @@ impl Effect {
                 // Insert the dummy sc before continuing. That way, if we recurse, we'll hit
                 // that dummy context instead of macro-expanding again.
                 self.cache.insert(key, args, tooltipped, false, our_sc.clone());
-                validate_effect(&block, data, &mut our_sc, tooltipped);
+                with_validating_item(Item::ScriptedEffect, || {
+                    data.call_graph.with_caller(Item::ScriptedEffect, &self.key, || {
+                        validate_effect(&block, data, &mut our_sc, tooltipped);
+                    });
+                });
                 if let Some(scopes) = self.scope_override {
                     our_sc = ScopeContext::new_unrooted(scopes, key);
                     our_sc.set_strict_scopes(false);
@@ -1,11 +1,13 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 
 use crate::block::Block;
+use crate::compat::Overrides;
 use crate::context::ScopeContext;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
-use crate::helpers::{dup_error, TigerHashMap};
+use crate::helpers::{dup_error, TigerHashMap, TigerHashSet};
+use crate::item::Item;
 use crate::parse::ParserMemory;
 use crate::pdxfile::PdxFile;
 use crate::report::{err, ErrorKey};
@@ -19,14 +21,21 @@ use crate::variables::Variables;
 #[derive(Debug, Default)]
 pub struct ScriptedLists {
     lists: TigerHashMap<&'static str, List>,
+    /// Definitions that override those of other mods, for the compatibility report.
+    overrides: Overrides,
 }
 
 impl ScriptedLists {
+    pub(crate) fn overrides(&self) -> &Overrides {
+        &self.overrides
+    }
+
     fn load_item(&mut self, key: Token, block: Block) {
         if let Some(other) = self.lists.get(key.as_str()) {
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "scripted list");
             }
+            self.overrides.record(Item::ScriptedList, &other.key, &key);
         }
         self.lists.insert(key.as_str(), List::new(key, block));
     }
@@ -55,6 +64,14 @@ impl ScriptedLists {
         self.lists.get(item.as_str()).and_then(|item| item.block.get_field_value("base"))
     }
 
+    /// Forget the cached validations of calls made from the given files, so that they are
+    /// validated again.
+    pub fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        for item in self.lists.values() {
+            item.forget_calls_in(files);
+        }
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.lists.values() {
             item.validate(data);
@@ -134,6 +151,11 @@ impl List {
         }
     }
 
+    /// Forget the cached validations of calls made from the given files.
+    pub fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        self.cache.write().unwrap().retain(|loc, _| !files.contains(loc.pathname()));
+    }
+
     pub fn validate_call(&self, key: &Token, data: &Everything, sc: &mut ScopeContext) {
         if !self.cached_compat(key, sc) {
             let mut our_sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
@@ -1,10 +1,12 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::block::Block;
+use crate::compat::Overrides;
 use crate::context::ScopeContext;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
-use crate::helpers::{dup_error, TigerHashMap, BANNED_NAMES};
+use crate::helpers::{dup_error, TigerHashMap, TigerHashSet, BANNED_NAMES};
+use crate::item::Item;
 use crate::macros::{MacroCache, MACRO_MAP};
 use crate::parse::ParserMemory;
 use crate::pdxfile::PdxFile;
@@ -19,14 +21,21 @@ use crate::variables::Variables;
 #[derive(Debug, Default)]
 pub struct ScriptedModifiers {
     scripted_modifiers: TigerHashMap<&'static str, ScriptedModifier>,
+    /// Definitions that override those of other mods, for the compatibility report.
+    overrides: Overrides,
 }
 
 impl ScriptedModifiers {
+    pub(crate) fn overrides(&self) -> &Overrides {
+        &self.overrides
+    }
+
     fn load_item(&mut self, key: Token, block: Block) {
         if let Some(other) = self.scripted_modifiers.get(key.as_str()) {
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "scripted modifier");
             }
+            self.overrides.record(Item::ScriptedModifier, &other.key, &key);
         }
         if BANNED_NAMES.contains(&key.as_str()) {
             let msg = "scripted modifier has the same name as an important builtin";
@@ -57,6 +66,14 @@ impl ScriptedModifiers {
         self.scripted_modifiers.get(key)
     }
 
+    /// Forget the cached validations of calls made from the given files, so that they are
+    /// validated again.
+    pub fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        for item in self.scripted_modifiers.values() {
+            item.forget_calls_in(files);
+        }
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.scripted_modifiers.values() {
             item.validate(data);
@@ -110,14 +127,21 @@ impl ScriptedModifier {
             let mut our_sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
             our_sc.set_strict_scopes(false);
             self.cache.insert(key, &[], Tooltipped::No, false, our_sc.clone());
-            let mut vd = Validator::new(&self.block, data);
-            validate_modifiers(&mut vd, &mut our_sc);
-            validate_scripted_modifier_calls(vd, data, &mut our_sc);
+            data.call_graph.with_caller(Item::ScriptedModifier, &self.key, || {
+                let mut vd = Validator::new(&self.block, data);
+                validate_modifiers(&mut vd, &mut our_sc);
+                validate_scripted_modifier_calls(vd, data, &mut our_sc);
+            });
             sc.expect_compatibility(&our_sc, key);
             self.cache.insert(key, &[], Tooltipped::No, false, our_sc);
         }
     }
 
+    /// Forget the cached validations of calls made from the given files.
+    pub fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        self.cache.forget_calls_in(files);
+    }
+
     pub fn macro_parms(&self) -> Vec<&'static str> {
         self.block.macro_parms()
     }
@@ -143,15 +167,18 @@ impl ScriptedModifier {
         // Every invocation is treated as different even if the args are the same,
         // because we want to point to the correct one when reporting errors.
         if !self.cached_compat(key, args, sc) {
+            MACRO_MAP.record_frame(key.loc, Item::ScriptedModifier, &self.key, args);
             if let Some(block) = self.block.expand_macro(args, key.loc, &data.parser.pdxfile) {
                 let mut our_sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
                 our_sc.set_strict_scopes(false);
                 // Insert the dummy sc before continuing. That way, if we recurse, we'll hit
                 // that dummy context instead of macro-expanding again.
                 self.cache.insert(key, args, Tooltipped::No, false, our_sc.clone());
-                let mut vd = Validator::new(&block, data);
-                validate_modifiers(&mut vd, &mut our_sc);
-                validate_scripted_modifier_calls(vd, data, &mut our_sc);
+                data.call_graph.with_caller(Item::ScriptedModifier, &self.key, || {
+                    let mut vd = Validator::new(&block, data);
+                    validate_modifiers(&mut vd, &mut our_sc);
+                    validate_scripted_modifier_calls(vd, data, &mut our_sc);
+                });
                 sc.expect_compatibility(&our_sc, key);
                 self.cache.insert(key, args, Tooltipped::No, false, our_sc);
             }
@@ -1,17 +1,19 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::block::Block;
-use crate::context::ScopeContext;
+use crate::compat::Overrides;
+use crate::context::{Expectations, ScopeContext};
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
 #[cfg(feature = "hoi4")]
 use crate::game::Game;
-use crate::helpers::{dup_error, exact_dup_error, TigerHashMap, BANNED_NAMES};
+use crate::helpers::{dup_error, exact_dup_error, TigerHashMap, TigerHashSet, BANNED_NAMES};
+use crate::item::Item;
 use crate::lowercase::Lowercase;
 use crate::macros::{MacroCache, MACRO_MAP};
 use crate::parse::ParserMemory;
 use crate::pdxfile::PdxFile;
-use crate::report::{err, warn, ErrorKey};
+use crate::report::{err, warn, with_validating_item, ErrorKey};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
@@ -21,12 +23,19 @@ use crate::validator::Validator;
 use crate::variables::Variables;
 
 #[derive(Debug, Default)]
+#[allow(clippy::struct_field_names)]
 pub struct Triggers {
     scope_overrides: TigerHashMap<&'static str, Scopes>,
     triggers: TigerHashMap<&'static str, Trigger>,
+    /// Definitions that override those of other mods, for the compatibility report.
+    overrides: Overrides,
 }
 
 impl Triggers {
+    pub(crate) fn overrides(&self) -> &Overrides {
+        &self.overrides
+    }
+
     fn load_item(&mut self, key: Token, block: Block) {
         if let Some(other) = self.triggers.get(key.as_str()) {
             if other.key.loc.kind >= key.loc.kind {
@@ -36,6 +45,7 @@ impl Triggers {
                     dup_error(&key, &other.key, "scripted trigger");
                 }
             }
+            self.overrides.record(Item::ScriptedTrigger, &other.key, &key);
         }
         if BANNED_NAMES.contains(&key.as_str()) {
             let msg = "scripted trigger has the same name as an important builtin";
@@ -67,10 +77,22 @@ impl Triggers {
         self.triggers.values().map(|item| &item.key)
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &Trigger> {
+        self.triggers.values()
+    }
+
     pub fn get(&self, key: &str) -> Option<&Trigger> {
         self.triggers.get(key)
     }
 
+    /// Forget the cached validations of calls made from the given files, so that they are
+    /// validated again.
+    pub fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        for item in self.triggers.values() {
+            item.forget_calls_in(files);
+        }
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.triggers.values() {
             item.validate(data);
@@ -164,17 +186,21 @@ impl Trigger {
                 our_sc.set_no_warn(true);
             }
             self.cache.insert(key, &[], tooltipped, negated, our_sc.clone());
-            let vd = Validator::new(&self.block, data);
-            validate_trigger_internal(
-                Lowercase::empty(),
-                ListType::None,
-                &self.block,
-                data,
-                &mut our_sc,
-                vd,
-                tooltipped,
-                negated,
-            );
+            with_validating_item(Item::ScriptedTrigger, || {
+                data.call_graph.with_caller(Item::ScriptedTrigger, &self.key, || {
+                    let vd = Validator::new(&self.block, data);
+                    validate_trigger_internal(
+                        Lowercase::empty(),
+                        ListType::None,
+                        &self.block,
+                        data,
+                        &mut our_sc,
+                        vd,
+                        tooltipped,
+                        negated,
+                    );
+                });
+            });
             if let Some(scopes) = self.scope_override {
                 our_sc = ScopeContext::new_unrooted(scopes, key);
                 our_sc.set_strict_scopes(false);
@@ -184,10 +210,26 @@ impl Trigger {
         }
     }
 
+    /// Forget the cached validations of calls made from the given files.
+    pub fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        self.cache.forget_calls_in(files);
+    }
+
     pub fn macro_parms(&self) -> Vec<&'static str> {
         self.block.macro_parms()
     }
 
+    /// Return what this scripted trigger expects of its caller, as inferred when it was validated on its
+    /// own. Returns `None` if it has macro parameters, because then it is only validated per call.
+    pub fn expectations(&self) -> Option<Expectations> {
+        let sc = self.cache.get(&self.key, &[], Tooltipped::No, false)?;
+        Some(sc.expectations())
+    }
+
+    pub fn has_scope_override(&self) -> bool {
+        self.scope_override.is_some()
+    }
+
     pub fn cached_compat(
         &self,
         key: &Token,
@@ -213,6 +255,7 @@ impl Trigger {
         // Every invocation is treated as different even if the args are the same,
         // because we want to point to the correct one when reporting errors.
         if !self.cached_compat(key, args, tooltipped, negated, sc) {
+            MACRO_MAP.record_frame(key.loc, Item::ScriptedTrigger, &self.key, args);
             if let Some(block) = self.block.expand_macro(args, key.loc, &data.parser.pdxfile) {
                 let mut our_sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
                 our_sc.set_strict_scopes(false);
@@ -222,17 +265,21 @@ impl Trigger {
                 // Insert the dummy sc before continuing. That way, if we recurse, we'll hit
                 // that dummy context instead of macro-expanding again.
                 self.cache.insert(key, args, tooltipped, negated, our_sc.clone());
-                let vd = Validator::new(&block, data);
-                validate_trigger_internal(
-                    Lowercase::empty(),
-                    ListType::None,
-                    &block,
-                    data,
-                    &mut our_sc,
-                    vd,
-                    tooltipped,
-                    negated,
-                );
+                with_validating_item(Item::ScriptedTrigger, || {
+                    data.call_graph.with_caller(Item::ScriptedTrigger, &self.key, || {
+                        let vd = Validator::new(&block, data);
+                        validate_trigger_internal(
+                            Lowercase::empty(),
+                            ListType::None,
+                            &block,
+                            data,
+                            &mut our_sc,
+                            vd,
+                            tooltipped,
+                            negated,
+                        );
+                    });
+                });
                 if let Some(scopes) = self.scope_override {
                     our_sc = ScopeContext::new_unrooted(scopes, key);
                     our_sc.set_strict_scopes(false);
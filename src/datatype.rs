@@ -685,7 +685,12 @@ pub fn validate_datatypes(
             let msg = format!("unknown datafunction {}", &code.name);
             if let Some(alternative) = lookup_alternative(code.name.as_str()) {
                 let info = format!("did you mean {alternative}?");
-                warn(ErrorKey::Datafunctions).msg(msg).info(info).loc(&code.name).push();
+                warn(ErrorKey::Datafunctions)
+                    .msg(msg)
+                    .info(info)
+                    .loc(&code.name)
+                    .fix(alternative)
+                    .push();
             } else {
                 warn(ErrorKey::Datafunctions).msg(msg).loc(&code.name).push();
             }
@@ -5,18 +5,22 @@
 use std::any::Any;
 use std::fmt::Debug;
 use std::mem::take;
+use std::path::Path;
+use std::sync::RwLock;
 
 use as_any::AsAny;
 use itertools::Itertools;
 use rayon::prelude::*;
-use strum::EnumCount;
+use strum::{EnumCount, IntoEnumIterator};
 
 use crate::block::Block;
+use crate::compat::Overrides;
 use crate::context::ScopeContext;
 use crate::everything::Everything;
 use crate::helpers::{dup_error, exact_dup_advice, exact_dup_error, TigerHashMap, TigerHashSet};
 use crate::item::Item;
 use crate::lowercase::Lowercase;
+use crate::report::with_validating_item;
 use crate::token::Token;
 use crate::variables::Variables;
 
@@ -36,6 +40,15 @@ pub struct Db {
     anonymous: Vec<DbEntry>,
     /// Lowercased registry of database items and flags, for case insensitive lookups
     items_lc: Vec<TigerHashMap<Lowercase<'static>, &'static str>>,
+    /// Whether uses are recorded in `used`. Recording is off unless enabled, because every use
+    /// would otherwise take a lock.
+    track_used: bool,
+    /// The keys of items that are used somewhere, for the item types that track usage, with the
+    /// files they are used in. This covers items that are stored outside the `Db` too.
+    /// The `Vec` is indexed with an `Item` discriminant.
+    used: Vec<RwLock<TigerHashMap<String, TigerHashSet<&'static Path>>>>,
+    /// Definitions that override those of other mods, for the compatibility report.
+    overrides: Overrides,
 }
 
 impl Default for Db {
@@ -45,11 +58,18 @@ impl Default for Db {
             flags: (0..Item::COUNT).map(|_| (TigerHashSet::default(), None)).collect(),
             anonymous: Vec::new(),
             items_lc: (0..Item::COUNT).map(|_| TigerHashMap::default()).collect(),
+            track_used: false,
+            used: (0..Item::COUNT).map(|_| RwLock::new(TigerHashMap::default())).collect(),
+            overrides: Overrides::default(),
         }
     }
 }
 
 impl Db {
+    pub(crate) fn overrides(&self) -> &Overrides {
+        &self.overrides
+    }
+
     pub fn add(&mut self, item: Item, key: Token, block: Block, kind: Box<dyn DbKind>) {
         if let Some(other) = self.database[item as usize].get(key.as_str()) {
             if other.key.loc.kind >= key.loc.kind {
@@ -59,6 +79,7 @@ impl Db {
                     dup_error(&key, &other.key, &item.to_string());
                 }
             }
+            self.overrides.record(item, &other.key, &key);
         }
         self.items_lc[item as usize].insert(Lowercase::new(key.as_str()), key.as_str());
         self.database[item as usize].insert(key.as_str(), DbEntry { key, block, kind });
@@ -80,6 +101,7 @@ impl Db {
                     dup_error(&key, &other.key, &item.to_string());
                 }
             }
+            self.overrides.record(item, &other.key, &key);
         }
         self.items_lc[item as usize].insert(Lowercase::new(key.as_str()), key.as_str());
         self.database[item as usize].insert(key.as_str(), DbEntry { key, block, kind });
@@ -101,9 +123,18 @@ impl Db {
     }
 
     pub fn add_subitems(&mut self) {
+        self.add_subitems_filtered(|_| true);
+    }
+
+    /// Like [`Db::add_subitems`], but only for the items defined in files under `path`.
+    pub fn add_subitems_under(&mut self, path: &Path) {
+        self.add_subitems_filtered(|entry| entry.key.loc.pathname().starts_with(path));
+    }
+
+    fn add_subitems_filtered<F: Fn(&DbEntry) -> bool>(&mut self, f: F) {
         for itype in 0..Item::COUNT {
             let queue = take(&mut self.database[itype]);
-            for entry in queue.values().sorted_by_key(|e| e.key.loc) {
+            for entry in queue.values().filter(|e| f(e)).sorted_by_key(|e| e.key.loc) {
                 entry.kind.add_subitems(&entry.key, &entry.block, self);
             }
             if self.database[itype].is_empty() {
@@ -116,6 +147,20 @@ impl Db {
         }
     }
 
+    /// Forget all items and flags that were defined in files under `path`, so that those files
+    /// can be loaded again.
+    pub fn remove_under(&mut self, path: &Path) {
+        let under = |token: &Token| token.loc.pathname().starts_with(path);
+        for itype in 0..Item::COUNT {
+            self.database[itype].retain(|_, entry| !under(&entry.key));
+            self.flags[itype].0.retain(|flag| !under(flag));
+            let (database, flags) = (&self.database[itype], &self.flags[itype].0);
+            self.items_lc[itype]
+                .retain(|_, key| database.contains_key(key) || flags.contains(*key));
+        }
+        self.anonymous.retain(|entry| !under(&entry.key));
+    }
+
     pub fn scan_variables(&self, registry: &mut Variables) {
         for map in &self.database {
             for entry in map.values() {
@@ -125,15 +170,20 @@ impl Db {
     }
 
     pub fn validate(&self, data: &Everything) {
-        self.database.par_iter().for_each(|map| {
+        let items: Vec<Item> = Item::iter().collect();
+        self.database.par_iter().zip(&items).for_each(|(map, item)| {
             map.par_iter().for_each(|(_, entry)| {
-                entry.kind.validate(&entry.key, &entry.block, data);
+                with_validating_item(*item, || {
+                    data.call_graph.with_caller(*item, &entry.key, || {
+                        entry.kind.validate(&entry.key, &entry.block, data);
+                    });
+                });
             });
         });
-        self.flags.par_iter().for_each(|(map, fv)| {
+        self.flags.par_iter().zip(&items).for_each(|((map, fv), item)| {
             if let Some(fv) = fv {
                 map.par_iter().for_each(|flag| {
-                    fv(flag, data);
+                    with_validating_item(*item, || fv(flag, data));
                 });
             }
         });
@@ -142,6 +192,66 @@ impl Db {
         });
     }
 
+    /// Like [`Db::validate`], but only for the items and flags defined in the given files.
+    pub fn validate_files(&self, data: &Everything, files: &TigerHashSet<&Path>) {
+        let in_files = |token: &Token| files.contains(token.loc.pathname());
+        let items: Vec<Item> = Item::iter().collect();
+        self.database.par_iter().zip(&items).for_each(|(map, item)| {
+            map.par_iter().filter(|(_, entry)| in_files(&entry.key)).for_each(|(_, entry)| {
+                with_validating_item(*item, || {
+                    data.call_graph.with_caller(*item, &entry.key, || {
+                        entry.kind.validate(&entry.key, &entry.block, data);
+                    });
+                });
+            });
+        });
+        self.flags.par_iter().zip(&items).for_each(|((map, fv), item)| {
+            if let Some(fv) = fv {
+                map.par_iter().filter(|flag| in_files(flag)).for_each(|flag| {
+                    with_validating_item(*item, || fv(flag, data));
+                });
+            }
+        });
+        self.anonymous.par_iter().filter(|entry| in_files(&entry.key)).for_each(|entry| {
+            entry.kind.validate(&entry.key, &entry.block, data);
+        });
+    }
+
+    /// Start recording which items are used. See [`Db::mark_used`].
+    pub(crate) fn enable_used(&mut self) {
+        self.track_used = true;
+    }
+
+    /// Record that the item `key` of type `item` is used in the file `path`, if recording is
+    /// enabled. See [`Item::tracks_usage`].
+    pub fn mark_used(&self, item: Item, key: &str, path: &'static Path) {
+        if !self.track_used {
+            return;
+        }
+        let used = &self.used[item as usize];
+        if !used.read().unwrap().get(key).is_some_and(|paths| paths.contains(path)) {
+            used.write().unwrap().entry(key.to_owned()).or_default().insert(path);
+        }
+    }
+
+    pub(crate) fn is_tracking_used(&self) -> bool {
+        self.track_used
+    }
+
+    /// Forget the uses recorded in the given files, because they are about to be validated again.
+    pub(crate) fn forget_uses_in(&self, files: &TigerHashSet<&Path>) {
+        for used in &self.used {
+            used.write().unwrap().retain(|_, paths| {
+                paths.retain(|path| !files.contains(path));
+                !paths.is_empty()
+            });
+        }
+    }
+
+    pub fn is_used(&self, item: Item, key: &str) -> bool {
+        self.used[item as usize].read().unwrap().contains_key(key)
+    }
+
     pub fn exists(&self, item: Item, key: &str) -> bool {
         self.database[item as usize].contains_key(key) || self.flags[item as usize].0.contains(key)
     }
@@ -5,16 +5,42 @@ use std::io::{Read, Result};
 use std::path::PathBuf;
 
 use crate::fileset::{FileEntry, FileHandler};
+use crate::game::Game;
 use crate::helpers::TigerHashMap;
 use crate::parse::ParserMemory;
-use crate::report::{err, tips, warn, ErrorKey};
+use crate::report::{err, tips, untidy, warn, ErrorKey};
 #[cfg(feature = "ck3")]
 use crate::token::Token;
 
-const DDS_HEADER_SIZE: usize = 124;
+/// The size of the DDS header, including the `DDS ` magic number at the start.
+const DDS_HEADER_SIZE: usize = 128;
+/// The size of the extra header that follows the DDS header if the `FourCC` is `DX10`.
+const DDS_DX10_HEADER_SIZE: usize = 20;
+
+const DDS_SIZE_OFFSET: usize = 4;
+const DDS_FLAGS_OFFSET: usize = 8;
 const DDS_HEIGHT_OFFSET: usize = 12;
 const DDS_WIDTH_OFFSET: usize = 16;
+const DDS_MIPMAP_COUNT_OFFSET: usize = 28;
+const DDS_PIXELFORMAT_SIZE_OFFSET: usize = 76;
 const DDS_PIXELFORMAT_FLAGS_OFFSET: usize = 80;
+const DDS_PIXELFORMAT_FOURCC_OFFSET: usize = 84;
+const DDS_PIXELFORMAT_BITCOUNT_OFFSET: usize = 88;
+const DDS_CAPS2_OFFSET: usize = 112;
+
+const DX10_FORMAT_OFFSET: usize = 0;
+const DX10_MISC_FLAG_OFFSET: usize = 8;
+const DX10_ARRAY_SIZE_OFFSET: usize = 12;
+
+/// The header flag that says the mipmap count is valid.
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+/// The pixel format flag that says the `FourCC` is valid, which means the texture is compressed
+/// or uses the DX10 header.
+const DDPF_FOURCC: u32 = 0x4;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+/// The flags for the six faces of a cubemap.
+const DDSCAPS2_CUBEMAP_ALLFACES: u32 = 0xfc00;
+const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
 
 fn from_le32(buffer: &[u8], offset: usize) -> u32 {
     u32::from(buffer[offset])
@@ -36,7 +62,7 @@ impl DdsFiles {
         }
         let mut f = File::open(entry.fullpath())?;
         let mut buffer = [0; DDS_HEADER_SIZE];
-        f.read_exact(&mut buffer)?;
+        f.read_exact(&mut buffer[..4])?;
         if buffer.starts_with(b"\x89PNG") {
             let msg = "actually a PNG";
             let info =
@@ -48,7 +74,20 @@ impl DdsFiles {
             err(ErrorKey::ImageFormat).msg("not a DDS file").loc(entry).push();
             return Ok(None);
         }
-        Ok(Some(DdsInfo::new(entry.clone(), &buffer)))
+        f.read_exact(&mut buffer[4..])?;
+        if from_le32(&buffer, DDS_SIZE_OFFSET) != 124
+            || from_le32(&buffer, DDS_PIXELFORMAT_SIZE_OFFSET) != 32
+        {
+            err(ErrorKey::ImageFormat).msg("DDS file has a corrupted header").loc(entry).push();
+            return Ok(None);
+        }
+        let mut dx10 = [0; DDS_DX10_HEADER_SIZE];
+        let has_dx10 = from_le32(&buffer, DDS_PIXELFORMAT_FLAGS_OFFSET) & DDPF_FOURCC != 0
+            && &buffer[DDS_PIXELFORMAT_FOURCC_OFFSET..DDS_PIXELFORMAT_FOURCC_OFFSET + 4] == b"DX10";
+        if has_dx10 {
+            f.read_exact(&mut dx10)?;
+        }
+        Ok(Some(DdsInfo::new(entry.clone(), &buffer, has_dx10.then_some(&dx10[..]))))
     }
 
     fn handle_dds(&mut self, entry: &FileEntry, info: DdsInfo) {
@@ -107,26 +146,183 @@ impl FileHandler<DdsInfo> for DdsFiles {
     }
 }
 
+/// The pixel format of a DDS file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DdsFormat {
+    /// An uncompressed format described by bit masks, with this many bits per pixel.
+    Uncompressed(u32),
+    /// A format identified by its `FourCC` code.
+    FourCC([u8; 4]),
+    /// A format from the DX10 header, identified by its `DXGI_FORMAT` number.
+    Dxgi(u32),
+}
+
+impl DdsFormat {
+    fn is_compressed(self) -> bool {
+        match self {
+            DdsFormat::Uncompressed(_) => false,
+            DdsFormat::FourCC(fourcc) => fourcc_name(fourcc).is_some(),
+            DdsFormat::Dxgi(format) => dxgi_name(format).is_some_and(|name| name.starts_with("BC")),
+        }
+    }
+
+    fn describe(self) -> String {
+        match self {
+            DdsFormat::Uncompressed(bits) => format!("uncompressed {bits}-bit"),
+            DdsFormat::FourCC(fourcc) => fourcc_name(fourcc).map_or_else(
+                || format!("FourCC `{}`", String::from_utf8_lossy(&fourcc)),
+                str::to_owned,
+            ),
+            DdsFormat::Dxgi(format) => {
+                dxgi_name(format).map_or_else(|| format!("DXGI format {format}"), str::to_owned)
+            }
+        }
+    }
+}
+
+/// The block compression formats that can be identified by `FourCC` alone.
+fn fourcc_name(fourcc: [u8; 4]) -> Option<&'static str> {
+    match &fourcc {
+        b"DXT1" => Some("BC1 (DXT1)"),
+        b"DXT2" | b"DXT3" => Some("BC2 (DXT3)"),
+        b"DXT4" | b"DXT5" => Some("BC3 (DXT5)"),
+        b"ATI1" | b"BC4U" | b"BC4S" => Some("BC4"),
+        b"ATI2" | b"BC5U" | b"BC5S" => Some("BC5"),
+        _ => None,
+    }
+}
+
+/// The DXGI formats that the games are known to load from DDS files with a DX10 header.
+fn dxgi_name(format: u32) -> Option<&'static str> {
+    match format {
+        2 => Some("R32G32B32A32_FLOAT"),
+        10 => Some("R16G16B16A16_FLOAT"),
+        11 => Some("R16G16B16A16_UNORM"),
+        24 => Some("R10G10B10A2_UNORM"),
+        28 => Some("R8G8B8A8_UNORM"),
+        29 => Some("R8G8B8A8_UNORM_SRGB"),
+        34 => Some("R16G16_FLOAT"),
+        35 => Some("R16G16_UNORM"),
+        41 => Some("R32_FLOAT"),
+        49 => Some("R8G8_UNORM"),
+        54 => Some("R16_FLOAT"),
+        56 => Some("R16_UNORM"),
+        61 => Some("R8_UNORM"),
+        65 => Some("A8_UNORM"),
+        71 => Some("BC1_UNORM"),
+        72 => Some("BC1_UNORM_SRGB"),
+        74 => Some("BC2_UNORM"),
+        75 => Some("BC2_UNORM_SRGB"),
+        77 => Some("BC3_UNORM"),
+        78 => Some("BC3_UNORM_SRGB"),
+        80 => Some("BC4_UNORM"),
+        81 => Some("BC4_SNORM"),
+        83 => Some("BC5_UNORM"),
+        84 => Some("BC5_SNORM"),
+        87 => Some("B8G8R8A8_UNORM"),
+        88 => Some("B8G8R8X8_UNORM"),
+        91 => Some("B8G8R8A8_UNORM_SRGB"),
+        95 => Some("BC6H_UF16"),
+        96 => Some("BC6H_SF16"),
+        98 => Some("BC7_UNORM"),
+        99 => Some("BC7_UNORM_SRGB"),
+        _ => None,
+    }
+}
+
+/// The DXGI formats that only say how much memory a pixel takes, not how to read it.
+/// Textures must have a concrete format to be sampled.
+fn dxgi_is_typeless(format: u32) -> bool {
+    matches!(
+        format,
+        1 | 5
+            | 9
+            | 15
+            | 19
+            | 23
+            | 27
+            | 33
+            | 39
+            | 44
+            | 48
+            | 53
+            | 59
+            | 70
+            | 73
+            | 76
+            | 79
+            | 82
+            | 90
+            | 92
+            | 94
+            | 97
+    )
+}
+
 #[derive(Clone, Debug)]
 pub struct DdsInfo {
     entry: FileEntry,
-    compressed: bool,
+    format: DdsFormat,
     width: u32,
     height: u32,
+    /// The number of mipmap levels, including the full-size image. It is 1 if there are no
+    /// mipmaps.
+    mipmaps: u32,
+    /// Whether the file has a DX10 header.
+    dx10: bool,
+    /// Whether the file is a cubemap, and if so, whether it has all six faces.
+    cubemap: Option<bool>,
 }
 
 impl DdsInfo {
-    pub fn new(entry: FileEntry, header: &[u8]) -> Self {
+    /// Interpret the `header`, which is the DDS header including the magic number, and the
+    /// `dx10` header if there is one.
+    pub fn new(entry: FileEntry, header: &[u8], dx10: Option<&[u8]>) -> Self {
+        let pf_flags = from_le32(header, DDS_PIXELFORMAT_FLAGS_OFFSET);
+        let caps2 = from_le32(header, DDS_CAPS2_OFFSET);
+        let mut cubemap = (caps2 & DDSCAPS2_CUBEMAP != 0)
+            .then_some(caps2 & DDSCAPS2_CUBEMAP_ALLFACES == DDSCAPS2_CUBEMAP_ALLFACES);
+        let format = if let Some(dx10) = dx10 {
+            if from_le32(dx10, DX10_MISC_FLAG_OFFSET) & DDS_RESOURCE_MISC_TEXTURECUBE != 0 {
+                // In the DX10 header, all cubemaps have six faces per array element.
+                cubemap = Some(from_le32(dx10, DX10_ARRAY_SIZE_OFFSET) >= 1);
+            }
+            DdsFormat::Dxgi(from_le32(dx10, DX10_FORMAT_OFFSET))
+        } else if pf_flags & DDPF_FOURCC != 0 {
+            let offset = DDS_PIXELFORMAT_FOURCC_OFFSET;
+            DdsFormat::FourCC([
+                header[offset],
+                header[offset + 1],
+                header[offset + 2],
+                header[offset + 3],
+            ])
+        } else {
+            DdsFormat::Uncompressed(from_le32(header, DDS_PIXELFORMAT_BITCOUNT_OFFSET))
+        };
+        let mipmaps = if from_le32(header, DDS_FLAGS_OFFSET) & DDSD_MIPMAPCOUNT != 0 {
+            from_le32(header, DDS_MIPMAP_COUNT_OFFSET).max(1)
+        } else {
+            1
+        };
         Self {
             entry,
-            compressed: (from_le32(header, DDS_PIXELFORMAT_FLAGS_OFFSET) & 0x04) != 0,
+            format,
             width: from_le32(header, DDS_WIDTH_OFFSET),
             height: from_le32(header, DDS_HEIGHT_OFFSET),
+            mipmaps,
+            dx10: dx10.is_some(),
+            cubemap,
         }
     }
 
     fn validate(&self) {
-        if self.compressed && !(self.width % 4 == 0 || self.height % 4 == 0) {
+        if self.width == 0 || self.height == 0 {
+            let msg = format!("DDS file has size {}x{}", self.width, self.height);
+            err(ErrorKey::ImageSize).msg(msg).loc(&self.entry).push();
+            return;
+        }
+
+        if self.format.is_compressed() && !(self.width % 4 == 0 || self.height % 4 == 0) {
             let msg = "compressed DDS must have width and height divisible by 4";
             let info = format!(
                 "DDS file is {}x{}, which can cause scaling problems and graphical artifacts",
@@ -134,5 +330,203 @@ impl DdsInfo {
             );
             err(ErrorKey::ImageSize).msg(msg).info(info).loc(&self.entry).push();
         }
+
+        // Each mipmap level halves the size, down to 1x1.
+        let max_mipmaps = 32 - self.width.max(self.height).leading_zeros();
+        if self.mipmaps > max_mipmaps {
+            let msg = format!(
+                "DDS file has {} mipmap levels, but a {}x{} texture can have at most {max_mipmaps}",
+                self.mipmaps, self.width, self.height
+            );
+            let info = "the game may crash or show garbage when it reads the missing levels";
+            err(ErrorKey::ImageFormat).msg(msg).info(info).loc(&self.entry).push();
+        }
+
+        self.validate_format();
+        self.validate_usage();
+    }
+
+    fn validate_format(&self) {
+        if Game::is_hoi4() && self.dx10 {
+            let msg = format!("{} DDS file with a DX10 header", self.format.describe());
+            let info = "Hearts of Iron IV can only load DDS files without the DX10 header, so BC6H and BC7 are not supported. Save it as DXT1, DXT5, or uncompressed instead.";
+            err(ErrorKey::ImageFormat).msg(msg).info(info).loc(&self.entry).push();
+            return;
+        }
+        match self.format {
+            DdsFormat::Dxgi(format) if dxgi_is_typeless(format) => {
+                let msg = format!("DDS file has typeless DXGI format {format}");
+                let info = "the game can't read textures without a concrete format, such as BC7_UNORM instead of BC7_TYPELESS";
+                err(ErrorKey::ImageFormat).msg(msg).info(info).loc(&self.entry).push();
+            }
+            DdsFormat::Dxgi(format) if dxgi_name(format).is_none() => {
+                let msg =
+                    format!("DDS file has DXGI format {format}, which the game may not support");
+                let info = "save it as BC1, BC3, BC7, or R8G8B8A8_UNORM instead";
+                warn(ErrorKey::ImageFormat).weak().msg(msg).info(info).loc(&self.entry).push();
+            }
+            DdsFormat::FourCC(fourcc) if fourcc_name(fourcc).is_none() => {
+                let msg = format!(
+                    "DDS file has FourCC `{}`, which the game may not support",
+                    String::from_utf8_lossy(&fourcc).escape_debug()
+                );
+                let info = "save it as DXT1, DXT5, or uncompressed instead";
+                warn(ErrorKey::ImageFormat).weak().msg(msg).info(info).loc(&self.entry).push();
+            }
+            DdsFormat::Uncompressed(bits) if !matches!(bits, 8 | 16 | 24 | 32 | 64 | 128) => {
+                let msg = format!("DDS file has {bits} bits per pixel");
+                err(ErrorKey::ImageFormat).msg(msg).loc(&self.entry).push();
+            }
+            _ => (),
+        }
+    }
+
+    /// Check the format against what the texture is used for, as far as that can be told from
+    /// where the file is.
+    fn validate_usage(&self) {
+        let path = self.entry.path();
+        let filename = self.entry.filename().to_string_lossy().to_ascii_lowercase();
+        let in_dir = |name: &str| path.parent().is_some_and(|dir| dir.iter().any(|c| c == name));
+
+        if in_dir("icons") && !(self.width.is_power_of_two() && self.height.is_power_of_two()) {
+            let msg = format!(
+                "icon is {}x{}, which is not a power of two on each side",
+                self.width, self.height
+            );
+            let info = if self.mipmaps > 1 {
+                "the smaller mipmap levels may not line up with the full-size image, which can make the icon look blurry when scaled down"
+            } else {
+                "icons with power-of-two sizes scale more cleanly"
+            };
+            tips(ErrorKey::ImageSize).weak().msg(msg).info(info).loc(&self.entry).push();
+        }
+
+        if let Some(complete) = self.cubemap {
+            if !complete {
+                let msg = "cubemap does not have all six faces";
+                err(ErrorKey::ImageFormat).msg(msg).loc(&self.entry).push();
+            } else if self.width != self.height {
+                let msg =
+                    format!("cubemap faces are {}x{}, but must be square", self.width, self.height);
+                err(ErrorKey::ImageSize).msg(msg).loc(&self.entry).push();
+            }
+        } else if filename.contains("cubemap") {
+            let msg = "texture is named as a cubemap but is not one";
+            let info = "the game expects environment maps to be cubemaps, with the six faces in one DDS file";
+            err(ErrorKey::ImageFormat).weak().msg(msg).info(info).loc(&self.entry).push();
+        } else if in_dir("environment") || in_dir("environments") {
+            let msg = "environment texture is not a cubemap";
+            let info = "the game expects environment maps to be cubemaps, with the six faces in one DDS file";
+            warn(ErrorKey::ImageFormat).weak().msg(msg).info(info).loc(&self.entry).push();
+        }
+
+        if let DdsFormat::Uncompressed(bits) = self.format {
+            if bits >= 32 && in_dir("models") && self.width.max(self.height) > 512 {
+                let msg = format!("{}x{} model texture is uncompressed", self.width, self.height);
+                let info = "uncompressed textures take four to eight times as much video memory as BC1, BC3, or BC7, which can make the game run out of memory on weaker systems";
+                untidy(ErrorKey::ImageFormat).msg(msg).info(info).loc(&self.entry).push();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fileset::FileKind;
+    use crate::report::take_reports;
+    use crate::session::Session;
+
+    fn entry() -> FileEntry {
+        let path = PathBuf::from("gfx/interface/icons/test.dds");
+        FileEntry::new(path.clone(), FileKind::Mod, path)
+    }
+
+    /// Make a DDS header for a `width` by `height` texture with the given pixel format.
+    fn header(width: u32, height: u32, mipmaps: u32, pf_flags: u32, fourcc: [u8; 4]) -> Vec<u8> {
+        let mut header = vec![0; DDS_HEADER_SIZE];
+        let mut set = |offset: usize, value: u32| {
+            header[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        };
+        set(DDS_SIZE_OFFSET, 124);
+        set(DDS_FLAGS_OFFSET, if mipmaps > 0 { DDSD_MIPMAPCOUNT } else { 0 });
+        set(DDS_HEIGHT_OFFSET, height);
+        set(DDS_WIDTH_OFFSET, width);
+        set(DDS_MIPMAP_COUNT_OFFSET, mipmaps);
+        set(DDS_PIXELFORMAT_SIZE_OFFSET, 32);
+        set(DDS_PIXELFORMAT_FLAGS_OFFSET, pf_flags);
+        set(DDS_PIXELFORMAT_BITCOUNT_OFFSET, 32);
+        header[..4].copy_from_slice(b"DDS ");
+        header[DDS_PIXELFORMAT_FOURCC_OFFSET..DDS_PIXELFORMAT_FOURCC_OFFSET + 4]
+            .copy_from_slice(&fourcc);
+        header
+    }
+
+    fn messages(info: &DdsInfo) -> Vec<String> {
+        let session = Session::new().unwrap();
+        let reports = session.run(|| {
+            info.validate();
+            take_reports()
+        });
+        reports.into_keys().map(|report| report.msg).collect()
+    }
+
+    #[test]
+    fn test_dx10_bc7() {
+        let mut dx10 = [0; DDS_DX10_HEADER_SIZE];
+        dx10[DX10_FORMAT_OFFSET..DX10_FORMAT_OFFSET + 4].copy_from_slice(&98u32.to_le_bytes());
+        let header = header(64, 64, 7, DDPF_FOURCC, *b"DX10");
+        let info = DdsInfo::new(entry(), &header, Some(&dx10));
+        assert_eq!(info.format, DdsFormat::Dxgi(98));
+        assert_eq!(info.format.describe(), "BC7_UNORM");
+        assert!(info.format.is_compressed());
+        assert!(info.dx10);
+        assert_eq!((info.width, info.height, info.mipmaps), (64, 64, 7));
+        assert_eq!(info.cubemap, None);
+        assert!(messages(&info).is_empty());
+    }
+
+    #[test]
+    fn test_fourcc() {
+        let info = DdsInfo::new(entry(), &header(32, 16, 0, DDPF_FOURCC, *b"DXT5"), None);
+        assert_eq!(info.format, DdsFormat::FourCC(*b"DXT5"));
+        assert_eq!(info.format.describe(), "BC3 (DXT5)");
+        assert!(!info.dx10);
+        // Without the mipmap count flag, there is only the full-size image.
+        assert_eq!(info.mipmaps, 1);
+
+        let info = DdsInfo::new(entry(), &header(32, 32, 0, DDPF_FOURCC, *b"ABCD"), None);
+        assert!(!info.format.is_compressed());
+        let msg = "DDS file has FourCC `ABCD`, which the game may not support";
+        assert_eq!(messages(&info), [msg]);
+    }
+
+    #[test]
+    fn test_uncompressed() {
+        let info = DdsInfo::new(entry(), &header(256, 128, 9, 0, [0; 4]), None);
+        assert_eq!(info.format, DdsFormat::Uncompressed(32));
+        assert_eq!(info.format.describe(), "uncompressed 32-bit");
+        assert!(!info.format.is_compressed());
+        assert!(messages(&info).is_empty());
+    }
+
+    #[test]
+    fn test_cubemap_missing_faces() {
+        let mut header = header(128, 128, 0, DDPF_FOURCC, *b"DXT1");
+        // Only the positive x face.
+        let caps2 = DDSCAPS2_CUBEMAP | 0x400;
+        header[DDS_CAPS2_OFFSET..DDS_CAPS2_OFFSET + 4].copy_from_slice(&caps2.to_le_bytes());
+        let info = DdsInfo::new(entry(), &header, None);
+        assert_eq!(info.cubemap, Some(false));
+        assert_eq!(messages(&info), ["cubemap does not have all six faces"]);
+    }
+
+    #[test]
+    fn test_too_many_mipmaps() {
+        let info = DdsInfo::new(entry(), &header(16, 8, 6, DDPF_FOURCC, *b"DXT1"), None);
+        assert_eq!(info.mipmaps, 6);
+        let msg = "DDS file has 6 mipmap levels, but a 16x8 texture can have at most 5";
+        assert_eq!(messages(&info), [msg]);
     }
 }
@@ -126,6 +126,8 @@ pub fn validate_effect_field(
     tooltipped: Tooltipped,
 ) {
     if let Some(effect) = data.get_effect(key) {
+        data.mark_used_by(Item::ScriptedEffect, key.as_str(), key);
+        data.call_graph.record(Item::ScriptedEffect, key, "effect", None);
         match bv {
             BV::Value(token) => {
                 if !effect.macro_parms().is_empty() {
@@ -171,6 +173,7 @@ pub fn validate_effect_field(
     #[cfg(feature = "jomini")]
     if Game::is_jomini() {
         if let Some(modifier) = data.scripted_modifiers.get(key.as_str()) {
+            data.mark_used_by(Item::ScriptedModifier, key.as_str(), key);
             if caller != "random" && caller != "random_list" && caller != "duel" {
                 let msg = "cannot use scripted modifier here";
                 err(ErrorKey::Validation).msg(msg).loc(key).push();
@@ -1,6 +1,7 @@
 //! Validators for effects that are generic across multiple games.
 
 use crate::block::{Block, Comparator, Eq::Single, BV};
+use crate::callgraph::describe_delay;
 use crate::context::ScopeContext;
 use crate::desc::validate_desc;
 use crate::effect::{validate_effect, validate_effect_control};
@@ -299,12 +300,21 @@ pub fn validate_trigger_event(
     match bv {
         BV::Value(token) => {
             data.verify_exists(Item::Event, token);
+            data.call_graph.record(Item::Event, token, "trigger_event", None);
             data.event_check_scope(token, sc);
             if let Some(mut event_sc) = sc.root_for_event(token) {
                 data.event_validate_call(token, &mut event_sc);
             }
         }
         BV::Block(block) => {
+            // Record the calls with their delay before the validator records them without.
+            let delay = describe_delay(block);
+            if let Some(token) = block.get_field_value("id") {
+                data.call_graph.record(Item::Event, token, "trigger_event", delay.clone());
+            }
+            if let Some(token) = block.get_field_value("on_action") {
+                data.call_graph.record(Item::OnAction, token, "trigger_event", delay);
+            }
             let mut vd = Validator::new(block, data);
             vd.set_case_sensitive(false);
             vd.field_event("id", sc);
@@ -6,6 +6,7 @@
 use std::borrow::Cow;
 use std::fmt::Debug;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 #[cfg(any(feature = "ck3", feature = "vic3"))]
 use std::sync::RwLock;
 
@@ -17,6 +18,7 @@ use thiserror::Error;
 use crate::block::Block;
 #[cfg(any(feature = "ck3", feature = "vic3"))]
 use crate::block::BV;
+use crate::callgraph::CallGraph;
 #[cfg(feature = "ck3")]
 use crate::ck3::data::{
     characters::Characters,
@@ -35,7 +37,8 @@ use crate::ck3::data::{
 };
 #[cfg(feature = "ck3")]
 use crate::ck3::tables::misc::*;
-use crate::config_load::{check_for_legacy_ignore, load_filter};
+use crate::compat::CompatReport;
+use crate::config_load::{check_for_legacy_ignore, load_filter, load_severity_overrides};
 use crate::context::ScopeContext;
 #[cfg(any(feature = "ck3", feature = "vic3"))]
 use crate::data::data_binding::DataBindings;
@@ -55,9 +58,8 @@ use crate::data::{
 };
 use crate::db::{Db, DbKind};
 use crate::dds::DdsFiles;
-use crate::fileset::{FileEntry, FileKind, Fileset};
+use crate::fileset::{handles_any, FileEntry, FileKind, Fileset};
 use crate::game::Game;
-#[cfg(any(feature = "ck3", feature = "vic3"))]
 use crate::helpers::TigerHashSet;
 #[cfg(feature = "hoi4")]
 use crate::hoi4::data::{
@@ -70,16 +72,26 @@ use crate::imperator::data::{decisions::Decisions, provinces::ImperatorProvinces
 #[cfg(feature = "imperator")]
 use crate::imperator::tables::misc::*;
 use crate::item::{Item, ItemLoader};
+use crate::launcher_settings::get_version_from_launcher;
 use crate::lowercase::Lowercase;
 use crate::macros::MACRO_MAP;
+#[cfg(feature = "jomini")]
+use crate::map_images::check_map_images;
 #[cfg(feature = "vic3")]
 use crate::parse::json::parse_json_file;
 use crate::parse::ParserMemory;
 use crate::pdxfile::PdxFile;
+use crate::playset::Playset;
 #[cfg(any(feature = "ck3", feature = "vic3"))]
 use crate::report::err;
-use crate::report::{report, set_output_style, ErrorKey, OutputStyle, Severity};
+use crate::report::{
+    check_unused_ignores, files_with_reports_naming, forget_ignore_filters, forget_previous_load,
+    forget_reports_in, report, set_game_version, set_output_style, with_validating_item, ErrorKey,
+    OutputStyle, Severity,
+};
+use crate::river_graph::RiverGraph;
 use crate::rivers::Rivers;
+use crate::signatures::{ScriptSignature, ScriptSignatures};
 use crate::token::{Loc, Token};
 use crate::variables::Variables;
 #[cfg(feature = "vic3")]
@@ -89,6 +101,7 @@ use crate::vic3::data::{
 };
 #[cfg(feature = "vic3")]
 use crate::vic3::tables::misc::*;
+use crate::xref::{XrefIndex, XrefRecorder};
 
 #[derive(Debug, Error)]
 #[allow(clippy::enum_variant_names)]
@@ -101,6 +114,11 @@ pub enum FilesError {
     ConfigUnreadable { path: PathBuf },
 }
 
+/// The number of [`Everything`] instances that exist, so that `MACRO_MAP`, which they share, is
+/// only cleared when the last one is dropped.
+#[allow(clippy::mutex_atomic)] // the lock is held while clearing MACRO_MAP
+static LIVE_EVERYTHINGS: Mutex<usize> = Mutex::new(0);
+
 /// A record of everything known about the game and mod being validated.
 ///
 /// References to [`Everything`] are passed down through nearly all of the validation logic, so
@@ -221,6 +239,19 @@ pub struct Everything {
     pub(crate) wars: Wars,
 
     pub(crate) variables: Variables,
+
+    /// The calls between events, `on_actions`, and other items, recorded during validation.
+    pub(crate) call_graph: CallGraph,
+
+    /// The uses of items found during validation, if enabled with [`Everything::enable_xref`].
+    xref: XrefRecorder,
+
+    /// The files that were reloaded by the most recent [`Everything::reload_files`].
+    changed_files: Vec<FileEntry>,
+
+    /// The files that use items defined in the `changed_files`, according to the uses recorded
+    /// before the most recent [`Everything::reload_files`]. They were reloaded too.
+    dependent_files: Vec<FileEntry>,
 }
 
 impl Everything {
@@ -241,6 +272,31 @@ impl Everything {
         mod_root: &Path,
         replace_paths: Vec<PathBuf>,
     ) -> Result<Self> {
+        Self::new_with_playset(
+            config_filepath,
+            vanilla_dir,
+            workshop_dir,
+            paradox_dir,
+            mod_root,
+            replace_paths,
+            None,
+        )
+    }
+
+    /// Like [`Everything::new`], but also load the mods of a playset from the Paradox launcher
+    /// before the mod. They are loaded after the mods from `load_mod` in the config, if any.
+    #[allow(clippy::missing_panics_doc)] // only panics on poisoned mutex
+    pub fn new_with_playset(
+        config_filepath: Option<&Path>,
+        vanilla_dir: Option<&Path>,
+        workshop_dir: Option<&Path>,
+        paradox_dir: Option<&Path>,
+        mod_root: &Path,
+        replace_paths: Vec<PathBuf>,
+        playset: Option<&Playset>,
+    ) -> Result<Self> {
+        forget_previous_load();
+        set_game_version(vanilla_dir.and_then(|dir| get_version_from_launcher(dir).ok()));
         let mut fileset = Fileset::new(vanilla_dir, mod_root.to_path_buf(), replace_paths);
 
         let config_file_name = match Game::game() {
@@ -267,10 +323,14 @@ impl Everything {
         };
 
         fileset.config(config.clone(), workshop_dir, paradox_dir)?;
+        if let Some(playset) = playset {
+            fileset.load_playset(playset)?;
+        }
 
         fileset.scan_all()?;
         fileset.finalize();
 
+        *LIVE_EVERYTHINGS.lock().unwrap() += 1;
         Ok(Everything {
             parser: ParserMemory::default(),
             fileset,
@@ -343,6 +403,10 @@ impl Everything {
             #[cfg(feature = "ck3")]
             wars: Wars::default(),
             variables: Variables::new(),
+            call_graph: CallGraph::default(),
+            xref: XrefRecorder::default(),
+            changed_files: Vec::new(),
+            dependent_files: Vec::new(),
         })
     }
 
@@ -354,6 +418,7 @@ impl Everything {
     pub fn load_config_filtering_rules(&self) {
         check_for_legacy_ignore(&self.config);
         load_filter(&self.config);
+        load_severity_overrides(&self.config);
     }
 
     /// Load the `OutputStyle` settings from the config.
@@ -596,7 +661,10 @@ impl Everything {
             Game::Hoi4 => self.load_all_hoi4(),
         }
         self.database.add_subitems();
+        self.scan_all();
+    }
 
+    fn scan_all(&mut self) {
         self.scan_all_generic();
         match Game::game() {
             #[cfg(feature = "ck3")]
@@ -613,7 +681,7 @@ impl Everything {
 
     fn validate_all_generic<'a>(&'a self, s: &Scope<'a>) {
         s.spawn(|_| self.fileset.validate(self));
-        s.spawn(|_| self.defines.validate(self));
+        spawn_tagged(s, Item::Define, || self.defines.validate(self));
         s.spawn(|_| self.triggers.validate(self));
         s.spawn(|_| self.effects.validate(self));
         s.spawn(|_| self.assets.validate(self));
@@ -625,25 +693,27 @@ impl Everything {
     #[cfg(feature = "ck3")]
     fn validate_all_ck3<'a>(&'a self, s: &Scope<'a>) {
         s.spawn(|_| self.events.validate(self));
-        s.spawn(|_| self.interaction_cats.validate(self));
+        spawn_tagged(s, Item::CharacterInteractionCategory, || {
+            self.interaction_cats.validate(self);
+        });
         s.spawn(|_| self.province_histories.validate(self));
         s.spawn(|_| self.province_properties.validate(self));
         s.spawn(|_| self.province_terrains.validate(self));
-        s.spawn(|_| self.gameconcepts.validate(self));
-        s.spawn(|_| self.titles.validate(self));
-        s.spawn(|_| self.characters.validate(self));
-        s.spawn(|_| self.traits.validate(self));
-        s.spawn(|_| self.title_history.validate(self));
-        s.spawn(|_| self.doctrines.validate(self));
-        s.spawn(|_| self.menatarmstypes.validate(self));
+        spawn_tagged(s, Item::GameConcept, || self.gameconcepts.validate(self));
+        spawn_tagged(s, Item::Title, || self.titles.validate(self));
+        spawn_tagged(s, Item::Character, || self.characters.validate(self));
+        spawn_tagged(s, Item::Trait, || self.traits.validate(self));
+        spawn_tagged(s, Item::TitleHistory, || self.title_history.validate(self));
+        spawn_tagged(s, Item::Doctrine, || self.doctrines.validate(self));
+        spawn_tagged(s, Item::MenAtArms, || self.menatarmstypes.validate(self));
         s.spawn(|_| self.data_bindings.validate(self));
         s.spawn(|_| self.provinces_ck3.validate(self));
         s.spawn(|_| self.wars.validate(self));
-        s.spawn(|_| self.coas.validate(self));
-        s.spawn(|_| self.scripted_lists.validate(self));
-        s.spawn(|_| self.scripted_modifiers.validate(self));
-        s.spawn(|_| self.script_values.validate(self));
-        s.spawn(|_| self.music.validate(self));
+        spawn_tagged(s, Item::Coa, || self.coas.validate(self));
+        spawn_tagged(s, Item::ScriptedList, || self.scripted_lists.validate(self));
+        spawn_tagged(s, Item::ScriptedModifier, || self.scripted_modifiers.validate(self));
+        spawn_tagged(s, Item::ScriptValue, || self.script_values.validate(self));
+        spawn_tagged(s, Item::Music, || self.music.validate(self));
         s.spawn(|_| Climate::validate_all(&self.database, self));
     }
 
@@ -653,11 +723,11 @@ impl Everything {
         s.spawn(|_| self.history.validate(self));
         s.spawn(|_| self.provinces_vic3.validate(self));
         s.spawn(|_| self.data_bindings.validate(self));
-        s.spawn(|_| self.coas.validate(self));
-        s.spawn(|_| self.scripted_lists.validate(self));
-        s.spawn(|_| self.scripted_modifiers.validate(self));
-        s.spawn(|_| self.script_values.validate(self));
-        s.spawn(|_| self.music.validate(self));
+        spawn_tagged(s, Item::Coa, || self.coas.validate(self));
+        spawn_tagged(s, Item::ScriptedList, || self.scripted_lists.validate(self));
+        spawn_tagged(s, Item::ScriptedModifier, || self.scripted_modifiers.validate(self));
+        spawn_tagged(s, Item::ScriptValue, || self.script_values.validate(self));
+        spawn_tagged(s, Item::Music, || self.music.validate(self));
         s.spawn(|_| StrategicRegion::crosscheck(self));
         s.spawn(|_| BuyPackage::crosscheck(self));
     }
@@ -665,21 +735,21 @@ impl Everything {
     #[cfg(feature = "imperator")]
     fn validate_all_imperator<'a>(&'a self, s: &Scope<'a>) {
         s.spawn(|_| self.events.validate(self));
-        s.spawn(|_| self.decisions_imperator.validate(self));
+        spawn_tagged(s, Item::Decision, || self.decisions_imperator.validate(self));
         s.spawn(|_| self.provinces_imperator.validate(self));
-        s.spawn(|_| self.coas.validate(self));
-        s.spawn(|_| self.scripted_lists.validate(self));
-        s.spawn(|_| self.scripted_modifiers.validate(self));
-        s.spawn(|_| self.script_values.validate(self));
-        s.spawn(|_| self.music.validate(self));
+        spawn_tagged(s, Item::Coa, || self.coas.validate(self));
+        spawn_tagged(s, Item::ScriptedList, || self.scripted_lists.validate(self));
+        spawn_tagged(s, Item::ScriptedModifier, || self.scripted_modifiers.validate(self));
+        spawn_tagged(s, Item::ScriptValue, || self.script_values.validate(self));
+        spawn_tagged(s, Item::Music, || self.music.validate(self));
     }
 
     #[cfg(feature = "hoi4")]
     fn validate_all_hoi4<'a>(&'a self, s: &Scope<'a>) {
-        s.spawn(|_| self.events_hoi4.validate(self));
+        spawn_tagged(s, Item::Event, || self.events_hoi4.validate(self));
         s.spawn(|_| self.provinces_hoi4.validate(self));
         s.spawn(|_| self.gfx.validate(self));
-        s.spawn(|_| self.music_hoi4.validate(self));
+        spawn_tagged(s, Item::Music, || self.music_hoi4.validate(self));
     }
 
     pub fn validate_all(&self) {
@@ -701,10 +771,555 @@ impl Everything {
         self.localization.validate_pass2(self);
     }
 
+    /// Record which events and scripted items are used during validation, so that
+    /// [`Everything::check_unused`] can report the ones that aren't.
+    /// This must be called before validation.
+    pub fn enable_unused(&mut self) {
+        self.database.enable_used();
+    }
+
+    /// Record the calls between items during validation, for [`Everything::call_graph`].
+    /// This must be called before validation. It also lets [`Everything::check_unused`] report
+    /// items that are only used by other unused items.
+    pub fn enable_call_graph(&mut self) {
+        self.call_graph.enable();
+    }
+
+    /// The calls between events, `on_actions`, scripted effects, and other items that were found
+    /// during validation. It is only complete after [`Everything::validate_all`], and empty unless
+    /// [`Everything::enable_call_graph`] was called before it.
+    pub fn call_graph(&self) -> &CallGraph {
+        &self.call_graph
+    }
+
+    /// Collect what each scripted effect, scripted trigger, and script value expects of its
+    /// callers, as inferred during validation. Only useful after [`Everything::validate_all`].
+    pub fn script_signatures(&self) -> ScriptSignatures {
+        let mut vec = Vec::new();
+        for effect in self.effects.iter() {
+            vec.push(ScriptSignature {
+                itype: Item::ScriptedEffect,
+                key: effect.key.clone(),
+                expectations: effect.expectations(),
+                macro_parms: effect.macro_parms(),
+                scope_override: effect.has_scope_override(),
+            });
+        }
+        for trigger in self.triggers.iter() {
+            vec.push(ScriptSignature {
+                itype: Item::ScriptedTrigger,
+                key: trigger.key.clone(),
+                expectations: trigger.expectations(),
+                macro_parms: trigger.macro_parms(),
+                scope_override: trigger.has_scope_override(),
+            });
+        }
+        #[cfg(feature = "jomini")]
+        for value in self.script_values.iter() {
+            vec.push(ScriptSignature {
+                itype: Item::ScriptValue,
+                key: value.key().clone(),
+                expectations: value.expectations(),
+                macro_parms: Vec::new(),
+                scope_override: value.has_scope_override(),
+            });
+        }
+        ScriptSignatures::new(vec)
+    }
+
+    /// Collect the ways in which the mods of the playset override each other: the mods loaded
+    /// with `load_mod` in the config, and the mod itself. Only useful after [`Everything::load_all`].
+    pub fn compat_report(&self) -> CompatReport {
+        let mut report = CompatReport::new(self.fileset.loaded_mods_and_mod());
+        report.add_overridden_files(self.fileset.overridden_files());
+        report.add_replaced_files(self.fileset.replaced_files());
+        #[allow(unused_mut)] // hoi4 doesn't add to it
+        let mut overrides = vec![
+            self.database.overrides(),
+            self.localization.overrides(),
+            self.triggers.overrides(),
+            self.effects.overrides(),
+        ];
+        #[cfg(feature = "jomini")]
+        overrides.extend([
+            self.events.overrides(),
+            self.scripted_lists.overrides(),
+            self.scripted_modifiers.overrides(),
+            self.script_values.overrides(),
+        ]);
+        report.add_overrides(overrides);
+        report
+    }
+
+    /// Record where items are used during validation, for [`Everything::xref_index`].
+    /// This must be called before validation.
+    pub fn enable_xref(&mut self) {
+        self.xref.enable();
+    }
+
+    /// Build an index of where all items are defined and, if [`Everything::enable_xref`] was
+    /// called before validation, where they are used.
+    pub fn xref_index(&self) -> XrefIndex {
+        let mut index = XrefIndex::default();
+        for itype in Item::iter() {
+            for token in self.iter_keys(itype) {
+                index.add_definition(itype, token);
+            }
+        }
+        for ((itype, key), uses) in self.xref.uses() {
+            index.add_uses(itype, key, uses);
+        }
+        index
+    }
+
+    /// Reload the given changed files, without rereading the rest of the game and mod files.
+    ///
+    /// `changed` holds full filesystem paths. Only the item types and file handlers whose
+    /// directories contain one of the changed files are reloaded; their other files are reread
+    /// too, because items in them may have been overridden by the changed files.
+    ///
+    /// If [`Everything::enable_xref`] was called before the first validation, the files that used
+    /// the items defined in the changed files are reloaded as well, so that
+    /// [`Everything::validate_changed`] can validate them again.
+    ///
+    /// Returns false if the changes can't be handled incrementally, for example because a file
+    /// was added or removed or the config file changed. Then the caller should create a new
+    /// `Everything` instead.
+    pub fn reload_files(&mut self, changed: &[PathBuf]) -> bool {
+        let mut entries = Vec::new();
+        for fullpath in changed {
+            if fullpath == self.config.loc.fullpath() || !fullpath.is_file() {
+                return false;
+            }
+            let Some(entry) = self.fileset.entry_for_fullpath(fullpath) else {
+                return false;
+            };
+            // The reader_export files affect how all other files are parsed.
+            if entry.path().starts_with("reader_export") {
+                return false;
+            }
+            entries.push(entry.clone());
+        }
+
+        let changed_paths: TigerHashSet<&Path> = entries.iter().map(FileEntry::path).collect();
+        let mut changed_items = self.items_defined_in(&changed_paths);
+        self.reload_entries(&entries);
+
+        // Items that were removed or renamed count as changed too, so the items from before the
+        // reload are kept. Items that are new can only have been used in reports about them
+        // being unknown.
+        let new_items = self.items_defined_in(&changed_paths);
+        let new_keys = new_items
+            .iter()
+            .filter(|item| !changed_items.contains(item))
+            .map(|(_, key)| *key)
+            .collect();
+        changed_items.extend(new_items);
+        let mut dependents = self.xref.files_using(&changed_items);
+        dependents.extend(files_with_reports_naming(&new_keys));
+        dependents.retain(|path| !changed_paths.contains(path));
+        let dependents = self.fileset.entries_for_paths(&dependents);
+        // The files that use the changed items are read again too, so that all the reports about
+        // them are made again.
+        if !dependents.is_empty() {
+            self.reload_entries(&dependents);
+        }
+
+        self.variables = Variables::new();
+        self.scan_all();
+        self.changed_files = entries;
+        self.dependent_files = dependents;
+        true
+    }
+
+    /// Forget the reports about the given files, then reload them together with the other files
+    /// of the item types and file handlers whose directories contain them.
+    fn reload_entries(&mut self, entries: &[FileEntry]) {
+        // The reports and `tiger-ignore` directives for these files will be made again while
+        // reading them.
+        let paths: TigerHashSet<&Path> = entries.iter().map(FileEntry::path).collect();
+        forget_reports_in(&paths);
+        for path in &paths {
+            forget_ignore_filters(path);
+        }
+
+        // Find the item directories that are affected, then forget everything that was loaded
+        // from them before loading them again. This has to be done in two steps because several
+        // loaders may share a directory.
+        let touched = |path: &Path| entries.iter().any(|entry| entry.path().starts_with(path));
+        let mut reload_paths: Vec<PathBuf> = Vec::new();
+        for loader in inventory::iter::<ItemLoader> {
+            let path = PathBuf::from(loader.itype().path());
+            if loader.for_game(Game::game()) && touched(&path) && !reload_paths.contains(&path) {
+                reload_paths.push(path);
+            }
+        }
+        #[cfg(feature = "vic3")]
+        if touched(Path::new(Item::TerrainMask.path())) {
+            reload_paths.push(PathBuf::from(Item::TerrainMask.path()));
+        }
+        for path in &reload_paths {
+            self.database.remove_under(path);
+        }
+        for loader in inventory::iter::<ItemLoader> {
+            let path = PathBuf::from(loader.itype().path());
+            if loader.for_game(Game::game()) && reload_paths.iter().any(|p| path.starts_with(p)) {
+                self.load_pdx_files(loader);
+            }
+        }
+        #[cfg(feature = "vic3")]
+        if reload_paths.iter().any(|p| Path::new(Item::TerrainMask.path()).starts_with(p)) {
+            self.load_json(Item::TerrainMask, TerrainMask::add_json);
+        }
+        for path in &reload_paths {
+            self.database.add_subitems_under(path);
+        }
+        #[cfg(feature = "ck3")]
+        if reload_paths.iter().any(|p| Path::new(Item::Building.path()).starts_with(p)) {
+            crate::ck3::data::buildings::Building::finalize(&mut self.database);
+        }
+
+        self.reload_handlers_generic(entries);
+        match Game::game() {
+            #[cfg(feature = "ck3")]
+            Game::Ck3 => self.reload_handlers_ck3(entries),
+            #[cfg(feature = "vic3")]
+            Game::Vic3 => self.reload_handlers_vic3(entries),
+            #[cfg(feature = "imperator")]
+            Game::Imperator => self.reload_handlers_imperator(entries),
+            #[cfg(feature = "hoi4")]
+            Game::Hoi4 => self.reload_handlers_hoi4(entries),
+        }
+    }
+
+    /// Return the items defined in the given files, if the uses of items are being recorded.
+    /// Otherwise there is no use for them.
+    fn items_defined_in(&self, files: &TigerHashSet<&Path>) -> TigerHashSet<(Item, &'static str)> {
+        let mut items = TigerHashSet::default();
+        if self.xref.is_enabled() {
+            for itype in Item::iter() {
+                for token in self.iter_keys(itype) {
+                    if files.contains(token.loc.pathname()) {
+                        items.insert((itype, token.as_str()));
+                    }
+                }
+            }
+        }
+        items
+    }
+
+    fn reload_handlers_generic(&mut self, changed: &[FileEntry]) {
+        let (fileset, parser) = (&self.fileset, &self.parser);
+        fileset.rehandle(&mut self.dds, parser, changed);
+        fileset.rehandle(&mut self.localization, parser, changed);
+        fileset.rehandle(&mut self.defines, parser, changed);
+        fileset.rehandle(&mut self.triggers, parser, changed);
+        fileset.rehandle(&mut self.effects, parser, changed);
+        fileset.rehandle(&mut self.assets, parser, changed);
+        fileset.rehandle(&mut self.gui, parser, changed);
+        fileset.rehandle(&mut self.on_actions, parser, changed);
+    }
+
+    #[cfg(feature = "ck3")]
+    fn reload_handlers_ck3(&mut self, changed: &[FileEntry]) {
+        let (fileset, parser) = (&self.fileset, &self.parser);
+        fileset.rehandle(&mut self.events, parser, changed);
+        fileset.rehandle(&mut self.interaction_cats, parser, changed);
+        fileset.rehandle(&mut self.province_histories, parser, changed);
+        fileset.rehandle(&mut self.province_properties, parser, changed);
+        fileset.rehandle(&mut self.province_terrains, parser, changed);
+        fileset.rehandle(&mut self.gameconcepts, parser, changed);
+        fileset.rehandle(&mut self.titles, parser, changed);
+        fileset.rehandle(&mut self.characters, parser, changed);
+        fileset.rehandle(&mut self.traits, parser, changed);
+        fileset.rehandle(&mut self.title_history, parser, changed);
+        fileset.rehandle(&mut self.doctrines, parser, changed);
+        fileset.rehandle(&mut self.menatarmstypes, parser, changed);
+        fileset.rehandle(&mut self.music, parser, changed);
+        fileset.rehandle(&mut self.data_bindings, parser, changed);
+        fileset.rehandle(&mut self.provinces_ck3, parser, changed);
+        fileset.rehandle(&mut self.scripted_lists, parser, changed);
+        fileset.rehandle(&mut self.wars, parser, changed);
+        fileset.rehandle(&mut self.coas, parser, changed);
+        fileset.rehandle(&mut self.scripted_modifiers, parser, changed);
+        fileset.rehandle(&mut self.script_values, parser, changed);
+    }
+
+    #[cfg(feature = "vic3")]
+    fn reload_handlers_vic3(&mut self, changed: &[FileEntry]) {
+        let (fileset, parser) = (&self.fileset, &self.parser);
+        fileset.rehandle(&mut self.events, parser, changed);
+        fileset.rehandle(&mut self.history, parser, changed);
+        fileset.rehandle(&mut self.provinces_vic3, parser, changed);
+        fileset.rehandle(&mut self.data_bindings, parser, changed);
+        fileset.rehandle(&mut self.coas, parser, changed);
+        fileset.rehandle(&mut self.scripted_lists, parser, changed);
+        fileset.rehandle(&mut self.scripted_modifiers, parser, changed);
+        fileset.rehandle(&mut self.script_values, parser, changed);
+        fileset.rehandle(&mut self.music, parser, changed);
+    }
+
+    #[cfg(feature = "imperator")]
+    fn reload_handlers_imperator(&mut self, changed: &[FileEntry]) {
+        let (fileset, parser) = (&self.fileset, &self.parser);
+        fileset.rehandle(&mut self.events, parser, changed);
+        fileset.rehandle(&mut self.decisions_imperator, parser, changed);
+        fileset.rehandle(&mut self.provinces_imperator, parser, changed);
+        fileset.rehandle(&mut self.coas, parser, changed);
+        fileset.rehandle(&mut self.scripted_lists, parser, changed);
+        fileset.rehandle(&mut self.scripted_modifiers, parser, changed);
+        fileset.rehandle(&mut self.script_values, parser, changed);
+        fileset.rehandle(&mut self.music, parser, changed);
+    }
+
+    #[cfg(feature = "hoi4")]
+    fn reload_handlers_hoi4(&mut self, changed: &[FileEntry]) {
+        let (fileset, parser) = (&self.fileset, &self.parser);
+        fileset.rehandle(&mut self.events_hoi4, parser, changed);
+        fileset.rehandle(&mut self.gfx, parser, changed);
+        fileset.rehandle(&mut self.provinces_hoi4, parser, changed);
+        fileset.rehandle(&mut self.music_hoi4, parser, changed);
+    }
+
+    /// Validate what was changed by the most recent [`Everything::reload_files`]: the items
+    /// defined in the changed files, and all the items of file handlers that were reloaded.
+    ///
+    /// If [`Everything::enable_xref`] was called before the first validation, the files that use
+    /// items defined in the changed files are validated again too. Otherwise reports about their
+    /// use of the changed items are not refreshed; use [`Everything::validate_all`] for that.
+    ///
+    /// The stored reports that pointed into these files were forgotten when they were reloaded,
+    /// so together with [`set_keep_reports`](crate::set_keep_reports) the stored reports stay a
+    /// full set.
+    pub fn validate_changed(&self) {
+        let changed: Vec<FileEntry> =
+            self.changed_files.iter().chain(&self.dependent_files).cloned().collect();
+        let files: TigerHashSet<&Path> = changed.iter().map(FileEntry::path).collect();
+        self.forget_calls_in(&files);
+        self.xref.forget_uses_in(&files);
+        self.database.forget_uses_in(&files);
+        let changed = &changed[..];
+        scope(|s| {
+            s.spawn(|_| self.fileset.validate(self));
+            self.validate_changed_generic(s, changed);
+            match Game::game() {
+                #[cfg(feature = "ck3")]
+                Game::Ck3 => self.validate_changed_ck3(s, changed),
+                #[cfg(feature = "vic3")]
+                Game::Vic3 => self.validate_changed_vic3(s, changed),
+                #[cfg(feature = "imperator")]
+                Game::Imperator => self.validate_changed_imperator(s, changed),
+                #[cfg(feature = "hoi4")]
+                Game::Hoi4 => self.validate_changed_hoi4(s, changed),
+            }
+        });
+        self.database.validate_files(self, &files);
+
+        self.localization.validate_pass2(self);
+    }
+
+    /// Forget the cached validations made for the given files, so that validating them again
+    /// makes the same reports again.
+    fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        self.triggers.forget_calls_in(files);
+        self.effects.forget_calls_in(files);
+        #[cfg(feature = "jomini")]
+        if Game::is_jomini() {
+            self.scripted_lists.forget_calls_in(files);
+            self.scripted_modifiers.forget_calls_in(files);
+            self.script_values.forget_calls_in(files);
+            self.events.forget_calls_in(files);
+        }
+        #[cfg(feature = "hoi4")]
+        if Game::is_hoi4() {
+            self.events_hoi4.forget_calls_in(files);
+        }
+    }
+
+    fn validate_changed_generic<'a>(&'a self, s: &Scope<'a>, changed: &'a [FileEntry]) {
+        if handles_any(&self.defines, changed) {
+            spawn_tagged(s, Item::Define, || self.defines.validate(self));
+        }
+        if handles_any(&self.triggers, changed) {
+            s.spawn(|_| self.triggers.validate(self));
+        }
+        if handles_any(&self.effects, changed) {
+            s.spawn(|_| self.effects.validate(self));
+        }
+        if handles_any(&self.assets, changed) {
+            s.spawn(|_| self.assets.validate(self));
+        }
+        if handles_any(&self.gui, changed) {
+            s.spawn(|_| self.gui.validate(self));
+        }
+        if handles_any(&self.on_actions, changed) {
+            s.spawn(|_| self.on_actions.validate(self));
+        }
+        if handles_any(&self.dds, changed) {
+            s.spawn(|_| self.dds.validate());
+        }
+    }
+
+    #[cfg(feature = "ck3")]
+    fn validate_changed_ck3<'a>(&'a self, s: &Scope<'a>, changed: &'a [FileEntry]) {
+        if handles_any(&self.events, changed) {
+            s.spawn(|_| self.events.validate(self));
+        }
+        if handles_any(&self.interaction_cats, changed) {
+            spawn_tagged(s, Item::CharacterInteractionCategory, || {
+                self.interaction_cats.validate(self);
+            });
+        }
+        if handles_any(&self.province_histories, changed) {
+            s.spawn(|_| self.province_histories.validate(self));
+        }
+        if handles_any(&self.province_properties, changed) {
+            s.spawn(|_| self.province_properties.validate(self));
+        }
+        if handles_any(&self.province_terrains, changed) {
+            s.spawn(|_| self.province_terrains.validate(self));
+        }
+        if handles_any(&self.gameconcepts, changed) {
+            spawn_tagged(s, Item::GameConcept, || self.gameconcepts.validate(self));
+        }
+        if handles_any(&self.titles, changed) {
+            spawn_tagged(s, Item::Title, || self.titles.validate(self));
+        }
+        if handles_any(&self.characters, changed) {
+            spawn_tagged(s, Item::Character, || self.characters.validate(self));
+        }
+        if handles_any(&self.traits, changed) {
+            spawn_tagged(s, Item::Trait, || self.traits.validate(self));
+        }
+        if handles_any(&self.title_history, changed) {
+            spawn_tagged(s, Item::TitleHistory, || self.title_history.validate(self));
+        }
+        if handles_any(&self.doctrines, changed) {
+            spawn_tagged(s, Item::Doctrine, || self.doctrines.validate(self));
+        }
+        if handles_any(&self.menatarmstypes, changed) {
+            spawn_tagged(s, Item::MenAtArms, || self.menatarmstypes.validate(self));
+        }
+        if handles_any(&self.data_bindings, changed) {
+            s.spawn(|_| self.data_bindings.validate(self));
+        }
+        if handles_any(&self.provinces_ck3, changed) {
+            s.spawn(|_| self.provinces_ck3.validate(self));
+        }
+        if handles_any(&self.wars, changed) {
+            s.spawn(|_| self.wars.validate(self));
+        }
+        if handles_any(&self.coas, changed) {
+            spawn_tagged(s, Item::Coa, || self.coas.validate(self));
+        }
+        if handles_any(&self.scripted_lists, changed) {
+            spawn_tagged(s, Item::ScriptedList, || self.scripted_lists.validate(self));
+        }
+        if handles_any(&self.scripted_modifiers, changed) {
+            spawn_tagged(s, Item::ScriptedModifier, || self.scripted_modifiers.validate(self));
+        }
+        if handles_any(&self.script_values, changed) {
+            spawn_tagged(s, Item::ScriptValue, || self.script_values.validate(self));
+        }
+        if handles_any(&self.music, changed) {
+            spawn_tagged(s, Item::Music, || self.music.validate(self));
+        }
+        s.spawn(|_| Climate::validate_all(&self.database, self));
+    }
+
+    #[cfg(feature = "vic3")]
+    fn validate_changed_vic3<'a>(&'a self, s: &Scope<'a>, changed: &'a [FileEntry]) {
+        if handles_any(&self.events, changed) {
+            s.spawn(|_| self.events.validate(self));
+        }
+        if handles_any(&self.history, changed) {
+            s.spawn(|_| self.history.validate(self));
+        }
+        if handles_any(&self.provinces_vic3, changed) {
+            s.spawn(|_| self.provinces_vic3.validate(self));
+        }
+        if handles_any(&self.data_bindings, changed) {
+            s.spawn(|_| self.data_bindings.validate(self));
+        }
+        if handles_any(&self.coas, changed) {
+            spawn_tagged(s, Item::Coa, || self.coas.validate(self));
+        }
+        if handles_any(&self.scripted_lists, changed) {
+            spawn_tagged(s, Item::ScriptedList, || self.scripted_lists.validate(self));
+        }
+        if handles_any(&self.scripted_modifiers, changed) {
+            spawn_tagged(s, Item::ScriptedModifier, || self.scripted_modifiers.validate(self));
+        }
+        if handles_any(&self.script_values, changed) {
+            spawn_tagged(s, Item::ScriptValue, || self.script_values.validate(self));
+        }
+        if handles_any(&self.music, changed) {
+            spawn_tagged(s, Item::Music, || self.music.validate(self));
+        }
+        s.spawn(|_| StrategicRegion::crosscheck(self));
+        s.spawn(|_| BuyPackage::crosscheck(self));
+    }
+
+    #[cfg(feature = "imperator")]
+    fn validate_changed_imperator<'a>(&'a self, s: &Scope<'a>, changed: &'a [FileEntry]) {
+        if handles_any(&self.events, changed) {
+            s.spawn(|_| self.events.validate(self));
+        }
+        if handles_any(&self.decisions_imperator, changed) {
+            spawn_tagged(s, Item::Decision, || self.decisions_imperator.validate(self));
+        }
+        if handles_any(&self.provinces_imperator, changed) {
+            s.spawn(|_| self.provinces_imperator.validate(self));
+        }
+        if handles_any(&self.coas, changed) {
+            spawn_tagged(s, Item::Coa, || self.coas.validate(self));
+        }
+        if handles_any(&self.scripted_lists, changed) {
+            spawn_tagged(s, Item::ScriptedList, || self.scripted_lists.validate(self));
+        }
+        if handles_any(&self.scripted_modifiers, changed) {
+            spawn_tagged(s, Item::ScriptedModifier, || self.scripted_modifiers.validate(self));
+        }
+        if handles_any(&self.script_values, changed) {
+            spawn_tagged(s, Item::ScriptValue, || self.script_values.validate(self));
+        }
+        if handles_any(&self.music, changed) {
+            spawn_tagged(s, Item::Music, || self.music.validate(self));
+        }
+    }
+
+    #[cfg(feature = "hoi4")]
+    fn validate_changed_hoi4<'a>(&'a self, s: &Scope<'a>, changed: &'a [FileEntry]) {
+        if handles_any(&self.events_hoi4, changed) {
+            spawn_tagged(s, Item::Event, || self.events_hoi4.validate(self));
+        }
+        if handles_any(&self.provinces_hoi4, changed) {
+            s.spawn(|_| self.provinces_hoi4.validate(self));
+        }
+        if handles_any(&self.gfx, changed) {
+            s.spawn(|_| self.gfx.validate(self));
+        }
+        if handles_any(&self.music_hoi4, changed) {
+            spawn_tagged(s, Item::Music, || self.music_hoi4.validate(self));
+        }
+    }
+
     pub fn check_rivers(&mut self) {
         let mut rivers = Rivers::default();
         self.fileset.handle(&mut rivers, &self.parser);
         rivers.validate(self);
+        #[cfg(feature = "jomini")]
+        if Game::is_jomini() {
+            check_map_images(self, &rivers);
+        }
+    }
+
+    /// Build the graph of the river network from the rivers image, or `None` if it can't be
+    /// loaded.
+    pub fn river_graph(&self) -> Option<RiverGraph> {
+        let mut rivers = Rivers::default();
+        self.fileset.handle(&mut rivers, &self.parser);
+        RiverGraph::new(&rivers)
     }
 
     #[cfg(feature = "ck3")]
@@ -717,6 +1332,54 @@ impl Everything {
     pub fn check_unused(&mut self) {
         self.localization.check_unused(self);
         self.fileset.check_unused_dds(self);
+        self.check_unused_items();
+        check_unused_ignores();
+    }
+
+    /// Report the events and scripted code that nothing uses. See [`Item::tracks_usage`].
+    /// If the call graph was recorded, items that are only used by other unused items are reported
+    /// too, with a weaker report. Nothing is reported unless [`Everything::enable_unused`] was
+    /// called before validation.
+    fn check_unused_items(&self) {
+        if !self.database.is_tracking_used() {
+            return;
+        }
+        let reachable = self.call_graph.is_enabled().then(|| self.call_graph.reachable_items());
+        let is_reachable = |itype, key: &Token| match &reachable {
+            Some(reachable) => reachable.contains(&(itype, key.to_string())),
+            None => self.database.is_used(itype, key.as_str()),
+        };
+        for itype in Item::iter().filter(|itype| itype.tracks_usage()) {
+            let mut vec: Vec<_> =
+                self.iter_keys(itype).filter(|key| !is_reachable(itype, key)).collect();
+            vec.sort_unstable_by_key(|key| key.loc);
+            for key in vec {
+                if self.database.is_used(itype, key.as_str()) {
+                    let msg = format!("{itype} `{key}` is only used by unused items");
+                    let info = "nothing that is itself used leads to it";
+                    report(ErrorKey::UnusedItem, Severity::Untidy)
+                        .weak()
+                        .msg(msg)
+                        .info(info)
+                        .loc(key)
+                        .push();
+                    continue;
+                }
+                let msg = format!("{itype} `{key}` is never used");
+                if itype == Item::Event {
+                    // Events can also be fired from the interface or by the game engine itself.
+                    let info = "nothing in the script fires this event";
+                    report(ErrorKey::UnusedItem, Severity::Untidy)
+                        .weak()
+                        .msg(msg)
+                        .info(info)
+                        .loc(key)
+                        .push();
+                } else {
+                    report(ErrorKey::UnusedItem, Severity::Untidy).msg(msg).loc(key).push();
+                }
+            }
+        }
     }
 
     #[allow(dead_code)]
@@ -949,10 +1612,22 @@ impl Everything {
             Item::Localization => {
                 self.localization.mark_used_return_exists(key);
             }
+            // Without a token there is no file to record, so this use is never forgotten.
+            _ if itype.tracks_usage() => self.database.mark_used(itype, key, Path::new("")),
             _ => (),
         }
     }
 
+    /// Record that `token` refers to the item `key` of type `itype`, both for `--unused` and for
+    /// the cross-reference index.
+    pub(crate) fn mark_used_by(&self, itype: Item, key: &str, token: &Token) {
+        if itype.tracks_usage() {
+            self.database.mark_used(itype, key, token.loc.pathname());
+            self.call_graph.record_use(itype, key, token);
+        }
+        self.xref.record(itype, key, token);
+    }
+
     pub(crate) fn verify_exists(&self, itype: Item, token: &Token) {
         self.verify_exists_implied(itype, token.as_str(), token);
     }
@@ -968,6 +1643,7 @@ impl Everything {
         token: &Token,
         max_sev: Severity,
     ) {
+        self.mark_used_by(itype, key, token);
         match itype {
             Item::Entry => self.fileset.verify_entry_exists(key, token, max_sev),
             Item::File => self.fileset.verify_exists_implied(key, token, max_sev),
@@ -1347,10 +2023,21 @@ impl Everything {
     }
 }
 
+/// Spawn a file handler's validation, with its reports marked as made while validating items of
+/// type `itype`.
+fn spawn_tagged<'a>(s: &Scope<'a>, itype: Item, f: impl FnOnce() + Send + 'a) {
+    s.spawn(move |_| with_validating_item(itype, f));
+}
+
 impl Drop for Everything {
     fn drop(&mut self) {
-        // For the sake of the benchmark code, restore MACRO_MAP to a clean slate
-        MACRO_MAP.clear();
+        // For the sake of the benchmark code, restore MACRO_MAP to a clean slate.
+        // Other instances, such as in concurrent sessions, may still be using it.
+        let mut live = LIVE_EVERYTHINGS.lock().unwrap();
+        *live -= 1;
+        if *live == 0 {
+            MACRO_MAP.clear();
+        }
     }
 }
 
@@ -23,6 +23,7 @@ use crate::mod_metadata::ModMetadata;
 use crate::modfile::ModFile;
 use crate::parse::ParserMemory;
 use crate::pathtable::{PathTable, PathTableIndex};
+use crate::playset::Playset;
 use crate::report::{
     add_loaded_dlc_root, add_loaded_mod_root, err, fatal, report, ErrorKey, Severity,
 };
@@ -57,6 +58,11 @@ impl FileKind {
             FileKind::Internal | FileKind::LoadedMod(_) | FileKind::Mod => false,
         }
     }
+
+    /// Returns true iff this is the mod under scrutiny or one of the other loaded mods.
+    pub fn is_mod(&self) -> bool {
+        matches!(self, FileKind::LoadedMod(_) | FileKind::Mod)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -109,6 +115,17 @@ impl FileEntry {
     }
 }
 
+/// A file of one mod that is not loaded because a later mod overrides it.
+#[derive(Clone, Debug)]
+pub struct FileOverride {
+    /// Pathname components below the mod directories.
+    pub path: PathBuf,
+    /// The mod whose file is not loaded.
+    pub loser: FileKind,
+    /// The mod that has a file with the same path, or that replaces the file's directory.
+    pub winner: FileKind,
+}
+
 impl Display for FileEntry {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), std::fmt::Error> {
         write!(fmt, "{}", self.path.display())
@@ -164,13 +181,18 @@ pub trait FileHandler<T: Send>: Sync + Send {
     fn finalize(&mut self) {}
 }
 
+/// Returns true iff any of the `entries` is in the directory that `handler` processes.
+pub fn handles_any<T: Send, H: FileHandler<T>>(handler: &H, entries: &[FileEntry]) -> bool {
+    let subpath = handler.subpath();
+    entries.iter().any(|entry| entry.path.starts_with(&subpath))
+}
+
 #[derive(Clone, Debug)]
 pub struct LoadedMod {
     /// The `FileKind` to use for file entries from this mod.
     kind: FileKind,
 
     /// The tag used for this mod in error messages.
-    #[allow(dead_code)]
     label: String,
 
     /// The location of this mod in the filesystem.
@@ -197,6 +219,10 @@ impl LoadedMod {
         self.kind
     }
 
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
     pub fn should_replace(&self, path: &Path) -> bool {
         self.replace_paths.iter().any(|p| p == path)
     }
@@ -245,6 +271,13 @@ pub struct Fileset {
 
     /// Filenames that have been looked up during validation. Used to filter the --unused output.
     used: RwLock<TigerHashSet<String>>,
+
+    /// Files of mods that are hidden by files with the same path in later mods.
+    overridden_files: Vec<FileOverride>,
+
+    /// Files of mods that are not loaded because a later mod has their directory as a
+    /// `replace_path`.
+    replaced_files: Vec<FileOverride>,
 }
 
 impl Fileset {
@@ -275,6 +308,8 @@ impl Fileset {
             filenames: TigerHashSet::default(),
             directories: RwLock::new(TigerHashSet::default()),
             used: RwLock::new(TigerHashSet::default()),
+            overridden_files: Vec::new(),
+            replaced_files: Vec::new(),
         }
     }
 
@@ -286,13 +321,7 @@ impl Fileset {
     ) -> Result<()> {
         let config_path = config.loc.fullpath();
         for block in config.get_field_blocks("load_mod") {
-            let mod_idx;
-            if let Ok(idx) = u8::try_from(self.loaded_mods.len()) {
-                mod_idx = idx;
-            } else {
-                bail!("too many loaded mods, cannot process more");
-            }
-
+            let mod_idx = self.next_mod_idx()?;
             let default_label = || format!("MOD{mod_idx}");
             let label =
                 block.get_field_value("label").map_or_else(default_label, ToString::to_string);
@@ -300,56 +329,14 @@ impl Fileset {
             if Game::is_ck3() || Game::is_imperator() || Game::is_hoi4() {
                 #[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
                 if let Some(path) = get_modfile(&label, config_path, block, paradox_dir) {
-                    let modfile = ModFile::read(&path)?;
-                    eprintln!(
-                        "Loading secondary mod {label} from: {}{}",
-                        modfile.modpath().display(),
-                        modfile
-                            .display_name()
-                            .map_or_else(String::new, |name| format!(" \"{name}\"")),
-                    );
-                    let kind = FileKind::LoadedMod(mod_idx);
-                    let loaded_mod = LoadedMod::new(
-                        kind,
-                        label.clone(),
-                        modfile.modpath().clone(),
-                        modfile.replace_paths(),
-                    );
-                    add_loaded_mod_root(label);
-                    self.loaded_mods.push(loaded_mod);
+                    self.load_modfile(label, &path)?;
                 } else {
                     bail!("could not load secondary mod from config; missing valid `modfile` or `workshop_id` field");
                 }
             } else if Game::is_vic3() {
                 #[cfg(feature = "vic3")]
                 if let Some(pathdir) = get_mod(&label, config_path, block, workshop_dir) {
-                    match ModMetadata::read(&pathdir) {
-                        Ok(metadata) => {
-                            eprintln!(
-                                "Loading secondary mod {label} from: {}{}",
-                                pathdir.display(),
-                                metadata
-                                    .display_name()
-                                    .map_or_else(String::new, |name| format!(" \"{name}\"")),
-                            );
-                            let kind = FileKind::LoadedMod(mod_idx);
-                            let loaded_mod = LoadedMod::new(
-                                kind,
-                                label.clone(),
-                                pathdir,
-                                metadata.replace_paths(),
-                            );
-                            add_loaded_mod_root(label);
-                            self.loaded_mods.push(loaded_mod);
-                        }
-                        Err(e) => {
-                            eprintln!(
-                                "could not load secondary mod {label} from: {}",
-                                pathdir.display()
-                            );
-                            eprintln!("  because: {e}");
-                        }
-                    }
+                    self.load_metadata(label, pathdir)?;
                 } else {
                     bail!("could not load secondary mod from config; missing valid `mod` or `workshop_id` field");
                 }
@@ -359,19 +346,107 @@ impl Fileset {
         Ok(())
     }
 
-    fn should_replace(&self, path: &Path, kind: FileKind) -> bool {
-        if kind == FileKind::Mod {
-            return false;
+    /// Load the enabled mods of a playset from the Paradox launcher, in their load order, as if
+    /// they were given with `load_mod` in the config. Their labels are the mods' names.
+    ///
+    /// The mod under scrutiny is skipped if it is in the playset. It is always loaded last.
+    pub fn load_playset(&mut self, playset: &Playset) -> Result<()> {
+        let the_mod_root = self.the_mod.root.canonicalize().ok();
+        let mut after_the_mod = Vec::new();
+        let mut seen_the_mod = false;
+        for playset_mod in &playset.mods {
+            if playset_mod.path.canonicalize().ok() == the_mod_root {
+                seen_the_mod = true;
+                continue;
+            }
+            if seen_the_mod {
+                after_the_mod.push(playset_mod.name.as_str());
+            }
+            let mod_idx = self.next_mod_idx()?;
+            let label = if playset_mod.name.is_empty()
+                || self.loaded_mods.iter().any(|loaded_mod| loaded_mod.label == playset_mod.name)
+            {
+                format!("MOD{mod_idx}")
+            } else {
+                playset_mod.name.clone()
+            };
+
+            if Game::is_ck3() || Game::is_imperator() || Game::is_hoi4() {
+                #[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
+                self.load_modfile(label, &playset_mod.path.join("descriptor.mod"))?;
+            } else if Game::is_vic3() {
+                #[cfg(feature = "vic3")]
+                self.load_metadata(label, playset_mod.path.clone())?;
+            }
         }
-        if kind < FileKind::Mod && self.the_mod.should_replace(path) {
-            return true;
+        if !after_the_mod.is_empty() {
+            eprintln!(
+                "These mods come after the mod in playset \"{}\", but are loaded before it: {}",
+                playset.name,
+                after_the_mod.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    fn next_mod_idx(&self) -> Result<u8> {
+        if let Ok(idx) = u8::try_from(self.loaded_mods.len()) {
+            Ok(idx)
+        } else {
+            bail!("too many loaded mods, cannot process more");
         }
-        for loaded_mod in &self.loaded_mods {
-            if kind < loaded_mod.kind && loaded_mod.should_replace(path) {
-                return true;
+    }
+
+    /// Load a secondary mod described by the `.mod` file at `path`.
+    #[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
+    fn load_modfile(&mut self, label: String, path: &Path) -> Result<()> {
+        let modfile = ModFile::read(path)?;
+        eprintln!(
+            "Loading secondary mod {label} from: {}{}",
+            modfile.modpath().display(),
+            modfile.display_name().map_or_else(String::new, |name| format!(" \"{name}\"")),
+        );
+        let kind = FileKind::LoadedMod(self.next_mod_idx()?);
+        let loaded_mod =
+            LoadedMod::new(kind, label.clone(), modfile.modpath().clone(), modfile.replace_paths());
+        add_loaded_mod_root(label);
+        self.loaded_mods.push(loaded_mod);
+        Ok(())
+    }
+
+    /// Load a secondary mod from the directory `pathdir`, which has the mod's metadata.
+    #[cfg(feature = "vic3")]
+    fn load_metadata(&mut self, label: String, pathdir: PathBuf) -> Result<()> {
+        match ModMetadata::read(&pathdir) {
+            Ok(metadata) => {
+                eprintln!(
+                    "Loading secondary mod {label} from: {}{}",
+                    pathdir.display(),
+                    metadata.display_name().map_or_else(String::new, |name| format!(" \"{name}\"")),
+                );
+                let kind = FileKind::LoadedMod(self.next_mod_idx()?);
+                let loaded_mod =
+                    LoadedMod::new(kind, label.clone(), pathdir, metadata.replace_paths());
+                add_loaded_mod_root(label);
+                self.loaded_mods.push(loaded_mod);
+            }
+            Err(e) => {
+                eprintln!("could not load secondary mod {label} from: {}", pathdir.display());
+                eprintln!("  because: {e}");
             }
         }
-        false
+        Ok(())
+    }
+
+    /// Return the mods that are loaded after `kind` and have the directory `path` as a
+    /// `replace_path`. Files of `kind` in that directory should not be loaded.
+    fn replaced_by(&self, path: &Path, kind: FileKind) -> Vec<FileKind> {
+        self.loaded_mods
+            .iter()
+            .chain(std::iter::once(&self.the_mod))
+            .filter(|loaded_mod| kind < loaded_mod.kind && loaded_mod.should_replace(path))
+            .map(LoadedMod::kind)
+            .collect()
     }
 
     fn scan(&mut self, path: &Path, kind: FileKind) -> Result<(), walkdir::Error> {
@@ -386,7 +461,14 @@ impl Fileset {
                 continue;
             }
             let inner_dir = inner_path.parent().unwrap_or_else(|| Path::new(""));
-            if self.should_replace(inner_dir, kind) {
+            let replaced_by = self.replaced_by(inner_dir, kind);
+            if !replaced_by.is_empty() {
+                if kind.is_mod() {
+                    for winner in replaced_by {
+                        let path = inner_path.to_path_buf();
+                        self.replaced_files.push(FileOverride { path, loser: kind, winner });
+                    }
+                }
                 continue;
             }
             self.files.push(FileEntry::new(
@@ -463,16 +545,29 @@ impl Fileset {
         self.files.sort();
 
         // When there are identical paths, only keep the last entry of them.
+        // Remember which mods had the path, to track the files that mods override in each other.
+        let mut mods_with_path = Vec::new();
         for entry in self.files.drain(..) {
+            let kind = entry.kind;
             if let Some(prev) = self.ordered_files.last_mut() {
                 if entry.path == prev.path {
+                    if kind.is_mod() {
+                        for &loser in &mods_with_path {
+                            let path = entry.path.clone();
+                            self.overridden_files.push(FileOverride { path, loser, winner: kind });
+                        }
+                    }
                     *prev = entry;
                 } else {
+                    mods_with_path.clear();
                     self.ordered_files.push(entry);
                 }
             } else {
                 self.ordered_files.push(entry);
             }
+            if kind.is_mod() {
+                mods_with_path.push(kind);
+            }
         }
 
         for entry in &mut self.ordered_files {
@@ -498,6 +593,32 @@ impl Fileset {
         self.get_files_under(subpath).par_iter().filter_map(f).collect()
     }
 
+    /// Find the entry for a file given its full filesystem path.
+    pub fn entry_for_fullpath(&self, fullpath: &Path) -> Option<&FileEntry> {
+        self.ordered_files.iter().find(|entry| entry.fullpath == fullpath)
+    }
+
+    /// Find the entries for the given paths, which are relative to the game or mod root.
+    pub fn entries_for_paths(&self, paths: &TigerHashSet<&Path>) -> Vec<FileEntry> {
+        self.ordered_files.iter().filter(|entry| paths.contains(entry.path())).cloned().collect()
+    }
+
+    /// Reset the handler and let it process its files again, if any of the `changed` entries are
+    /// in its directory. Returns true iff the handler was reloaded.
+    pub fn rehandle<T: Send, H: FileHandler<T> + Default>(
+        &self,
+        handler: &mut H,
+        parser: &ParserMemory,
+        changed: &[FileEntry],
+    ) -> bool {
+        if !handles_any(handler, changed) {
+            return false;
+        }
+        *handler = H::default();
+        self.handle(handler, parser);
+        true
+    }
+
     pub fn handle<T: Send, H: FileHandler<T>>(&self, handler: &mut H, parser: &ParserMemory) {
         if let Some(config) = &self.config {
             handler.config(config);
@@ -512,6 +633,21 @@ impl Fileset {
         handler.finalize();
     }
 
+    /// Return the mods being loaded, in load order, ending with the mod under scrutiny.
+    pub fn loaded_mods_and_mod(&self) -> impl Iterator<Item = &LoadedMod> {
+        self.loaded_mods.iter().chain(std::iter::once(&self.the_mod))
+    }
+
+    /// Files of mods that are hidden by files with the same path in later mods.
+    pub fn overridden_files(&self) -> &[FileOverride] {
+        &self.overridden_files
+    }
+
+    /// Files of mods that are not loaded because a later mod replaces their directory.
+    pub fn replaced_files(&self) -> &[FileOverride] {
+        &self.replaced_files
+    }
+
     pub fn mark_used(&self, file: &str) {
         let file = file.strip_prefix('/').unwrap_or(file);
         self.used.write().unwrap().insert(file.to_string());
@@ -3,9 +3,10 @@ use ahash::{HashMap, HashSet, RandomState};
 use bimap::BiHashMap;
 
 use std::fmt::{Display, Formatter};
+use std::path::Path;
 use std::str::FromStr;
 
-use crate::report::{tips, warn, ErrorKey};
+use crate::report::{definition_end, tips, warn, ErrorKey};
 #[cfg(feature = "hoi4")]
 use crate::scopes::Scopes;
 use crate::token::Token;
@@ -31,13 +32,16 @@ pub fn dup_error(key: &Token, other: &Token, id: &str) {
         .push();
 }
 
-/// Warns about an exact redefinition of a database item
+/// Warns about an exact redefinition of a database item, and suggests removing the redefinition
 pub fn exact_dup_error(key: &Token, other: &Token, id: &str) {
-    warn(ErrorKey::ExactDuplicateItem)
+    let report = warn(ErrorKey::ExactDuplicateItem)
         .msg(format!("{id} is redefined by an identical {id}"))
         .loc(other)
-        .loc_msg(key, format!("the other {id} is here"))
-        .push();
+        .loc_msg(key, format!("the other {id} is here"));
+    match definition_end(key) {
+        Some(end) => report.fix_until(end, "").push(),
+        None => report.push(),
+    }
 }
 
 /// Warns about a redefinition of a database item, but only at "advice" level
@@ -235,3 +239,28 @@ pub fn camel_case_to_separated_words(s: &str) -> String {
     }
     temp_s
 }
+
+/// Percent-encode a path for use in a URI, keeping the `/` separators.
+pub fn encode_uri_path(path: &str) -> String {
+    const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/:".contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push('%');
+            encoded.push(char::from(HEX_DIGITS[usize::from(byte >> 4)]));
+            encoded.push(char::from(HEX_DIGITS[usize::from(byte & 0xf)]));
+        }
+    }
+    encoded
+}
+
+/// Convert an absolute path to a `file://` URI. Windows paths get a leading `/` and forward slashes.
+pub fn path_to_uri(path: &Path) -> String {
+    let mut path = path.to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        path.insert(0, '/');
+    }
+    format!("file://{}", encode_uri_path(&path))
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Mutex;
 
@@ -94,6 +94,16 @@ impl Hoi4Events {
         self.events.values().map(|item| &item.key)
     }
 
+    /// Forget which scope contexts the events in the given files were validated in, so that they
+    /// are validated again.
+    pub fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        for item in self.events.values() {
+            if files.contains(item.key.loc.pathname()) {
+                item.visited.lock().unwrap().clear();
+            }
+        }
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.events.values() {
             item.validate(data);
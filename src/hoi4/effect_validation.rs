@@ -262,7 +262,7 @@ pub fn validate_clr_flag(
 ) {
     if key.is("clr_unit_leader_flag") {
         let msg = "deprecated in favor of clr_character_flag";
-        warn(ErrorKey::Deprecated).msg(msg).loc(key).push();
+        warn(ErrorKey::Deprecated).msg(msg).loc(key).fix("clr_character_flag").push();
     }
 
     validate_flag_name(vd.value());
@@ -279,7 +279,7 @@ pub fn validate_modify_flag(
 ) {
     if key.is("modify_unit_leader_flag") {
         let msg = "deprecated in favor of modify_character_flag";
-        warn(ErrorKey::Deprecated).msg(msg).loc(key).push();
+        warn(ErrorKey::Deprecated).msg(msg).loc(key).fix("modify_character_flag").push();
     }
 
     vd.req_field("flag");
@@ -296,7 +296,7 @@ pub fn validate_set_flag(
 ) {
     if key.is("set_unit_leader_flag") {
         let msg = "deprecated in favor of set_character_flag";
-        warn(ErrorKey::Deprecated).msg(msg).loc(key).push();
+        warn(ErrorKey::Deprecated).msg(msg).loc(key).fix("set_character_flag").push();
     }
 
     match bv {
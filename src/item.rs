@@ -1841,6 +1841,18 @@ impl Item {
         }
     }
 
+    /// Whether items of this type are reported by `--unused` if nothing uses them.
+    /// Uses of these items must be recorded with `Everything::mark_used`, which is already done
+    /// by `Everything::verify_exists`.
+    pub fn tracks_usage(self) -> bool {
+        match self {
+            Item::Event | Item::ScriptedEffect | Item::ScriptedTrigger => true,
+            #[cfg(feature = "jomini")]
+            Item::ScriptValue | Item::ScriptedModifier => true,
+            _ => false,
+        }
+    }
+
     /// Confidence value to use when reporting that an item is missing.
     /// Should be `Strong` for most, `Weak` for items that aren't defined anywhere but just used (such as gfx flags).
     pub fn confidence(self) -> Confidence {
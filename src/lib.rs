@@ -17,23 +17,34 @@ compile_error!(
     "exactly one of the features \"ck3\", \"vic3\", \"imperator\", \"hoi4\" must be enabled"
 );
 
+pub use crate::callgraph::{CallEdge, CallGraph, CallNode};
+pub use crate::compat::{CompatReport, ItemOverride, ModConflicts};
 pub use crate::config_load::validate_config_file;
 pub use crate::everything::Everything;
 pub use crate::fileset::FileKind;
 pub use crate::game::Game;
-pub use crate::helpers::{TigerHashMap, TigerHashSet};
+pub use crate::helpers::{path_to_uri, TigerHashMap, TigerHashSet};
 pub use crate::item::Item;
 pub use crate::launcher_settings::get_version_from_launcher;
 #[cfg(feature = "vic3")]
 pub use crate::mod_metadata::ModMetadata;
 #[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
 pub use crate::modfile::ModFile;
+pub use crate::playset::{Playset, PlaysetMod, LAUNCHER_DATABASE};
 pub use crate::report::{
-    add_loaded_mod_root, disable_ansi_colors, emit_reports, log, set_output_style,
-    set_show_loaded_mods, set_show_vanilla, suppress_from_json, take_reports, Confidence,
-    LogReportMetadata, LogReportPointers, PointedMessage, Severity,
+    add_loaded_mod_root, applicable_fix, apply_fixes, baseline_summary, diff_reports,
+    disable_ansi_colors, emit_reports, emit_reports_sarif, emit_summary, load_baseline, log,
+    report_stats, saved_reports_from_json, set_explain, set_keep_reports, set_macro_trace,
+    set_output_style, set_show_loaded_mods, set_show_vanilla, suppress_from_json, take_reports,
+    take_saved_reports, take_sorted_reports, write_baseline, BaselineSummary, Confidence,
+    EmitSummary, ErrorKey, FixSummary, LogReport, LogReportMetadata, LogReportPointers,
+    PointedMessage, ReportDiff, ReportStats, SavedLocation, SavedReport, Severity,
 };
+pub use crate::river_graph::{RiverGraph, RiverNode, RiverNodeKind, RiverProblem, RiverSegment};
+pub use crate::session::Session;
+pub use crate::signatures::ScriptSignatures;
 pub use crate::token::{Loc, Token};
+pub use crate::xref::{XrefEntry, XrefIndex};
 
 #[cfg(feature = "internal_benches")]
 mod benches;
@@ -48,6 +59,8 @@ mod imperator;
 mod vic3;
 
 mod block;
+mod callgraph;
+mod compat;
 mod config_load;
 mod context;
 mod data;
@@ -69,6 +82,8 @@ mod item;
 mod launcher_settings;
 mod lowercase;
 mod macros;
+#[cfg(feature = "jomini")]
+mod map_images;
 #[cfg(feature = "vic3")]
 mod mod_metadata;
 #[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
@@ -78,11 +93,17 @@ mod on_action;
 mod parse;
 mod pathtable;
 mod pdxfile;
+mod playset;
+#[cfg(feature = "jomini")]
+mod province_map;
 mod report;
+mod river_graph;
 mod rivers;
 mod scopes;
 #[cfg(feature = "jomini")]
 mod script_value;
+mod session;
+mod signatures;
 mod token;
 mod tooltipped;
 mod trigger;
@@ -90,3 +111,4 @@ mod util;
 mod validate;
 mod validator;
 mod variables;
+mod xref;
@@ -2,9 +2,11 @@
 
 use std::hash::Hash;
 use std::num::NonZeroU32;
+use std::path::Path;
 use std::sync::{LazyLock, RwLock};
 
-use crate::helpers::{BiTigerHashMap, TigerHashMap};
+use crate::helpers::{BiTigerHashMap, TigerHashMap, TigerHashSet};
+use crate::item::Item;
 use crate::token::{Loc, Token};
 use crate::tooltipped::Tooltipped;
 
@@ -60,6 +62,20 @@ impl<T> MacroCache<T> {
         }
     }
 
+    pub fn get(
+        &self,
+        key: &Token,
+        args: &[(&'static str, Token)],
+        tooltipped: Tooltipped,
+        negated: bool,
+    ) -> Option<T>
+    where
+        T: Clone,
+    {
+        let key = MacroKey::new(key.loc, args, tooltipped, negated);
+        self.cache.read().unwrap().get(&key).cloned()
+    }
+
     pub fn insert(
         &self,
         key: &Token,
@@ -71,6 +87,12 @@ impl<T> MacroCache<T> {
         let key = MacroKey::new(key.loc, args, tooltipped, negated);
         self.cache.write().unwrap().insert(key, value);
     }
+
+    /// Forget the expansions made for call sites in the given files, so that they are validated
+    /// again.
+    pub fn forget_calls_in(&self, files: &TigerHashSet<&Path>) {
+        self.cache.write().unwrap().retain(|key, _| !files.contains(key.loc.pathname()));
+    }
 }
 
 impl<T> Default for MacroCache<T> {
@@ -90,14 +112,33 @@ pub struct MacroMap(RwLock<MacroMapInner>);
 pub struct MacroMapInner {
     counter: NonZeroU32,
     bi_map: BiTigerHashMap<NonZeroU32, Loc>,
+    /// The macro invocations made at the locs that are call sites, as opposed to the locs of
+    /// macro arguments.
+    frames: TigerHashMap<NonZeroU32, MacroFrame>,
+    /// For the locs of macro arguments, the index of the call site they were given at.
+    arg_call_sites: TigerHashMap<NonZeroU32, NonZeroU32>,
 }
 
 impl Default for MacroMapInner {
     fn default() -> Self {
-        Self { counter: NonZeroU32::new(1).unwrap(), bi_map: BiTigerHashMap::default() }
+        Self {
+            counter: NonZeroU32::new(1).unwrap(),
+            bi_map: BiTigerHashMap::default(),
+            frames: TigerHashMap::default(),
+            arg_call_sites: TigerHashMap::default(),
+        }
     }
 }
 
+/// One macro invocation: the scripted item that was expanded and the arguments it got.
+#[derive(Clone, Debug)]
+pub struct MacroFrame {
+    pub itype: Item,
+    pub name: &'static str,
+    /// The parameters and the values substituted for them, sorted by parameter.
+    pub args: Vec<(&'static str, &'static str)>,
+}
+
 impl MacroMap {
     /// Get the loc associated with the index
     pub fn get_loc(&self, index: MacroMapIndex) -> Option<Loc> {
@@ -132,11 +173,56 @@ impl MacroMap {
         }
     }
 
+    /// Record that the item `name` of type `itype` is expanded with `args` at the call site `loc`.
+    pub fn record_frame(
+        &self,
+        loc: Loc,
+        itype: Item,
+        name: &Token,
+        args: &[(&'static str, Token)],
+    ) {
+        let index = self.get_or_insert_loc(loc);
+        let mut args: Vec<_> = args.iter().map(|(parm, arg)| (*parm, arg.as_str())).collect();
+        args.sort_unstable();
+        let frame = MacroFrame { itype, name: name.as_str(), args };
+        self.0.write().unwrap().frames.entry(index.0).or_insert(frame);
+    }
+
+    /// Record that the macro argument `arg` was substituted in the expansion at `call_site`.
+    pub fn record_arg(&self, arg: MacroMapIndex, call_site: MacroMapIndex) {
+        self.0.write().unwrap().arg_call_sites.insert(arg.0, call_site.0);
+    }
+
+    /// Return the chain of macro invocations that `loc` is part of, innermost first, with the
+    /// locs of their call sites.
+    pub fn trace(&self, loc: Loc) -> Vec<(Loc, MacroFrame)> {
+        let guard = self.0.read().unwrap();
+        let mut trace = Vec::new();
+        let mut link_idx = loc.link_idx.map(|MacroMapIndex(index)| index);
+        while let Some(index) = link_idx {
+            if let Some(&call_site) = guard.arg_call_sites.get(&index) {
+                // The link is to a macro argument. Continue with the call that it was given to.
+                link_idx = Some(call_site);
+                continue;
+            }
+            let Some(&linked) = guard.bi_map.get_by_left(&index) else {
+                break;
+            };
+            if let Some(frame) = guard.frames.get(&index) {
+                trace.push((linked, frame.clone()));
+            }
+            link_idx = linked.link_idx.map(|MacroMapIndex(index)| index);
+        }
+        trace
+    }
+
     /// Clear all entries. This will break all existing `MacroMapIndex` values!
     pub(crate) fn clear(&self) {
         let mut guard = self.0.write().unwrap();
         guard.counter = NonZeroU32::new(1).unwrap();
         guard.bi_map.clear();
+        guard.frames.clear();
+        guard.arg_call_sites.clear();
     }
 }
 
@@ -0,0 +1,376 @@
+//! Checks that the images that make up the map fit together.
+//!
+//! The heightmap, the provinces image, the rivers image, and the terrain masks are each usable on
+//! their own, but the game expects them to agree with each other on size and content. When they
+//! don't, the game doesn't complain; the problems show up as graphical glitches on the map.
+
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use image::{DynamicImage, GrayImage, Rgb};
+use png::{BitDepth, ColorType, Decoder};
+
+use crate::block::{Block, BV};
+use crate::everything::Everything;
+use crate::fileset::FileEntry;
+use crate::game::Game;
+use crate::helpers::TigerHashMap;
+use crate::item::Item;
+use crate::pdxfile::PdxFile;
+use crate::report::{err, warn, will_maybe_log, ErrorKey};
+use crate::rivers::Rivers;
+
+/// At most this many coordinates are listed in a report about misplaced pixels.
+const MAX_LISTED_PIXELS: usize = 5;
+
+/// The size and format of a png image, as read from its header.
+#[derive(Clone, Copy, Debug)]
+struct PngInfo {
+    width: u32,
+    height: u32,
+    color_type: ColorType,
+    bit_depth: BitDepth,
+}
+
+/// Read the header of a png image. Returns `None`, after reporting the problem, if it can't be
+/// read.
+fn read_png_info(entry: &FileEntry) -> Option<PngInfo> {
+    let file = match File::open(entry.fullpath()) {
+        Ok(file) => file,
+        Err(e) => {
+            let msg = format!("could not read file: {e:#}");
+            err(ErrorKey::ReadError).msg(msg).loc(entry).push();
+            return None;
+        }
+    };
+    match Decoder::new(BufReader::new(file)).read_info() {
+        Ok(reader) => {
+            let info = reader.info();
+            Some(PngInfo {
+                width: info.width,
+                height: info.height,
+                color_type: info.color_type,
+                bit_depth: info.bit_depth,
+            })
+        }
+        Err(e) => {
+            let msg = format!("image format error: {e:#}");
+            err(ErrorKey::ImageFormat).msg(msg).loc(entry).push();
+            None
+        }
+    }
+}
+
+/// Look up the file entry for a path, if the file exists.
+fn find_entry<'a>(data: &'a Everything, path: &'a str) -> Option<&'a FileEntry> {
+    let path = Path::new(path);
+    data.fileset.get_files_under(path).iter().find(|entry| entry.path() == path)
+}
+
+fn define_number(data: &Everything, key: &str) -> Option<f64> {
+    match data.defines.get_bv(key)? {
+        BV::Value(token) => token.as_str().parse().ok(),
+        BV::Block(_) => None,
+    }
+}
+
+/// Return the highest pixel value on `heightmap.png` that is still under water, according to the
+/// defines.
+fn water_level(data: &Everything) -> Option<u8> {
+    let level = define_number(data, "NJominiMap|WATERLEVEL")?;
+    let extent = define_number(data, "NJominiMap|WORLD_EXTENTS_Y")?;
+    if extent <= 0.0 {
+        return None;
+    }
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    Some((level / extent * 255.0).clamp(0.0, 255.0) as u8)
+}
+
+/// Return the `default.map` of the game, if it's loaded. Vic3 doesn't need it for anything else.
+#[cfg_attr(not(any(feature = "ck3", feature = "imperator")), allow(unused_variables))]
+fn default_map(data: &Everything) -> Option<&Block> {
+    match Game::game() {
+        #[cfg(feature = "ck3")]
+        Game::Ck3 => data.provinces_ck3.default_map(),
+        #[cfg(feature = "imperator")]
+        Game::Imperator => data.provinces_imperator.default_map(),
+        #[cfg(feature = "vic3")]
+        Game::Vic3 => None,
+        #[cfg(feature = "hoi4")]
+        Game::Hoi4 => None,
+    }
+}
+
+/// Return the ids and colors of the provinces that should be under water.
+#[cfg_attr(not(any(feature = "ck3", feature = "imperator")), allow(unused_variables))]
+fn water_colors(data: &Everything) -> Vec<(u32, Rgb<u8>)> {
+    match Game::game() {
+        #[cfg(feature = "ck3")]
+        Game::Ck3 => data.provinces_ck3.water_colors(),
+        #[cfg(feature = "imperator")]
+        Game::Imperator => data.provinces_imperator.water_colors(),
+        #[cfg(feature = "vic3")]
+        Game::Vic3 => Vec::new(),
+        #[cfg(feature = "hoi4")]
+        Game::Hoi4 => Vec::new(),
+    }
+}
+
+/// Return the path of a map file, using the name given in `default.map` if there is one.
+fn map_file_path(data: &Everything, key: &str, default: &str) -> String {
+    if let Some(name) = default_map(data).and_then(|block| block.get_field_value(key)) {
+        let path = format!("map_data/{name}");
+        data.verify_exists_implied(Item::File, &path, name);
+        path
+    } else {
+        format!("map_data/{default}")
+    }
+}
+
+/// Check that the image is `expected` pixels in size, and report it if not.
+fn check_size(entry: &FileEntry, size: (u32, u32), expected: (u32, u32), reference: &str) {
+    if size != expected {
+        let msg = format!(
+            "image is {}x{} pixels, but {reference} is {}x{}",
+            size.0, size.1, expected.0, expected.1
+        );
+        err(ErrorKey::ImageSize).msg(msg).loc(entry).push();
+    }
+}
+
+/// Check the map images against each other. `rivers` is the already loaded rivers image.
+pub(crate) fn check_map_images(data: &Everything, rivers: &Rivers) {
+    let provinces_path = map_file_path(data, "provinces", "provinces.png");
+    let topology_path = map_file_path(data, "topology", "heightmap.heightmap");
+
+    // The provinces loader already reports problems with reading this file.
+    let Some(provinces) = find_entry(data, &provinces_path) else {
+        return;
+    };
+    let Ok(map_size) = image::image_dimensions(provinces.fullpath()) else {
+        return;
+    };
+    let reference = format!("`{provinces_path}`");
+
+    if let Some((entry, width, height)) = rivers.image() {
+        if width > 0 {
+            check_size(entry, (width, height), map_size, &reference);
+        }
+    }
+
+    let heightmap = find_entry(data, "map_data/heightmap.png");
+    if let Some(entry) = heightmap {
+        if let Some(info) = read_png_info(entry) {
+            check_size(entry, (info.width, info.height), map_size, &reference);
+            if info.color_type != ColorType::Grayscale || info.bit_depth != BitDepth::Eight {
+                let msg = format!(
+                    "heightmap should be an 8-bit grayscale image, but it is {:?} with {} bits",
+                    info.color_type, info.bit_depth as u8
+                );
+                err(ErrorKey::ImageFormat).msg(msg).loc(entry).push();
+            }
+        }
+    }
+
+    if let Some(entry) = find_entry(data, &topology_path) {
+        if let Some(block) = PdxFile::read_optional_bom(entry, &data.parser) {
+            check_topology(data, &block, map_size, &reference);
+        }
+    }
+
+    check_terrain_masks(data, map_size, &reference);
+
+    let Some(heightmap) = heightmap else {
+        return;
+    };
+    let check_water = Game::is_ck3() || Game::is_imperator();
+    let check_water = check_water && will_maybe_log(heightmap, ErrorKey::Validation);
+    let check_rivers = rivers.image().is_some_and(|(entry, width, height)| {
+        (width, height) == map_size && will_maybe_log(entry, ErrorKey::Rivers)
+    });
+    // Loading the images is expensive, so skip it if nothing would be reported.
+    if !check_water && !check_rivers {
+        return;
+    }
+    let Some(water_level) = water_level(data) else {
+        return;
+    };
+    let Ok(DynamicImage::ImageLuma8(height_img)) = image::open(heightmap.fullpath()) else {
+        return;
+    };
+    if height_img.dimensions() != map_size {
+        return;
+    }
+    if check_water {
+        if let Ok(DynamicImage::ImageRgb8(provinces_img)) = image::open(provinces.fullpath()) {
+            check_water_provinces(data, heightmap, &height_img, &provinces_img, water_level);
+        }
+    }
+    if check_rivers {
+        check_rivers_on_land(rivers, &height_img, water_level);
+    }
+}
+
+/// Check the sizes given in `heightmap.heightmap` and the images it refers to.
+fn check_topology(data: &Everything, block: &Block, map_size: (u32, u32), reference: &str) {
+    let original_size = block.get_field_list("original_heightmap_size").and_then(|list| {
+        if let [w, h] = &list[..] {
+            Some((w.as_str().parse::<u32>().ok()?, h.as_str().parse::<u32>().ok()?))
+        } else {
+            None
+        }
+    });
+    if let Some(size) = original_size {
+        if size != map_size {
+            let msg = format!(
+                "original_heightmap_size is {}x{}, but {reference} is {}x{}",
+                size.0, size.1, map_size.0, map_size.1
+            );
+            let key = block.get_key("original_heightmap_size").unwrap();
+            err(ErrorKey::ImageSize).msg(msg).loc(key).push();
+        }
+    }
+
+    let verify_image = |key: &str| {
+        let token = block.get_field_value(key)?;
+        data.verify_exists_implied(Item::File, token.as_str(), token);
+        let entry = find_entry(data, token.as_str())?;
+        let info = read_png_info(entry)?;
+        if info.bit_depth != BitDepth::Eight {
+            let msg = format!(
+                "image should have 8 bits per channel, but it has {}",
+                info.bit_depth as u8
+            );
+            err(ErrorKey::ImageFormat).msg(msg).loc(entry).push();
+        }
+        Some((entry, info))
+    };
+    verify_image("heightmap_file");
+
+    let tile_size = block.get_field_value("tile_size").and_then(|t| t.as_str().parse::<u32>().ok());
+    if let Some((entry, info)) = verify_image("indirection_file") {
+        // Each pixel of the indirection image describes one tile. Neighboring tiles share their
+        // edge pixels, so a tile covers one pixel less than its size.
+        if let (Some(tile_size @ 2..), Some((width, height))) = (tile_size, original_size) {
+            let tile = tile_size - 1;
+            let expected = (width.div_ceil(tile), height.div_ceil(tile));
+            let reference = format!("{width}x{height} divided into tiles of {tile} pixels");
+            check_size(entry, (info.width, info.height), expected, &reference);
+        }
+    }
+}
+
+/// Check that the terrain masks have the same size as each other, and the same proportions as
+/// the map.
+fn check_terrain_masks(data: &Everything, map_size: (u32, u32), reference: &str) {
+    let masks = data.fileset.filter_map_under(Path::new("gfx/map/terrain"), |entry| {
+        if !entry.filename().to_string_lossy().ends_with("_mask.png") {
+            return None;
+        }
+        Some((entry.clone(), read_png_info(entry)?))
+    });
+
+    let mut first: Option<&(FileEntry, PngInfo)> = None;
+    for mask in &masks {
+        let (entry, info) = mask;
+        if info.color_type != ColorType::Grayscale || info.bit_depth != BitDepth::Eight {
+            let msg = format!(
+                "terrain mask should be an 8-bit grayscale image, but it is {:?} with {} bits",
+                info.color_type, info.bit_depth as u8
+            );
+            err(ErrorKey::ImageFormat).msg(msg).loc(entry).push();
+        }
+        let size = (info.width, info.height);
+        if let Some((first_entry, first_info)) = first {
+            let reference = format!("`{}`", first_entry.path().display());
+            check_size(entry, size, (first_info.width, first_info.height), &reference);
+        } else {
+            first = Some(mask);
+            // Masks are usually smaller than the map, but they must cover it exactly.
+            let (width, height) = map_size;
+            if u64::from(size.0) * u64::from(height) != u64::from(size.1) * u64::from(width) {
+                let msg = format!(
+                    "terrain mask is {}x{} pixels, which doesn't match the proportions of {reference} at {width}x{height}",
+                    size.0, size.1
+                );
+                err(ErrorKey::ImageSize).msg(msg).loc(entry).push();
+            }
+        }
+    }
+}
+
+/// Check that sea zones and lakes are under water on the heightmap.
+fn check_water_provinces(
+    data: &Everything,
+    heightmap: &FileEntry,
+    height_img: &GrayImage,
+    provinces_img: &image::RgbImage,
+    water_level: u8,
+) {
+    let water: TigerHashMap<Rgb<u8>, u32> =
+        water_colors(data).into_iter().map(|(id, color)| (color, id)).collect();
+    if water.is_empty() {
+        return;
+    }
+    // For each water province, the number of its pixels and how many of those are above water.
+    let mut counts: TigerHashMap<u32, (u32, u32)> = TigerHashMap::default();
+    for (x, y, color) in provinces_img.enumerate_pixels() {
+        if let Some(id) = water.get(color) {
+            let count = counts.entry(*id).or_default();
+            count.0 += 1;
+            if height_img.get_pixel(x, y).0[0] > water_level {
+                count.1 += 1;
+            }
+        }
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_unstable();
+    for (id, (total, above)) in counts {
+        // Coasts are often a little above water, so only report provinces that are mostly dry.
+        if above * 2 > total {
+            let msg = format!("sea or lake province {id} is mostly above the water level");
+            let info = format!("{above} of its {total} pixels are higher than the water level set by NJominiMap|WATERLEVEL");
+            warn(ErrorKey::Validation).msg(msg).info(info).loc(heightmap).push();
+        }
+    }
+}
+
+/// Check that river pixels are on land. A river may reach into the water where it flows into the
+/// sea, so only pixels that are surrounded by water are reported.
+fn check_rivers_on_land(rivers: &Rivers, height_img: &GrayImage, water_level: u8) {
+    let Some((entry, width, height)) = rivers.image() else {
+        return;
+    };
+    let is_water = |x: u32, y: u32| height_img.get_pixel(x, y).0[0] <= water_level;
+    let mut in_water = Vec::new();
+    for (x, y) in rivers.river_pixels() {
+        let neighbors = [
+            (x > 0).then(|| (x - 1, y)),
+            (x + 1 < width).then(|| (x + 1, y)),
+            (y > 0).then(|| (x, y - 1)),
+            (y + 1 < height).then(|| (x, y + 1)),
+        ];
+        if is_water(x, y) && neighbors.into_iter().flatten().all(|(nx, ny)| is_water(nx, ny)) {
+            in_water.push((x, y));
+        }
+    }
+    if in_water.is_empty() {
+        return;
+    }
+    let mut list = String::new();
+    for (i, (x, y)) in in_water.iter().take(MAX_LISTED_PIXELS).enumerate() {
+        if i > 0 {
+            list.push_str(", ");
+        }
+        _ = write!(list, "({x}, {y})");
+    }
+    if in_water.len() > MAX_LISTED_PIXELS {
+        _ = write!(list, " and {} more", in_water.len() - MAX_LISTED_PIXELS);
+    }
+    let msg =
+        format!("{} river pixels are in the water according to the heightmap", in_water.len());
+    let info = format!("at {list}");
+    warn(ErrorKey::Rivers).msg(msg).info(info).loc(entry).push();
+}
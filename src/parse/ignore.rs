@@ -1,8 +1,12 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use lalrpop_util::lalrpop_mod;
 
 use crate::report::ErrorKey;
+use crate::token::Loc;
 
 lalrpop_mod! {
     #[allow(clippy::pedantic)]
@@ -25,6 +29,18 @@ pub enum IgnoreSize {
 pub struct IgnoreFilter {
     key: Option<ErrorKey>,
     text: Option<String>,
+    pub until: Option<IgnoreExpiry>,
+    /// Where the ignore comment is, for reports about the comment itself.
+    pub loc: Option<Loc>,
+}
+
+/// When an ignore comment stops applying.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IgnoreExpiry {
+    /// A date as year, month, day. The comment applies until the end of that day.
+    Date(u32, u32, u32),
+    /// A game version. The comment applies while the game is older than this version.
+    Version(Vec<u32>),
 }
 
 #[derive(Debug, Clone, Default)]
@@ -52,6 +68,11 @@ impl IgnoreSpec {
         self
     }
 
+    fn set_until(mut self, until: IgnoreExpiry) -> Self {
+        self.filter.until = Some(until);
+        self
+    }
+
     fn merge(mut self, other: Self) -> Self {
         if other.size != IgnoreSize::Line {
             self.size = other.size;
@@ -62,6 +83,9 @@ impl IgnoreSpec {
         if other.filter.text.is_some() {
             self.filter.text = other.filter.text;
         }
+        if other.filter.until.is_some() {
+            self.filter.until = other.filter.until;
+        }
         self
     }
 }
@@ -82,6 +106,87 @@ impl IgnoreFilter {
     }
 }
 
+impl IgnoreExpiry {
+    /// Return true iff the expiry date is in the past, or the game version is at least the
+    /// expiry version. If the game version is not known, version expiries never expire.
+    pub fn has_expired(&self, game_version: Option<&str>) -> bool {
+        match self {
+            IgnoreExpiry::Date(year, month, day) => today() > (*year, *month, *day),
+            IgnoreExpiry::Version(version) => {
+                game_version.is_some_and(|game_version| parse_version(game_version) >= *version)
+            }
+        }
+    }
+}
+
+impl FromStr for IgnoreExpiry {
+    type Err = ();
+
+    /// Parse a date like `2025-12-31` or a version like `1.18` or `1.18.2`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let [year, month, day] = s.split('-').collect::<Vec<_>>()[..] {
+            let year = year.parse().map_err(|_| ())?;
+            let month = month.parse().map_err(|_| ())?;
+            let day = day.parse().map_err(|_| ())?;
+            if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+                return Err(());
+            }
+            return Ok(IgnoreExpiry::Date(year, month, day));
+        }
+        let version =
+            s.split('.').map(str::parse).collect::<Result<Vec<u32>, _>>().map_err(|_| ())?;
+        Ok(IgnoreExpiry::Version(version))
+    }
+}
+
+impl Display for IgnoreExpiry {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            IgnoreExpiry::Date(year, month, day) => write!(f, "{year}-{month:02}-{day:02}"),
+            IgnoreExpiry::Version(version) => {
+                let parts: Vec<_> = version.iter().map(u32::to_string).collect();
+                write!(f, "version {}", parts.join("."))
+            }
+        }
+    }
+}
+
+/// Parse the leading numeric parts of a version string like `1.17.0.2 (Ascendant)`.
+fn parse_version(version: &str) -> Vec<u32> {
+    let version = version.trim_start_matches(['v', 'V']);
+    let mut parts = Vec::new();
+    for part in version.split('.') {
+        let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+        let Ok(n) = digits.parse() else {
+            break;
+        };
+        parts.push(n);
+        if digits.len() != part.len() {
+            break;
+        }
+    }
+    parts
+}
+
+/// Today's date in UTC, as year, month, day.
+#[allow(clippy::integer_division)]
+fn today() -> (u32, u32, u32) {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    // Convert days since 1970-01-01 to a civil date. This is Howard Hinnant's algorithm.
+    #[allow(clippy::cast_possible_wrap)]
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    (year as u32, month as u32, day as u32)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,6 +274,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_key_until_date() {
+        let result = parse_comment("tiger-ignore(key=missing-item, until=2025-06-30)");
+        assert!(result.is_some());
+        if let Some(spec) = result {
+            assert_eq!(spec.size, IgnoreSize::Line);
+            assert_eq!(spec.filter.key, Some(ErrorKey::MissingItem));
+            assert_eq!(spec.filter.until, Some(IgnoreExpiry::Date(2025, 6, 30)));
+            assert!(spec.filter.until.unwrap().has_expired(None));
+        }
+    }
+
+    #[test]
+    fn test_until_version() {
+        let result = parse_comment("tiger-ignore(until=1.18)");
+        assert!(result.is_some());
+        if let Some(spec) = result {
+            let until = spec.filter.until.unwrap();
+            assert_eq!(until, IgnoreExpiry::Version(vec![1, 18]));
+            assert!(!until.has_expired(None));
+            assert!(!until.has_expired(Some("1.17.0.2")));
+            assert!(until.has_expired(Some("1.18.0")));
+            assert!(until.has_expired(Some("v1.19")));
+        }
+    }
+
+    #[test]
+    fn test_until_invalid() {
+        let result = parse_comment("tiger-ignore(until=soon)");
+        assert!(result.is_none());
+    }
+
     #[test]
     fn test_block_with_quoted_text() {
         let result = parse_comment("tiger-ignore(block, text=\"missing english\")");
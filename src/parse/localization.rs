@@ -270,8 +270,10 @@ impl LocaParser {
             // Skip comments and blank lines
             self.skip_whitespace();
             if self.chars.peek() == Some(&'#') {
+                let comment_loc = self.loc;
                 self.next_char();
-                if let Some(spec) = parse_comment(self.get_rest_of_line()) {
+                if let Some(mut spec) = parse_comment(self.get_rest_of_line()) {
+                    spec.filter.loc = Some(comment_loc);
                     match spec.size {
                         IgnoreSize::Line => self.pending_line_ignores.push(spec.filter),
                         IgnoreSize::Block => (),
@@ -316,7 +316,12 @@ impl Iterator for Lexer<'_> {
                             "@:register-variable" => {
                                 let msg = format!("unknown reader directive `{token}`");
                                 let info = "did you mean `@:register_variable`?";
-                                err(ErrorKey::ParseError).msg(msg).info(info).loc(&token).push();
+                                err(ErrorKey::ParseError)
+                                    .msg(msg)
+                                    .info(info)
+                                    .loc(&token)
+                                    .fix("@:register_variable")
+                                    .push();
                                 None
                             }
                             "@:load_variable" => {
@@ -328,7 +333,12 @@ impl Iterator for Lexer<'_> {
                             "@:load-variable" => {
                                 let msg = format!("unknown reader directive `{token}`");
                                 let info = "did you mean `@:load_variable`?";
-                                err(ErrorKey::ParseError).msg(msg).info(info).loc(&token).push();
+                                err(ErrorKey::ParseError)
+                                    .msg(msg)
+                                    .info(info)
+                                    .loc(&token)
+                                    .fix("@:load_variable")
+                                    .push();
                                 None
                             }
                             "@:define" => Some(Lexeme::Directive(Directive::Define, token)),
@@ -520,6 +530,7 @@ impl Iterator for Lexer<'_> {
                 }
                 '#' => {
                     // A comment
+                    let comment_loc = self.loc;
                     self.consume();
                     let mut comment = self.start_cob();
                     while let Some((_, c)) = self.peek() {
@@ -535,7 +546,8 @@ impl Iterator for Lexer<'_> {
                     } else {
                         comment.take_to_token().as_str()
                     };
-                    if let Some(spec) = parse_comment(s) {
+                    if let Some(mut spec) = parse_comment(s) {
+                        spec.filter.loc = Some(comment_loc);
                         match spec.size {
                             IgnoreSize::Line => self.pending_line_ignores.push(spec.filter),
                             IgnoreSize::Block => self.pending_block_ignores.push(spec.filter),
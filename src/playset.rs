@@ -0,0 +1,86 @@
+//! Reader for the playsets in the Paradox launcher's database.
+//!
+//! The launcher keeps its playsets in `launcher-v2.sqlite` in the game's directory under
+//! `Paradox Interactive`. Each playset lists its mods with their position in the load order and
+//! whether they are enabled, and each mod has the directory it was installed in.
+//!
+//! Reading the database needs the `launcher` feature, which brings in `SQLite`.
+
+#[cfg(feature = "launcher")]
+use std::path::Path;
+use std::path::PathBuf;
+
+#[cfg(feature = "launcher")]
+use anyhow::{bail, Context, Result};
+#[cfg(feature = "launcher")]
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+
+/// The filename of the launcher's database, relative to the game's directory under
+/// `Paradox Interactive`.
+pub const LAUNCHER_DATABASE: &str = "launcher-v2.sqlite";
+
+/// A playset as configured in the Paradox launcher.
+#[derive(Clone, Debug)]
+pub struct Playset {
+    pub name: String,
+    /// The enabled mods of the playset, in load order.
+    pub mods: Vec<PlaysetMod>,
+}
+
+/// One mod in a [`Playset`].
+#[derive(Clone, Debug)]
+pub struct PlaysetMod {
+    /// The name of the mod as shown in the launcher.
+    pub name: String,
+    /// The directory the mod is installed in.
+    pub path: PathBuf,
+}
+
+#[cfg(feature = "launcher")]
+impl Playset {
+    /// Read the playset called `name` from the launcher database at `db_path`.
+    /// If `name` is `None`, read the playset that is active in the launcher.
+    pub fn read(db_path: &Path, name: Option<&str>) -> Result<Self> {
+        let db = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Could not open launcher database {}", db_path.display()))?;
+
+        let playset = if let Some(name) = name {
+            db.query_row("SELECT id, name FROM playsets WHERE name = ?1", [name], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+        } else {
+            db.query_row("SELECT id, name FROM playsets WHERE isActive = 1", [], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+        }
+        .optional()
+        .with_context(|| format!("Could not read playsets from {}", db_path.display()))?;
+
+        let Some((id, name)) = playset else {
+            match name {
+                Some(name) => bail!("No playset `{name}` in {}", db_path.display()),
+                None => bail!("No active playset in {}", db_path.display()),
+            }
+        };
+
+        let mut query = db.prepare(
+            "SELECT coalesce(mods.displayName, mods.name, mods.id), mods.dirPath
+             FROM playsets_mods JOIN mods ON playsets_mods.modId = mods.id
+             WHERE playsets_mods.playsetId = ?1 AND playsets_mods.enabled = 1
+             ORDER BY CAST(playsets_mods.position AS INTEGER)",
+        )?;
+        let mods = query
+            .query_map([&id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+            })?
+            .map(|row| {
+                let (mod_name, path) = row?;
+                let Some(path) = path else {
+                    bail!("Mod `{mod_name}` in playset `{name}` is not installed");
+                };
+                Ok(PlaysetMod { name: mod_name, path: PathBuf::from(path) })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Playset { name, mods })
+    }
+}
@@ -0,0 +1,241 @@
+//! Analysis of the pixels of `provinces.png`, for the games that define their provinces by color
+//! in that image.
+//!
+//! The analysis finds which provinces border each other, provinces that are split into several
+//! parts or have stray pixels, and places where four provinces meet at one corner.
+
+use std::cmp::Reverse;
+use std::fmt::Write as _;
+
+use bitvec::bitbox;
+use image::{Rgb, RgbImage};
+
+use crate::fileset::FileEntry;
+use crate::helpers::TigerHashMap;
+use crate::report::{warn, will_maybe_log, ErrorKey};
+
+/// At most this many locations are listed in a report about the parts of a province.
+const MAX_LISTED_PARTS: usize = 5;
+
+/// A connected area of pixels of one color.
+#[derive(Clone, Copy, Debug)]
+struct Part {
+    /// The number of pixels.
+    size: u32,
+    /// The first pixel of the part, scanning left to right and top to bottom.
+    x: u32,
+    y: u32,
+}
+
+/// The results of analyzing `provinces.png`.
+/// Vic3 has no adjacencies.csv, so it only uses this for the reports made during the analysis.
+#[cfg_attr(not(any(feature = "ck3", feature = "imperator")), allow(dead_code))]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ProvinceMap {
+    width: u32,
+    height: u32,
+    /// The colors at and around the points that were asked for during the analysis, so that the
+    /// image itself doesn't have to be kept.
+    near: TigerHashMap<(i32, i32), Vec<Rgb<u8>>>,
+    /// For each color, the colors of the provinces it touches horizontally or vertically.
+    /// The number of provinces each borders is most likely low (<=8) and hence using a `Vec`.
+    bordering: TigerHashMap<Rgb<u8>, Vec<Rgb<u8>>>,
+}
+
+#[cfg_attr(not(any(feature = "ck3", feature = "imperator")), allow(dead_code))]
+impl ProvinceMap {
+    /// Analyze the image, and report the problems found in it.
+    ///
+    /// `points` are the map coordinates that will later be passed to `colors_near`.
+    pub(crate) fn analyze(img: &RgbImage, entry: &FileEntry, points: &[(i32, i32)]) -> Self {
+        let (width, height) = img.dimensions();
+        let check_shapes = will_maybe_log(entry, ErrorKey::Validation);
+        let mut bordering: TigerHashMap<Rgb<u8>, Vec<Rgb<u8>>> = TigerHashMap::default();
+        let mut add_bordering = |c1: Rgb<u8>, c2: Rgb<u8>| {
+            let vec1 = bordering.entry(c1).or_insert_with(|| Vec::with_capacity(8));
+            // Since we always add to both vecs at the same time, we only need to check for
+            // existence in one.
+            if !vec1.contains(&c2) {
+                vec1.push(c2);
+                bordering.entry(c2).or_insert_with(|| Vec::with_capacity(8)).push(c1);
+            }
+        };
+
+        for (x, y, &color) in img.enumerate_pixels() {
+            let right = (x + 1 < width).then(|| *img.get_pixel(x + 1, y));
+            let down = (y + 1 < height).then(|| *img.get_pixel(x, y + 1));
+            if let Some(right_color) = right.filter(|&c| c != color) {
+                add_bordering(color, right_color);
+            }
+            if let Some(down_color) = down.filter(|&c| c != color) {
+                add_bordering(color, down_color);
+            }
+            if !check_shapes {
+                continue;
+            }
+            if let (Some(right_color), Some(down_color)) = (right, down) {
+                let down_right_color = *img.get_pixel(x + 1, y + 1);
+                let colors = [color, right_color, down_color, down_right_color];
+                if (1..4).all(|i| !colors[..i].contains(&colors[i])) {
+                    let msg = format!(
+                        "four provinces share a common corner in `provinces.png` around ({x}, {y})"
+                    );
+                    let info = "the game may connect either pair of diagonal provinces, which makes movement and borders unpredictable";
+                    warn(ErrorKey::Validation).msg(msg).info(info).loc(entry).push();
+                }
+            }
+        }
+
+        let mut near = TigerHashMap::default();
+        for &(x, y) in points {
+            if let Some(colors) = colors_near(img, x, y) {
+                near.insert((x, y), colors);
+            }
+        }
+
+        if !check_shapes {
+            return Self { width, height, near, bordering };
+        }
+
+        for (color, mut parts) in find_parts(img) {
+            parts.sort_by_key(|part| Reverse(part.size));
+            let Rgb([r, g, b]) = color;
+            if parts.len() == 1 {
+                if parts[0].size == 1 {
+                    let Part { x, y, .. } = parts[0];
+                    let msg = format!("province color ({r}, {g}, {b}) is a single pixel at ({x}, {y}) in `provinces.png`");
+                    let info = "provinces this small can't be seen or clicked on the map";
+                    warn(ErrorKey::Validation).msg(msg).info(info).loc(entry).push();
+                }
+                continue;
+            }
+            let (stray, fragments): (Vec<Part>, Vec<Part>) =
+                parts[1..].iter().partition(|part| part.size == 1);
+            if !fragments.is_empty() {
+                let msg = format!(
+                    "province color ({r}, {g}, {b}) is split into {} separate parts in `provinces.png`",
+                    fragments.len() + 1
+                );
+                let info = format!(
+                    "the main part is at {}; the other parts are at {}",
+                    list_parts(&parts[..1]),
+                    list_parts(&fragments)
+                );
+                warn(ErrorKey::Validation).msg(msg).info(info).loc(entry).push();
+            }
+            if !stray.is_empty() {
+                let msg = if stray.len() == 1 {
+                    format!("province color ({r}, {g}, {b}) has a stray pixel in `provinces.png`")
+                } else {
+                    format!(
+                        "province color ({r}, {g}, {b}) has {} stray pixels in `provinces.png`",
+                        stray.len()
+                    )
+                };
+                let info = format!(
+                    "stray pixels are not connected to the rest of the province: {}",
+                    list_parts(&stray)
+                );
+                warn(ErrorKey::Validation).msg(msg).info(info).loc(entry).push();
+            }
+        }
+
+        Self { width, height, near, bordering }
+    }
+
+    /// Return true iff this color borders any other color in the image.
+    pub(crate) fn contains(&self, color: Rgb<u8>) -> bool {
+        self.bordering.contains_key(&color)
+    }
+
+    /// Return true iff the provinces with these colors touch each other in the image.
+    pub(crate) fn is_bordering(&self, c1: Rgb<u8>, c2: Rgb<u8>) -> bool {
+        self.bordering.get(&c1).is_some_and(|v| v.contains(&c2))
+    }
+
+    /// Return true iff the given map coordinates are inside the image.
+    pub(crate) fn is_on_map(&self, x: i32, y: i32) -> bool {
+        u32::try_from(x).is_ok_and(|x| x < self.width)
+            && u32::try_from(y).is_ok_and(|y| y < self.height)
+    }
+
+    /// Return the colors of the pixel at the given map coordinates and of the pixels around it,
+    /// if those coordinates were passed to `analyze` and are on the map.
+    pub(crate) fn colors_near(&self, x: i32, y: i32) -> Option<&[Rgb<u8>]> {
+        self.near.get(&(x, y)).map(Vec::as_slice)
+    }
+}
+
+/// Return the colors of the pixel at the given map coordinates and of the pixels around it,
+/// or `None` if the coordinates are outside the image.
+///
+/// Map coordinates have their y axis going up from the bottom of the image.
+fn colors_near(img: &RgbImage, x: i32, y: i32) -> Option<Vec<Rgb<u8>>> {
+    let (width, height) = img.dimensions();
+    let x = u32::try_from(x).ok().filter(|&x| x < width)?;
+    let y = u32::try_from(y).ok().filter(|&y| y < height)?;
+    let y = height - 1 - y;
+    let mut colors = Vec::with_capacity(9);
+    for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+        for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+            let color = *img.get_pixel(nx, ny);
+            if !colors.contains(&color) {
+                colors.push(color);
+            }
+        }
+    }
+    Some(colors)
+}
+
+/// Find the connected parts of each color in the image. Pixels are connected if they touch
+/// horizontally or vertically.
+fn find_parts(img: &RgbImage) -> TigerHashMap<Rgb<u8>, Vec<Part>> {
+    let (width, height) = img.dimensions();
+    let index = |x: u32, y: u32| y as usize * width as usize + x as usize;
+    let mut seen = bitbox![0; width as usize * height as usize];
+    let mut parts: TigerHashMap<Rgb<u8>, Vec<Part>> = TigerHashMap::default();
+    let mut stack = Vec::new();
+
+    for (x, y, &color) in img.enumerate_pixels() {
+        if seen[index(x, y)] {
+            continue;
+        }
+        seen.set(index(x, y), true);
+        stack.push((x, y));
+        let mut size = 0;
+        while let Some((px, py)) = stack.pop() {
+            size += 1;
+            let neighbors = [
+                (px > 0).then(|| (px - 1, py)),
+                (px + 1 < width).then(|| (px + 1, py)),
+                (py > 0).then(|| (px, py - 1)),
+                (py + 1 < height).then(|| (px, py + 1)),
+            ];
+            for (nx, ny) in neighbors.into_iter().flatten() {
+                if !seen[index(nx, ny)] && *img.get_pixel(nx, ny) == color {
+                    seen.set(index(nx, ny), true);
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        parts.entry(color).or_default().push(Part { size, x, y });
+    }
+    parts
+}
+
+fn list_parts(parts: &[Part]) -> String {
+    let mut list = String::new();
+    for (i, part) in parts.iter().take(MAX_LISTED_PARTS).enumerate() {
+        if i > 0 {
+            list.push_str(", ");
+        }
+        _ = write!(list, "({}, {})", part.x, part.y);
+        if part.size > 1 {
+            _ = write!(list, " with {} pixels", part.size);
+        }
+    }
+    if parts.len() > MAX_LISTED_PARTS {
+        _ = write!(list, " and {} more", parts.len() - MAX_LISTED_PARTS);
+    }
+    list
+}
@@ -0,0 +1,196 @@
+//! Baseline files, which record the reports of an earlier run so that later runs can show only
+//! the new ones.
+//!
+//! Reports are matched by fingerprint rather than by exact location. The fingerprint ignores line
+//! numbers and whitespace, so that a baseline keeps working when the code around a report is
+//! edited or reindented.
+
+use std::fs::{read_to_string, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::TigerHashMap;
+use crate::report::errors::Errors;
+use crate::report::writer::kind_tag;
+use crate::report::{ErrorKey, LogReportMetadata, LogReportPointers};
+
+/// The version of the baseline file format.
+const BASELINE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct BaselineFile {
+    version: u32,
+    reports: Vec<BaselineEntry>,
+}
+
+/// One report in a baseline file. Only the fingerprint is used for matching; the other fields
+/// are there to make the file readable.
+#[derive(Serialize, Deserialize)]
+struct BaselineEntry {
+    fingerprint: String,
+    key: ErrorKey,
+    message: String,
+    path: String,
+    linenr: Option<u32>,
+}
+
+/// A loaded baseline, and the tally of how the current reports compare to it.
+#[derive(Debug, Default)]
+pub(crate) struct Baseline {
+    /// How many unmatched reports in the baseline have each fingerprint.
+    remaining: TigerHashMap<String, usize>,
+    new: usize,
+    still_present: usize,
+}
+
+impl Baseline {
+    /// Return true iff the report with this fingerprint was already in the baseline.
+    /// Each report in the baseline can only match once.
+    pub(crate) fn matches(&mut self, fingerprint: &str) -> bool {
+        if let Some(count) = self.remaining.get_mut(fingerprint) {
+            if *count > 0 {
+                *count -= 1;
+                self.still_present += 1;
+                return true;
+            }
+        }
+        self.new += 1;
+        false
+    }
+}
+
+/// How the emitted reports compare to the baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BaselineSummary {
+    /// Reports that are not in the baseline. These are the ones that were printed.
+    pub new: usize,
+    /// Reports in the baseline that no longer occur.
+    pub fixed: usize,
+    /// Reports in the baseline that still occur. These were not printed.
+    pub still_present: usize,
+}
+
+/// Compute a fingerprint of a report that is stable across whitespace changes and line moves.
+pub(crate) fn fingerprint(
+    errors: &Errors,
+    report: &LogReportMetadata,
+    pointers: &LogReportPointers,
+) -> String {
+    fingerprint_parts(
+        report.key,
+        &report.msg,
+        pointers.iter().map(|pointer| FingerprintLocation {
+            from: kind_tag(errors, pointer.loc.kind),
+            path: pointer.loc.pathname(),
+            tag: pointer.msg.as_deref(),
+            line: errors.cache.get_line(pointer.loc),
+        }),
+    )
+}
+
+/// The parts of a report location that go into its fingerprint.
+pub(crate) struct FingerprintLocation<'a> {
+    pub(crate) from: &'a str,
+    pub(crate) path: &'a Path,
+    pub(crate) tag: Option<&'a str>,
+    pub(crate) line: Option<&'a str>,
+}
+
+/// Compute a fingerprint from the report's key and message, and the file, tag, and source line
+/// (without whitespace) of each of its locations.
+pub(crate) fn fingerprint_parts<'a>(
+    key: ErrorKey,
+    msg: &str,
+    locations: impl Iterator<Item = FingerprintLocation<'a>>,
+) -> String {
+    let mut hash = Fnv64::default();
+    hash.write(key.to_string().as_bytes());
+    hash.write(msg.as_bytes());
+    for location in locations {
+        hash.write(location.from.as_bytes());
+        hash.write(location.path.to_string_lossy().as_bytes());
+        hash.write(location.tag.unwrap_or("").as_bytes());
+        if let Some(line) = location.line {
+            let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+            hash.write(line.as_bytes());
+        }
+    }
+    format!("{:016x}", hash.0)
+}
+
+/// The FNV-1a hash. It's used instead of the standard library hashers because its output must not
+/// change between program versions.
+struct Fnv64(u64);
+
+impl Default for Fnv64 {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv64 {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+        // Separate the fields, so that moving text from one field to the next changes the hash.
+        self.0 ^= 0xff;
+        self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+    }
+}
+
+/// Load a baseline file written by [`write_baseline`]. Reports that match the baseline will not
+/// be printed.
+pub fn load_baseline(fullpath: &Path) -> Result<()> {
+    let file: BaselineFile = serde_json::from_str(&read_to_string(fullpath)?)?;
+    if file.version != BASELINE_VERSION {
+        bail!("unsupported baseline version {}", file.version);
+    }
+    let mut baseline = Baseline::default();
+    for entry in file.reports {
+        *baseline.remaining.entry(entry.fingerprint).or_default() += 1;
+    }
+    Errors::get_mut().baseline = Some(baseline);
+    Ok(())
+}
+
+/// Write all the stored reports to a baseline file, instead of printing them.
+///
+/// The stored reports will be left empty. Returns the number of reports written.
+pub fn write_baseline(fullpath: &Path) -> Result<usize> {
+    let mut errors = Errors::get_mut();
+    let reports: Vec<_> = errors
+        .flatten_reports(false)
+        .iter()
+        .map(|(report, pointers, _)| BaselineEntry {
+            fingerprint: fingerprint(&errors, report, pointers),
+            key: report.key,
+            message: report.msg.clone(),
+            path: pointers[0].loc.pathname().to_string_lossy().into_owned(),
+            linenr: (pointers[0].loc.line != 0).then_some(pointers[0].loc.line),
+        })
+        .collect();
+    errors.storage.clear();
+    drop(errors);
+
+    let count = reports.len();
+    let file = BaselineFile { version: BASELINE_VERSION, reports };
+    serde_json::to_writer_pretty(BufWriter::new(File::create(fullpath)?), &file)?;
+    Ok(count)
+}
+
+/// Compare the reports emitted so far to the loaded baseline.
+///
+/// Returns `None` if no baseline was loaded.
+pub fn baseline_summary() -> Option<BaselineSummary> {
+    let errors = Errors::get_mut();
+    errors.baseline.as_ref().map(|baseline| BaselineSummary {
+        new: baseline.new,
+        fixed: baseline.remaining.values().sum(),
+        still_present: baseline.still_present,
+    })
+}
@@ -4,10 +4,31 @@
 //! - The user is forced to add at least one pointer, making it impossible to create a report
 //!   without pointers, which would lead to panics.
 
+use std::cell::Cell;
+
+use crate::item::Item;
 use crate::report::{
     log, Confidence, ErrorKey, ErrorLoc, LogReportMetadata, LogReportPointers, LogReportStyle,
     PointedMessage, Severity,
 };
+use crate::token::Loc;
+
+thread_local! {
+    /// The type of item being validated on this thread, to be recorded in the reports.
+    static VALIDATING_ITEM: Cell<Option<Item>> = const { Cell::new(None) };
+}
+
+/// Run `f` with reports being marked as made while validating an item of type `item`.
+pub(crate) fn with_validating_item<R>(item: Item, f: impl FnOnce() -> R) -> R {
+    let previous = VALIDATING_ITEM.replace(Some(item));
+    let result = f();
+    VALIDATING_ITEM.set(previous);
+    result
+}
+
+fn validating_item() -> Option<Item> {
+    VALIDATING_ITEM.get()
+}
 
 // =================================================================================================
 // =============== Starting points:
@@ -113,7 +134,13 @@ impl ReportBuilderStage2 {
             msg: self.msg,
             info: self.info,
             wiki: self.wiki,
-            pointers: vec![PointedMessage { loc: eloc.into_loc(), length, msg: None }],
+            pointers: vec![PointedMessage {
+                loc: eloc.into_loc(),
+                length,
+                msg: None,
+                fix: None,
+                fix_end: None,
+            }],
         }
     }
 
@@ -124,7 +151,13 @@ impl ReportBuilderStage2 {
             msg: self.msg,
             info: self.info,
             wiki: self.wiki,
-            pointers: vec![PointedMessage { loc: eloc.into_loc(), length, msg: Some(msg.into()) }],
+            pointers: vec![PointedMessage {
+                loc: eloc.into_loc(),
+                length,
+                msg: Some(msg.into()),
+                fix: None,
+                fix_end: None,
+            }],
         }
     }
 
@@ -144,7 +177,13 @@ impl ReportBuilderStage2 {
             msg: self.msg,
             info: self.info,
             wiki: self.wiki,
-            pointers: vec![PointedMessage { loc: eloc.into_loc(), length: 0, msg: None }],
+            pointers: vec![PointedMessage {
+                loc: eloc.into_loc(),
+                length: 0,
+                msg: None,
+                fix: None,
+                fix_end: None,
+            }],
         }
     }
 }
@@ -162,7 +201,13 @@ pub struct ReportBuilderFull {
 impl ReportBuilderFull {
     pub fn loc_msg<E: ErrorLoc, S: Into<String>>(mut self, eloc: E, msg: S) -> Self {
         let length = eloc.loc_length();
-        self.pointers.push(PointedMessage { loc: eloc.into_loc(), length, msg: Some(msg.into()) });
+        self.pointers.push(PointedMessage {
+            loc: eloc.into_loc(),
+            length,
+            msg: Some(msg.into()),
+            fix: None,
+            fix_end: None,
+        });
         self
     }
     pub fn opt_loc_msg<E: ErrorLoc, S: Into<String>>(mut self, eloc: Option<E>, msg: S) -> Self {
@@ -172,10 +217,36 @@ impl ReportBuilderFull {
                 loc: eloc.into_loc(),
                 length,
                 msg: Some(msg.into()),
+                fix: None,
+                fix_end: None,
             });
         }
         self
     }
+    /// Optional step. Suggests `replacement` for the text at the most recently added location.
+    /// The suggestion will be shown with the report, and may be applied by `--fix`.
+    pub fn fix<S: Into<String>>(mut self, replacement: S) -> Self {
+        if let Some(pointer) = self.pointers.last_mut() {
+            pointer.fix = Some(replacement.into());
+        }
+        self
+    }
+    /// Optional step. Like [`ReportBuilderFull::fix`], but replaces all the text from the most
+    /// recently added location up to `end`, which may be on a later line.
+    pub fn fix_until<S: Into<String>>(mut self, end: Loc, replacement: S) -> Self {
+        if let Some(pointer) = self.pointers.last_mut() {
+            pointer.fix = Some(replacement.into());
+            pointer.fix_end = Some(end);
+        }
+        self
+    }
+    /// Optional step. Like [`ReportBuilderFull::fix`], but only if `replacement` is `Some`.
+    pub fn opt_fix<S: Into<String>>(self, replacement: Option<S>) -> Self {
+        match replacement {
+            Some(replacement) => self.fix(replacement),
+            None => self,
+        }
+    }
     /// Build the report and return it.
     pub fn build(self) -> (LogReportMetadata, LogReportPointers) {
         (
@@ -186,6 +257,7 @@ impl ReportBuilderFull {
                 msg: self.msg,
                 info: self.info,
                 wiki: self.wiki,
+                item: validating_item(),
                 style: LogReportStyle::Full,
             },
             self.pointers,
@@ -218,6 +290,7 @@ impl ReportBuilderAbbreviated {
                 msg: self.msg,
                 info: self.info,
                 wiki: self.wiki,
+                item: validating_item(),
                 style: LogReportStyle::Abbreviated,
             },
             self.pointers,
@@ -0,0 +1,148 @@
+//! Comparing the reports of two runs, to see which reports were introduced and which were resolved.
+
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::TigerHashMap;
+use crate::report::baseline::{fingerprint_parts, FingerprintLocation};
+use crate::report::errors::Errors;
+use crate::report::writer::kind_tag;
+use crate::report::{Confidence, ErrorKey, Severity};
+
+/// A report in a form that does not depend on the loaded files, so that it can be compared with
+/// reports from another run. It has the same shape as the reports in `--json` output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedReport {
+    pub severity: Severity,
+    pub confidence: Confidence,
+    pub key: ErrorKey,
+    pub message: String,
+    pub info: Option<String>,
+    pub wiki: Option<String>,
+    pub locations: Vec<SavedLocation>,
+}
+
+/// One location of a [`SavedReport`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedLocation {
+    /// The path relative to the game or mod root.
+    pub path: PathBuf,
+    /// Which game, DLC, or mod the file is from.
+    pub from: String,
+    pub fullpath: PathBuf,
+    pub linenr: Option<u32>,
+    pub column: Option<u32>,
+    pub length: Option<usize>,
+    /// The contents of the source line.
+    pub line: Option<String>,
+    pub tag: Option<String>,
+    /// Suggested replacement text for the `length` characters at this location.
+    #[serde(default)]
+    pub fix: Option<String>,
+}
+
+impl SavedReport {
+    /// The identity of a report for the purpose of comparing runs. It leaves out line numbers and
+    /// whitespace, so that edits elsewhere in the file don't make a report look new.
+    fn identity(&self) -> (Severity, Confidence, String) {
+        let fingerprint = fingerprint_parts(
+            self.key,
+            &self.message,
+            self.locations.iter().map(|location| FingerprintLocation {
+                from: &location.from,
+                path: &location.path,
+                tag: location.tag.as_deref(),
+                line: location.line.as_deref(),
+            }),
+        );
+        (self.severity, self.confidence, fingerprint)
+    }
+}
+
+/// The differences between the reports of two runs.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReportDiff {
+    /// Reports that are only in the new run.
+    pub introduced: Vec<SavedReport>,
+    /// Reports that are only in the old run.
+    pub resolved: Vec<SavedReport>,
+}
+
+/// Extract the stored reports as [`SavedReport`]s, with `#tiger-ignore` directives applied.
+///
+/// The stored reports will be left empty.
+pub fn take_saved_reports() -> Vec<SavedReport> {
+    let mut errors = Errors::get_mut();
+    let reports = errors
+        .flatten_reports(false)
+        .iter()
+        .map(|(report, pointers, _)| SavedReport {
+            severity: report.severity,
+            confidence: report.confidence,
+            key: report.key,
+            message: report.msg.clone(),
+            info: report.info.clone(),
+            wiki: report.wiki.clone(),
+            locations: pointers
+                .iter()
+                .map(|pointer| SavedLocation {
+                    path: pointer.loc.pathname().to_path_buf(),
+                    from: kind_tag(&errors, pointer.loc.kind).to_owned(),
+                    fullpath: pointer.loc.fullpath().to_path_buf(),
+                    linenr: (pointer.loc.line != 0).then_some(pointer.loc.line),
+                    column: (pointer.loc.column != 0).then_some(pointer.loc.column),
+                    length: (pointer.length != 0).then_some(pointer.length),
+                    line: errors.cache.get_line(pointer.loc).map(ToOwned::to_owned),
+                    tag: pointer.msg.clone(),
+                    fix: pointer.fix.clone(),
+                })
+                .collect(),
+        })
+        .collect();
+    errors.storage.clear();
+    reports
+}
+
+/// Read the reports from a file that was written with the `--json` option.
+pub fn saved_reports_from_json(fullpath: &Path) -> Result<Vec<SavedReport>> {
+    Ok(serde_json::from_str(&read_to_string(fullpath)?)?)
+}
+
+/// Compare the reports of two runs.
+///
+/// Reports are matched by severity, confidence, key, message, and for each location the file,
+/// tag and source line. Line numbers and whitespace are ignored.
+pub fn diff_reports(old: Vec<SavedReport>, new: Vec<SavedReport>) -> ReportDiff {
+    let mut old_reports: TigerHashMap<_, Vec<SavedReport>> = TigerHashMap::default();
+    for report in old {
+        old_reports.entry(report.identity()).or_default().push(report);
+    }
+    let mut diff = ReportDiff::default();
+    for report in new {
+        if let Some(matched) = old_reports.get_mut(&report.identity()) {
+            if matched.pop().is_some() {
+                continue;
+            }
+        }
+        diff.introduced.push(report);
+    }
+    diff.resolved = old_reports.into_values().flatten().collect();
+    // Keep the output stable, in the same order as the normal output.
+    for reports in [&mut diff.introduced, &mut diff.resolved] {
+        reports.sort_by(|a, b| {
+            b.severity
+                .cmp(&a.severity)
+                .then(b.confidence.cmp(&a.confidence))
+                .then_with(|| location_key(a).cmp(&location_key(b)))
+                .then_with(|| a.message.cmp(&b.message))
+        });
+    }
+    diff
+}
+
+fn location_key(report: &SavedReport) -> Vec<(&Path, Option<u32>, Option<u32>)> {
+    report.locations.iter().map(|loc| (loc.path.as_path(), loc.linenr, loc.column)).collect()
+}
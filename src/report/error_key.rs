@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
-use strum_macros::{Display, EnumString};
+use strum_macros::{Display, EnumIter, EnumString};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumString, Hash, Serialize, Deserialize)]
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Display, EnumString, EnumIter, Hash, Serialize, Deserialize,
+)]
 #[strum(use_phf)]
 #[strum(serialize_all = "kebab-case")]
 #[serde(rename_all = "kebab-case")]
@@ -59,6 +61,7 @@ pub enum ErrorKey {
     UnusedLocalization,
     LocalizationKeyCollision,
     UnusedFile,
+    UnusedItem,
     UnknownList,
     Choice,
     UseOfThis,
@@ -72,8 +75,106 @@ pub enum ErrorKey {
     Deprecated,
     Variables,
     DefinitionName,
+    TigerIgnore,
 
     PrincesOfDarkness,
 
     Internal,
 }
+
+impl ErrorKey {
+    /// A long-form description of this kind of report, with examples of code that triggers it
+    /// and how to fix it. It is in markdown format.
+    ///
+    /// The texts are in the `explanations` directory, one file per key.
+    pub fn explanation(self) -> &'static str {
+        match self {
+            ErrorKey::Config => include_str!("explanations/config.md"),
+            ErrorKey::ReadError => include_str!("explanations/read-error.md"),
+            ErrorKey::ParseError => include_str!("explanations/parse-error.md"),
+            ErrorKey::BraceError => include_str!("explanations/brace-error.md"),
+            ErrorKey::BracePlacement => include_str!("explanations/brace-placement.md"),
+            ErrorKey::ReaderDirectives => include_str!("explanations/reader-directives.md"),
+            ErrorKey::Packaging => include_str!("explanations/packaging.md"),
+            ErrorKey::Validation => include_str!("explanations/validation.md"),
+            ErrorKey::Structure => include_str!("explanations/structure.md"),
+            ErrorKey::Filename => include_str!("explanations/filename.md"),
+            ErrorKey::Encoding => include_str!("explanations/encoding.md"),
+            ErrorKey::Localization => include_str!("explanations/localization.md"),
+            ErrorKey::Markup => include_str!("explanations/markup.md"),
+            ErrorKey::DuplicateItem => include_str!("explanations/duplicate-item.md"),
+            ErrorKey::ExactDuplicateItem => include_str!("explanations/exact-duplicate-item.md"),
+            ErrorKey::DuplicateField => include_str!("explanations/duplicate-field.md"),
+            ErrorKey::DuplicateCharacter => include_str!("explanations/duplicate-character.md"),
+            ErrorKey::NameConflict => include_str!("explanations/name-conflict.md"),
+            ErrorKey::EventNamespace => include_str!("explanations/event-namespace.md"),
+            ErrorKey::MissingLocalization => include_str!("explanations/missing-localization.md"),
+            ErrorKey::SuggestLocalization => include_str!("explanations/suggest-localization.md"),
+            ErrorKey::MissingFile => include_str!("explanations/missing-file.md"),
+            ErrorKey::MissingSound => include_str!("explanations/missing-sound.md"),
+            ErrorKey::MissingItem => include_str!("explanations/missing-item.md"),
+            ErrorKey::MissingPerspective => include_str!("explanations/missing-perspective.md"),
+            ErrorKey::ExtraFile => include_str!("explanations/extra-file.md"),
+            ErrorKey::WrongGender => include_str!("explanations/wrong-gender.md"),
+            ErrorKey::Conflict => include_str!("explanations/conflict.md"),
+            ErrorKey::ImageFormat => include_str!("explanations/image-format.md"),
+            ErrorKey::ImageSize => include_str!("explanations/image-size.md"),
+            ErrorKey::Unneeded => include_str!("explanations/unneeded.md"),
+            ErrorKey::Scopes => include_str!("explanations/scopes.md"),
+            ErrorKey::StrictScopes => include_str!("explanations/strict-scopes.md"),
+            ErrorKey::Crash => include_str!("explanations/crash.md"),
+            ErrorKey::Range => include_str!("explanations/range.md"),
+            ErrorKey::Tooltip => include_str!("explanations/tooltip.md"),
+            ErrorKey::IfElse => include_str!("explanations/if-else.md"),
+            ErrorKey::Rivers => include_str!("explanations/rivers.md"),
+            ErrorKey::Modifiers => include_str!("explanations/modifiers.md"),
+            ErrorKey::Macro => include_str!("explanations/macro.md"),
+            ErrorKey::History => include_str!("explanations/history.md"),
+            ErrorKey::Logic => include_str!("explanations/logic.md"),
+            ErrorKey::Bugs => include_str!("explanations/bugs.md"),
+            ErrorKey::Datafunctions => include_str!("explanations/datafunctions.md"),
+            ErrorKey::Removed => include_str!("explanations/removed.md"),
+            ErrorKey::FieldMissing => include_str!("explanations/field-missing.md"),
+            ErrorKey::UnknownField => include_str!("explanations/unknown-field.md"),
+            ErrorKey::TitleTier => include_str!("explanations/title-tier.md"),
+            ErrorKey::Colors => include_str!("explanations/colors.md"),
+            ErrorKey::UnusedLocalization => include_str!("explanations/unused-localization.md"),
+            ErrorKey::LocalizationKeyCollision => {
+                include_str!("explanations/localization-key-collision.md")
+            }
+            ErrorKey::UnusedFile => include_str!("explanations/unused-file.md"),
+            ErrorKey::UnusedItem => include_str!("explanations/unused-item.md"),
+            ErrorKey::UnknownList => include_str!("explanations/unknown-list.md"),
+            ErrorKey::Choice => include_str!("explanations/choice.md"),
+            ErrorKey::UseOfThis => include_str!("explanations/use-of-this.md"),
+            ErrorKey::CharacterId => include_str!("explanations/character-id.md"),
+            ErrorKey::Loop => include_str!("explanations/loop.md"),
+            ErrorKey::Bookmarks => include_str!("explanations/bookmarks.md"),
+            ErrorKey::WrongGame => include_str!("explanations/wrong-game.md"),
+            ErrorKey::Gui => include_str!("explanations/gui.md"),
+            ErrorKey::Performance => include_str!("explanations/performance.md"),
+            ErrorKey::Overflow => include_str!("explanations/overflow.md"),
+            ErrorKey::Deprecated => include_str!("explanations/deprecated.md"),
+            ErrorKey::Variables => include_str!("explanations/variables.md"),
+            ErrorKey::DefinitionName => include_str!("explanations/definition-name.md"),
+            ErrorKey::TigerIgnore => include_str!("explanations/tiger-ignore.md"),
+            ErrorKey::PrincesOfDarkness => include_str!("explanations/princes-of-darkness.md"),
+            ErrorKey::Internal => include_str!("explanations/internal.md"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    #[test]
+    fn test_explanations_have_examples() {
+        for key in ErrorKey::iter() {
+            let blocks = key.explanation().lines().filter(|line| line.starts_with("```")).count();
+            assert!(blocks >= 4, "explanation of {key} lacks an example and a corrected example");
+        }
+    }
+}
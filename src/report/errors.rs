@@ -1,29 +1,33 @@
 //! Collect error reports and then write them out.
 
 use std::borrow::Cow;
-use std::cell::RefCell;
-use std::cmp::{min_by, Ordering};
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
 use std::fs::read;
 use std::io::Write;
-use std::iter::{empty, once};
+use std::iter::once;
 use std::mem::take;
 use std::ops::{Bound, RangeBounds};
 use std::path::{Path, PathBuf};
 use std::sync::{LazyLock, Mutex, MutexGuard};
 
 use encoding_rs::{UTF_8, WINDOWS_1252};
+use strum::EnumCount;
 
+use crate::fileset::FileKind;
 use crate::helpers::{TigerHashMap, TigerHashSet};
 use crate::macros::MACRO_MAP;
 use crate::parse::ignore::IgnoreFilter;
+use crate::report::baseline::{fingerprint, Baseline};
 use crate::report::error_loc::ErrorLoc;
 use crate::report::filter::ReportFilter;
 use crate::report::suppress::{Suppression, SuppressionKey};
-use crate::report::writer::{log_report, log_summary};
+use crate::report::writer::{log_explanations, log_report, log_summary};
 use crate::report::writer_json::log_report_json;
+use crate::report::writer_sarif::log_reports_sarif;
 use crate::report::{
-    ErrorKey, FilterRule, LogReport, LogReportMetadata, LogReportPointers, LogReportStyle,
-    OutputStyle, PointedMessage,
+    untidy, warn, Confidence, ErrorKey, FilterRule, LogReport, LogReportMetadata,
+    LogReportPointers, LogReportStyle, OutputStyle, PointedMessage, Severity,
 };
 use crate::set;
 use crate::token::{leak, Loc};
@@ -41,6 +45,16 @@ static LOG_ONCE: LazyLock<TigerHashSet<ErrorKey>> = LazyLock::new(|| {
 
 static ERRORS: LazyLock<Mutex<Errors>> = LazyLock::new(|| Mutex::new(Errors::default()));
 
+/// The `Errors` of sessions that have ended, to be reused by new sessions.
+static FREE_SESSION_ERRORS: Mutex<Vec<&'static Mutex<Errors<'static>>>> = Mutex::new(Vec::new());
+
+thread_local! {
+    /// The `Errors` of the [`Session`](crate::Session) this thread works for, if any.
+    /// Threads outside of sessions use the global `ERRORS`.
+    static SESSION_ERRORS: Cell<Option<&'static Mutex<Errors<'static>>>> =
+        const { Cell::new(None) };
+}
+
 #[allow(missing_debug_implementations)]
 #[derive(Default)]
 pub struct Errors<'a> {
@@ -55,6 +69,10 @@ pub struct Errors<'a> {
     /// Determines whether a report should be printed.
     pub(crate) filter: ReportFilter,
 
+    /// The severity and confidence to give reports of certain keys, as set by `severity_override`
+    /// in the config. They are applied before the filter.
+    pub(crate) severity_overrides: TigerHashMap<ErrorKey, (Option<Severity>, Option<Confidence>)>,
+
     /// Output color and style configuration.
     pub(crate) styles: OutputStyle,
 
@@ -63,10 +81,30 @@ pub struct Errors<'a> {
     // avoid dyn shenanigans with the RangeBounds trait.
     ignore: TigerHashMap<&'a Path, Vec<IgnoreEntry>>,
 
+    /// Reports from an earlier run, which should not be printed again.
+    pub(crate) baseline: Option<Baseline>,
+
+    /// What the last call to `emit_reports` or `emit_reports_sarif` printed.
+    pub(crate) emitted: EmitSummary,
+
+    /// Print the long-form explanation of each key after the reports.
+    pub(crate) explain: bool,
+
+    /// Print the chain of macro expansions, with their arguments, that led to each report.
+    pub(crate) macro_trace: bool,
+
+    /// The version of the game being validated against, if known. Used for `tiger-ignore`
+    /// directives that expire at a game version.
+    pub(crate) game_version: Option<String>,
+
     /// All reports that passed the checks, stored here to be sorted before being emitted all at once.
     /// The "abbreviated" reports don't participate in this. They are still emitted immediately.
     /// It's a `HashSet` because duplicate reports are fairly common due to macro expansion and other revalidations.
-    storage: TigerHashMap<LogReportMetadata, TigerHashSet<LogReportPointers>>,
+    pub(crate) storage: TigerHashMap<LogReportMetadata, TigerHashSet<LogReportPointers>>,
+
+    /// Whether to keep the stored reports after emitting them, so that they can be emitted again
+    /// after [`Everything::validate_changed`](crate::Everything::validate_changed).
+    pub(crate) keep_reports: bool,
 }
 
 impl Errors<'_> {
@@ -105,10 +143,31 @@ impl Errors<'_> {
         false
     }
 
+    /// Record which `#tiger-ignore` directives match the report, so that
+    /// [`check_unused_ignores`] does not flag directives whose reports were filtered out.
+    fn mark_used_ignores(&mut self, report: &LogReportMetadata, pointers: &LogReportPointers) {
+        for p in pointers {
+            if let Some(vec) = self.ignore.get_mut(p.loc.pathname()) {
+                for entry in vec {
+                    if (entry.start, entry.end).contains(&p.loc.line)
+                        && entry.filter.matches(report.key, &report.msg)
+                    {
+                        entry.used = true;
+                    }
+                }
+            }
+        }
+    }
+
     /// Perform some checks to see whether the report should actually be logged.
     /// If yes, it will add it to the storage.
-    fn push_report(&mut self, report: LogReportMetadata, pointers: LogReportPointers) {
-        if !self.filter.should_print_report(&report, &pointers)
+    fn push_report(&mut self, mut report: LogReportMetadata, pointers: LogReportPointers) {
+        if let Some((severity, confidence)) = self.severity_overrides.get(&report.key) {
+            report.severity = severity.unwrap_or(report.severity);
+            report.confidence = confidence.unwrap_or(report.confidence);
+        }
+        self.mark_used_ignores(&report, &pointers);
+        if !self.filter.should_print_report(&report, &pointers, &self.loaded_mods_labels)
             || self.should_suppress(&report, &pointers)
         {
             return;
@@ -125,32 +184,11 @@ impl Errors<'_> {
             .storage
             .iter()
             .flat_map(|(report, occurrences)| -> Box<dyn Iterator<Item = _>> {
-                let mut iterator =
+                let iterator =
                     occurrences.iter().filter(|pointers| !self.should_ignore(report, pointers));
                 match report.style {
                     LogReportStyle::Full => {
-                        if consolidate && LOG_ONCE.contains(&report.key) {
-                            if let Some(initial) = iterator.next() {
-                                let (pointers, additional_count) = iterator.fold(
-                                    (initial, 0usize),
-                                    |(first_occurrence, count), e| {
-                                        (
-                                            min_by(first_occurrence, e, |a, b| {
-                                                a.iter().map(|e| e.loc).cmp(b.iter().map(|e| e.loc))
-                                            }),
-                                            count + 1,
-                                        )
-                                    },
-                                );
-                                Box::new(once((report, Cow::Borrowed(pointers), additional_count)))
-                            } else {
-                                Box::new(empty())
-                            }
-                        } else {
-                            Box::new(
-                                iterator.map(move |pointers| (report, Cow::Borrowed(pointers), 0)),
-                            )
-                        }
+                        Box::new(iterator.map(move |pointers| (report, Cow::Borrowed(pointers), 0)))
                     }
                     LogReportStyle::Abbreviated => {
                         let mut pointers: Vec<_> = iterator.map(|o| o[0].clone()).collect();
@@ -179,7 +217,32 @@ impl Errors<'_> {
             }
             cmp
         });
-        reports
+        if consolidate {
+            consolidate_reports(reports)
+        } else {
+            reports
+        }
+    }
+
+    /// Like [`Errors::flatten_reports`], but leaves out the reports that are in the `baseline`.
+    /// Each occurrence is matched on its own before they are consolidated, the same way they are
+    /// written by [`write_baseline`](crate::write_baseline).
+    fn reports_to_emit(
+        &self,
+        consolidate: bool,
+        baseline: Option<&mut Baseline>,
+    ) -> Vec<(&LogReportMetadata, Cow<'_, LogReportPointers>, usize)> {
+        let mut reports = self.flatten_reports(false);
+        if let Some(baseline) = baseline {
+            reports.retain(|(report, pointers, _)| {
+                !baseline.matches(&fingerprint(self, report, pointers))
+            });
+        }
+        if consolidate {
+            consolidate_reports(reports)
+        } else {
+            reports
+        }
     }
 
     /// Print the stored reports.
@@ -189,7 +252,8 @@ impl Errors<'_> {
     /// Note that the default output format is not stable across versions. It is meant for human
     /// readability and occasionally gets changed to improve that.
     ///
-    /// Reports matched by `#tiger-ignore` directives will not be printed.
+    /// Reports matched by `#tiger-ignore` directives will not be printed, and neither will reports
+    /// that are in the loaded baseline.
     ///
     /// Returns true iff any reports were printed.
     pub fn emit_reports<O: Write + Send>(
@@ -199,8 +263,10 @@ impl Errors<'_> {
         consolidate: bool,
         summary: bool,
     ) -> bool {
-        let reports = self.flatten_reports(consolidate);
+        let mut baseline = self.baseline.take();
+        let reports = self.reports_to_emit(consolidate, baseline.as_mut());
         let result = !reports.is_empty();
+        let emitted = EmitSummary::new(&reports);
         if json {
             _ = writeln!(output, "[");
             let mut first = true;
@@ -216,24 +282,59 @@ impl Errors<'_> {
             for (report, pointers, additional) in &reports {
                 log_report(self, output, report, pointers, *additional);
             }
+            if self.explain {
+                log_explanations(output, &self.styles, &reports);
+            }
             if summary {
                 log_summary(output, &self.styles, &reports);
             }
         }
-        self.storage.clear();
+        if !self.keep_reports {
+            self.storage.clear();
+        }
+        self.baseline = baseline;
+        self.emitted = emitted;
+        result
+    }
+
+    /// Print the stored reports as a SARIF log, for use with code scanning tools.
+    /// The `tool_name` and `tool_version` identify the program that produced the reports.
+    ///
+    /// Reports matched by `#tiger-ignore` directives will not be printed.
+    ///
+    /// Returns true iff any reports were printed.
+    pub fn emit_reports_sarif<O: Write + Send>(
+        &mut self,
+        output: &mut O,
+        tool_name: &str,
+        tool_version: &str,
+    ) -> bool {
+        let mut baseline = self.baseline.take();
+        let reports = self.reports_to_emit(false, baseline.as_mut());
+        let result = !reports.is_empty();
+        let emitted = EmitSummary::new(&reports);
+        log_reports_sarif(self, output, &reports, tool_name, tool_version);
+        if !self.keep_reports {
+            self.storage.clear();
+        }
+        self.baseline = baseline;
+        self.emitted = emitted;
         result
     }
 
     pub fn store_source_file(&mut self, fullpath: PathBuf, source: &'static str) {
+        // Drop any stale line split from a previous run, so that a re-read file is shown correctly.
+        self.cache.linecache.borrow_mut().remove(&fullpath);
         self.cache.filecache.borrow_mut().insert(fullpath, source);
     }
 
-    /// Get a mutable lock on the global ERRORS struct.
+    /// Get a mutable lock on the ERRORS struct of the current [`Session`](crate::Session), or on
+    /// the global one if this thread is not working for a session.
     ///
     /// # Panics
     /// May panic when the mutex has been poisoned by another thread.
     pub fn get_mut() -> MutexGuard<'static, Errors<'static>> {
-        ERRORS.lock().unwrap()
+        SESSION_ERRORS.get().unwrap_or(&ERRORS).lock().unwrap()
     }
 
     /// Like [`Errors::get_mut`] but intended for read-only access.
@@ -244,7 +345,46 @@ impl Errors<'_> {
     /// # Panics
     /// May panic when the mutex has been poisoned by another thread.
     pub fn get() -> MutexGuard<'static, Errors<'static>> {
-        ERRORS.lock().unwrap()
+        SESSION_ERRORS.get().unwrap_or(&ERRORS).lock().unwrap()
+    }
+}
+
+/// The number of reports of each severity that were printed by the last call to
+/// [`emit_reports`] or [`emit_reports_sarif`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EmitSummary {
+    counts: [usize; Severity::COUNT],
+}
+
+/// Count the reports with the given severities, as if they were printed.
+impl FromIterator<Severity> for EmitSummary {
+    fn from_iter<I: IntoIterator<Item = Severity>>(iter: I) -> Self {
+        let mut counts = [0; Severity::COUNT];
+        for severity in iter {
+            counts[severity as usize] += 1;
+        }
+        Self { counts }
+    }
+}
+
+impl EmitSummary {
+    fn new(reports: &[(&LogReportMetadata, Cow<'_, LogReportPointers>, usize)]) -> Self {
+        reports.iter().map(|(report, _, _)| report.severity).collect()
+    }
+
+    /// The number of reports that were printed.
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// The number of printed reports with exactly this severity.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.counts[severity as usize]
+    }
+
+    /// The number of printed reports with this severity or a higher one.
+    pub fn at_least(&self, severity: Severity) -> usize {
+        self.counts[severity as usize..].iter().sum()
     }
 }
 
@@ -259,6 +399,11 @@ pub(crate) struct Cache {
 }
 
 impl Cache {
+    /// Fetch the contents of a file as it was when it was loaded, if it was cached.
+    pub(crate) fn get_source(&self, fullpath: &Path) -> Option<&'static str> {
+        self.filecache.borrow().get(fullpath).copied()
+    }
+
     /// Fetch the contents of a single line from a script file.
     pub(crate) fn get_line(&self, loc: Loc) -> Option<&'static str> {
         let mut filecache = self.filecache.borrow_mut();
@@ -299,6 +444,37 @@ struct IgnoreEntry {
     start: Bound<u32>,
     end: Bound<u32>,
     filter: IgnoreFilter,
+    /// Whether any report matched this directive, including reports that were filtered out.
+    used: bool,
+}
+
+/// Merge the occurrences of each report whose key is in [`LOG_ONCE`] into its first occurrence,
+/// with a count of the others. The `reports` must be sorted as by [`Errors::flatten_reports`].
+fn consolidate_reports<'a>(
+    reports: Vec<(&'a LogReportMetadata, Cow<'a, LogReportPointers>, usize)>,
+) -> Vec<(&'a LogReportMetadata, Cow<'a, LogReportPointers>, usize)> {
+    let mut seen: TigerHashMap<&LogReportMetadata, usize> = TigerHashMap::default();
+    let mut consolidated: Vec<(_, _, usize)> = Vec::with_capacity(reports.len());
+    for (report, pointers, additional) in reports {
+        if report.style == LogReportStyle::Full && LOG_ONCE.contains(&report.key) {
+            if let Some(&idx) = seen.get(report) {
+                consolidated[idx].2 += 1;
+                continue;
+            }
+            seen.insert(report, consolidated.len());
+        }
+        consolidated.push((report, pointers, additional));
+    }
+    consolidated
+}
+
+/// Forget the loaded mod and DLC labels and the `tiger-ignore` directives of an earlier load, so
+/// that a new [`Everything`](crate::Everything) starts without them.
+pub(crate) fn forget_previous_load() {
+    let mut errors = Errors::get_mut();
+    errors.loaded_mods_labels.clear();
+    errors.loaded_dlcs_labels.clear();
+    errors.ignore.clear();
 }
 
 /// Record a secondary mod to be loaded before the one being validated.
@@ -337,6 +513,8 @@ fn recursive_pointed_msg_expansion(vec: &mut LogReportPointers, pointer: &Pointe
             loc: MACRO_MAP.get_loc(link).unwrap(),
             length: 1,
             msg: Some("from here".to_owned()),
+            fix: None,
+            fix_end: None,
         };
         let index = vec.len();
         recursive_pointed_msg_expansion(vec, &from_here);
@@ -366,6 +544,24 @@ pub fn emit_reports<O: Write + Send>(
     Errors::get_mut().emit_reports(output, json, consolidate, summary)
 }
 
+/// Print all the stored reports as a SARIF log to the given output.
+/// The `tool_name` and `tool_version` identify the program that produced the reports.
+///
+/// Returns true iff any reports were printed.
+pub fn emit_reports_sarif<O: Write + Send>(
+    output: &mut O,
+    tool_name: &str,
+    tool_version: &str,
+) -> bool {
+    Errors::get_mut().emit_reports_sarif(output, tool_name, tool_version)
+}
+
+/// Return what the last call to [`emit_reports`] or [`emit_reports_sarif`] printed, after
+/// filtering, suppression, and comparing to the baseline.
+pub fn emit_summary() -> EmitSummary {
+    Errors::get().emitted
+}
+
 /// Extract the stored reports, sort them, and return them as a hashmap with the occurrences for
 /// each instance of metadata split out.
 ///
@@ -374,18 +570,142 @@ pub fn take_reports() -> TigerHashMap<LogReportMetadata, TigerHashSet<LogReportP
     take(&mut Errors::get_mut().storage)
 }
 
+/// Keep the stored reports after [`emit_reports`] or [`emit_reports_sarif`] print them, instead of
+/// clearing them. This is for watching a mod, where only the reports about the files that are
+/// validated again get replaced and the full set is printed after each change.
+pub fn set_keep_reports(v: bool) {
+    Errors::get_mut().keep_reports = v;
+}
+
+/// Forget the stored reports that point into any of the given files, because those files are
+/// about to be read or validated again.
+pub(crate) fn forget_reports_in(files: &TigerHashSet<&Path>) {
+    Errors::get_mut().storage.retain(|_, occurrences| {
+        occurrences.retain(|pointers| !pointers.iter().any(|p| files.contains(p.loc.pathname())));
+        !occurrences.is_empty()
+    });
+}
+
+/// Return the files with stored reports whose messages name one of the given keys. These are
+/// usually reports about the keys being unknown, and uses of unknown items are not always
+/// recorded.
+pub(crate) fn files_with_reports_naming(keys: &TigerHashSet<&str>) -> TigerHashSet<&'static Path> {
+    let errors = Errors::get();
+    let mut files = TigerHashSet::default();
+    for (report, occurrences) in &errors.storage {
+        let mut words =
+            report.msg.split(|c: char| c.is_whitespace() || matches!(c, '`' | '\'' | '"' | ','));
+        if words.any(|word| keys.contains(word)) {
+            files.extend(occurrences.iter().map(|pointers| pointers[0].loc.pathname()));
+        }
+    }
+    files
+}
+
+/// Extract the stored reports, sorted and with `#tiger-ignore` directives applied, in the order
+/// in which [`emit_reports`] would print them.
+///
+/// The stored reports will be left empty.
+pub fn take_sorted_reports() -> Vec<LogReport> {
+    let mut errors = Errors::get_mut();
+    let reports = errors
+        .flatten_reports(false)
+        .into_iter()
+        .map(|(report, pointers, _)| (report.clone(), pointers.into_owned()))
+        .collect();
+    errors.storage.clear();
+    reports
+}
+
 pub fn store_source_file(fullpath: PathBuf, source: &'static str) {
     Errors::get_mut().store_source_file(fullpath, source);
 }
 
+/// Register a `tiger-ignore` directive for the given lines of a file.
+///
+/// If the directive has expired, it is not registered and a report about it is made instead.
 pub fn register_ignore_filter<R>(pathname: &'static Path, lines: R, filter: IgnoreFilter)
 where
     R: RangeBounds<u32>,
 {
+    let mut errors = Errors::get_mut();
+    if let Some(until) = &filter.until {
+        if until.has_expired(errors.game_version.as_deref()) {
+            drop(errors);
+            if let Some(loc) = filter.loc {
+                warn(ErrorKey::TigerIgnore)
+                    .msg(format!("this tiger-ignore expired at {until}"))
+                    .info("check if the reports it suppressed are still relevant, then remove it or update its `until`")
+                    .loc(loc)
+                    .push();
+            }
+            return;
+        }
+    }
     let start = lines.start_bound().cloned();
     let end = lines.end_bound().cloned();
-    let entry = IgnoreEntry { start, end, filter };
-    Errors::get_mut().ignore.entry(pathname).or_default().push(entry);
+    let entries = errors.ignore.entry(pathname).or_default();
+    // A file that is read again because it shares a directory with a changed file registers its
+    // directives again.
+    if filter.loc.is_some()
+        && entries.iter().any(|e| e.start == start && e.end == end && e.filter.loc == filter.loc)
+    {
+        return;
+    }
+    entries.push(IgnoreEntry { start, end, filter, used: false });
+}
+
+/// Forget the `tiger-ignore` directives of a file, because it changed and is about to be read
+/// again.
+pub(crate) fn forget_ignore_filters(pathname: &Path) {
+    Errors::get_mut().ignore.remove(pathname);
+}
+
+/// Report the `tiger-ignore` directives in the mod's files that did not match any report.
+///
+/// Reports that were dropped by the config's filter still count as matched.
+pub fn check_unused_ignores() {
+    let errors = Errors::get_mut();
+    let mut unused = Vec::new();
+    for entry in errors.ignore.values().flatten() {
+        if let Some(loc) = entry.filter.loc {
+            if loc.kind == FileKind::Mod && !entry.used {
+                unused.push(loc);
+            }
+        }
+    }
+    drop(errors);
+    for loc in unused {
+        untidy(ErrorKey::TigerIgnore)
+            .msg("this tiger-ignore did not suppress any reports")
+            .info("it can be removed")
+            .loc(loc)
+            .push();
+    }
+}
+
+// =================================================================================================
+// =============== Sessions:
+// =================================================================================================
+
+/// Get a fresh `Errors` for a new session.
+pub(crate) fn new_session_errors() -> &'static Mutex<Errors<'static>> {
+    FREE_SESSION_ERRORS
+        .lock()
+        .unwrap()
+        .pop()
+        .unwrap_or_else(|| Box::leak(Box::new(Mutex::new(Errors::default()))))
+}
+
+/// Reset the `Errors` of a session that has ended, and make it available to new sessions.
+pub(crate) fn release_session_errors(errors: &'static Mutex<Errors<'static>>) {
+    *errors.lock().unwrap() = Errors::default();
+    FREE_SESSION_ERRORS.lock().unwrap().push(errors);
+}
+
+/// Make the current thread log its reports to the given session's `Errors`.
+pub(crate) fn set_session_errors(errors: &'static Mutex<Errors<'static>>) {
+    SESSION_ERRORS.set(Some(errors));
 }
 
 // =================================================================================================
@@ -402,6 +722,18 @@ pub fn disable_ansi_colors() {
     Errors::get_mut().styles = OutputStyle::no_color();
 }
 
+/// Configure the text output to end with a long-form explanation of each kind of report that
+/// was printed.
+pub fn set_explain(v: bool) {
+    Errors::get_mut().explain = v;
+}
+
+/// Configure the text and JSON output to include, for reports inside macro expansions, the
+/// scripted items that were expanded and the arguments they got.
+pub fn set_macro_trace(v: bool) {
+    Errors::get_mut().macro_trace = v;
+}
+
 // =================================================================================================
 // =============== Configuration (Filter):
 // =================================================================================================
@@ -412,6 +744,11 @@ pub fn set_show_vanilla(v: bool) {
     Errors::get_mut().filter.show_vanilla = v;
 }
 
+/// Record the version of the game, for `tiger-ignore` directives that expire at a game version.
+pub(crate) fn set_game_version(version: Option<String>) {
+    Errors::get_mut().game_version = version;
+}
+
 /// Configure the error reporter to show errors that are in extra loaded mods.
 /// Normally those are filtered out, to only show errors that involve the mod's code.
 pub fn set_show_loaded_mods(v: bool) {
@@ -422,3 +759,11 @@ pub fn set_show_loaded_mods(v: bool) {
 pub(crate) fn set_predicate(predicate: FilterRule) {
     Errors::get_mut().filter.predicate = predicate;
 }
+
+/// Configure the error reporter to give the reports of the listed keys a different severity
+/// and/or confidence than the validator does.
+pub(crate) fn set_severity_overrides(
+    overrides: TigerHashMap<ErrorKey, (Option<Severity>, Option<Confidence>)>,
+) {
+    Errors::get_mut().severity_overrides = overrides;
+}
@@ -4,6 +4,7 @@ use std::path::Path;
 use crate::block::Comparator;
 
 use crate::fileset::FileKind;
+use crate::item::Item;
 use crate::report::{
     err, Confidence, ErrorKey, ErrorLoc, LogReportMetadata, LogReportPointers, Severity,
 };
@@ -28,10 +29,13 @@ impl ReportFilter {
     /// - Its Severity or Confidence level is too low.
     /// - It's from vanilla or a loaded mod and the program is configured to ignore those locations.
     /// - The filter has a trigger, and the report doesn't match it.
+    ///
+    /// The `loaded_mods_labels` are needed to match `mod = <label>` rules.
     pub fn should_print_report(
         &self,
         report: &LogReportMetadata,
         pointers: &LogReportPointers,
+        loaded_mods_labels: &[String],
     ) -> bool {
         if report.key == ErrorKey::Config {
             // Any errors concerning the Config should be easy to fix and will fundamentally
@@ -46,7 +50,7 @@ impl ReportFilter {
         if out_of_scope {
             return false;
         }
-        self.predicate.apply(report, pointers)
+        self.predicate.apply(report, pointers, loaded_mods_labels)
     }
 
     /// TODO: Check the filter rules to be more sure.
@@ -93,36 +97,79 @@ pub enum FilterRule {
     File(Pattern),
     /// The report's msg must contain the given text for the report to match the rule.
     Text(String),
+    /// The report's pointers must contain a file from the loaded mod with this label for the
+    /// report to match the rule.
+    Mod(String),
+    /// The report's pointers must contain a file from the given kind of source for the report to
+    /// match the rule.
+    From(FileSource),
+    /// The report's number of pointers must be within the given range to match the rule.
+    /// The condition is built like `pointers >= 2` in the filter trigger.
+    Pointers(Comparator, usize),
+    /// The line number of the report's main location must be within the given range to match the
+    /// rule. The condition is built like `line >= 100` in the filter trigger.
+    Line(Comparator, u32),
+    /// The report must have been made while validating an item of this type to match the rule.
+    ItemType(Item),
+}
+
+/// Where a file comes from, for the `from` filter rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileSource {
+    /// The base game, including its bundled directories.
+    Vanilla,
+    /// The mod under scrutiny.
+    Mod,
+    /// Downloadable content.
+    Dlc,
+}
+
+impl FileSource {
+    fn matches(self, kind: FileKind) -> bool {
+        match self {
+            FileSource::Vanilla => {
+                matches!(kind, FileKind::Clausewitz | FileKind::Jomini | FileKind::Vanilla)
+            }
+            FileSource::Mod => kind == FileKind::Mod,
+            FileSource::Dlc => matches!(kind, FileKind::Dlc(_)),
+        }
+    }
+}
+
+fn compare<T: PartialOrd + Copy>(comparator: Comparator, value: T, reference: T) -> bool {
+    match comparator {
+        Comparator::Equals(_) => value == reference,
+        Comparator::NotEquals => value != reference,
+        Comparator::GreaterThan => value > reference,
+        Comparator::AtLeast => value >= reference,
+        Comparator::LessThan => value < reference,
+        Comparator::AtMost => value <= reference,
+    }
 }
 
 impl FilterRule {
-    fn apply(&self, report: &LogReportMetadata, pointers: &LogReportPointers) -> bool {
+    fn apply(
+        &self,
+        report: &LogReportMetadata,
+        pointers: &LogReportPointers,
+        loaded_mods_labels: &[String],
+    ) -> bool {
         match self {
             FilterRule::Tautology => true,
             FilterRule::Contradiction => false,
             FilterRule::Conjunction(children) => {
-                children.iter().all(|child| child.apply(report, pointers))
+                children.iter().all(|child| child.apply(report, pointers, loaded_mods_labels))
             }
             FilterRule::Disjunction(children) => {
-                children.iter().any(|child| child.apply(report, pointers))
+                children.iter().any(|child| child.apply(report, pointers, loaded_mods_labels))
+            }
+            FilterRule::Negation(child) => !child.apply(report, pointers, loaded_mods_labels),
+            FilterRule::Severity(comparator, level) => {
+                compare(*comparator, report.severity, *level)
+            }
+            FilterRule::Confidence(comparator, level) => {
+                compare(*comparator, report.confidence, *level)
             }
-            FilterRule::Negation(child) => !child.apply(report, pointers),
-            FilterRule::Severity(comparator, level) => match comparator {
-                Comparator::Equals(_) => report.severity == *level,
-                Comparator::NotEquals => report.severity != *level,
-                Comparator::GreaterThan => report.severity > *level,
-                Comparator::AtLeast => report.severity >= *level,
-                Comparator::LessThan => report.severity < *level,
-                Comparator::AtMost => report.severity <= *level,
-            },
-            FilterRule::Confidence(comparator, level) => match comparator {
-                Comparator::Equals(_) => report.confidence == *level,
-                Comparator::NotEquals => report.confidence != *level,
-                Comparator::GreaterThan => report.confidence > *level,
-                Comparator::AtLeast => report.confidence >= *level,
-                Comparator::LessThan => report.confidence < *level,
-                Comparator::AtMost => report.confidence <= *level,
-            },
             FilterRule::Key(key) => report.key == *key,
             FilterRule::File(pattern) => pointers.iter().any(|pointer| {
                 pattern.matches_path(pointer.loc.pathname())
@@ -131,6 +178,18 @@ impl FilterRule {
             FilterRule::Text(s) => {
                 report.msg.to_ascii_lowercase().contains(&s.to_ascii_lowercase())
             }
+            FilterRule::Mod(label) => pointers.iter().any(|pointer| match pointer.loc.kind {
+                FileKind::LoadedMod(idx) => {
+                    loaded_mods_labels.get(idx as usize).is_some_and(|l| l == label)
+                }
+                _ => false,
+            }),
+            FilterRule::From(source) => {
+                pointers.iter().any(|pointer| source.matches(pointer.loc.kind))
+            }
+            FilterRule::Pointers(comparator, count) => compare(*comparator, pointers.len(), *count),
+            FilterRule::Line(comparator, line) => compare(*comparator, pointers[0].loc.line, *line),
+            FilterRule::ItemType(item) => report.item == Some(*item),
         }
     }
     pub fn file_from_token(token: &Token) -> Option<FilterRule> {
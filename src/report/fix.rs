@@ -0,0 +1,284 @@
+//! Applying the fixes suggested by reports to the mod's files.
+
+use std::fs::{read, write};
+use std::iter::once;
+use std::path::Path;
+
+use crate::fileset::FileKind;
+use crate::helpers::TigerHashMap;
+use crate::report::errors::Errors;
+use crate::report::{Confidence, LogReportMetadata, PointedMessage};
+use crate::token::{Loc, Token};
+
+const BOM: &str = "\u{feff}";
+
+/// A single suggested replacement, located by line and column.
+/// The replaced text ends just before `end_line` and `end_column`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Edit {
+    line: u32,
+    column: u32,
+    end_line: u32,
+    end_column: u32,
+    replacement: String,
+}
+
+/// What [`apply_fixes`] did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FixSummary {
+    /// The number of fixes that were applied.
+    pub applied: usize,
+    /// The number of files that were changed.
+    pub files: usize,
+    /// The number of fixes that could not be applied safely.
+    pub skipped: usize,
+}
+
+/// Apply the fixes suggested by the stored reports to the mod's files.
+///
+/// Only files of the mod itself are changed, never those of the base game or of other loaded
+/// mods. Fixes from reports with weak confidence, and fixes to text that came from a macro
+/// expansion, are not applied. A file is left alone if it changed since it was loaded.
+///
+/// The stored reports are not changed, so they can still be printed afterward.
+pub fn apply_fixes() -> FixSummary {
+    let errors = Errors::get();
+    let mut edits: TigerHashMap<&'static Path, Vec<Edit>> = TigerHashMap::default();
+    for (report, pointers, _) in errors.flatten_reports(false) {
+        for pointer in pointers.iter() {
+            let Some(fix) = applicable_fix(report, pointer) else {
+                continue;
+            };
+            let loc = pointer.loc;
+            let column = loc.column.max(1);
+            #[allow(clippy::cast_possible_truncation)]
+            let (end_line, end_column) = match pointer.fix_end {
+                Some(end) => (end.line, end.column),
+                None => (loc.line, column + pointer.length as u32),
+            };
+            edits.entry(loc.fullpath()).or_default().push(Edit {
+                line: loc.line,
+                column,
+                end_line,
+                end_column,
+                replacement: fix.to_owned(),
+            });
+        }
+    }
+
+    let mut summary = FixSummary::default();
+    for (fullpath, mut edits) in edits {
+        edits.sort();
+        edits.dedup();
+        let count = edits.len();
+        match apply_file_edits(fullpath, errors.cache.get_source(fullpath), edits) {
+            Some((applied, skipped)) => {
+                summary.applied += applied;
+                summary.skipped += skipped;
+                if applied > 0 {
+                    summary.files += 1;
+                }
+            }
+            None => summary.skipped += count,
+        }
+    }
+    summary
+}
+
+/// Return the fix suggested by `pointer`, if [`apply_fixes`] would apply it: the report is not
+/// weak, and the location is in the mod's own files and not in a macro expansion.
+pub fn applicable_fix<'a>(
+    report: &LogReportMetadata,
+    pointer: &'a PointedMessage,
+) -> Option<&'a str> {
+    let loc = pointer.loc;
+    if report.confidence < Confidence::Reasonable
+        || loc.kind != FileKind::Mod
+        || loc.link_idx.is_some()
+        || loc.line == 0
+    {
+        return None;
+    }
+    pointer.fix.as_deref()
+}
+
+/// Apply the `edits` to one file. Returns the number of applied and skipped edits, or `None`
+/// if the file could not be changed at all.
+fn apply_file_edits(
+    fullpath: &Path,
+    loaded: Option<&str>,
+    mut edits: Vec<Edit>,
+) -> Option<(usize, usize)> {
+    let bytes = read(fullpath).ok()?;
+    let contents = String::from_utf8(bytes).ok()?;
+    let (bom, contents) = match contents.strip_prefix(BOM) {
+        Some(rest) => (BOM, rest),
+        None => ("", contents.as_str()),
+    };
+    if loaded.is_some_and(|loaded| loaded != contents) {
+        eprintln!("Not fixing {} because it changed since it was loaded", fullpath.display());
+        return None;
+    }
+
+    let line_starts: Vec<usize> =
+        once(0).chain(contents.match_indices('\n').map(|(i, _)| i + 1)).collect();
+    // Find the byte offset of a line and column. The column may be just past the end of the line.
+    let offset = |line: u32, column: u32| -> Option<usize> {
+        let start = *line_starts.get((line as usize).checked_sub(1)?)?;
+        let text = &contents[start..];
+        let text = text[..text.find('\n').unwrap_or(text.len())].trim_end_matches('\r');
+        let column = (column as usize).checked_sub(1)?;
+        if column == text.chars().count() {
+            return Some(start + text.len());
+        }
+        text.char_indices().nth(column).map(|(i, _)| start + i)
+    };
+
+    let mut new_contents = contents.to_owned();
+    let (mut applied, mut skipped) = (0, 0);
+    // Apply the edits from back to front, so that the earlier positions stay valid.
+    edits.reverse();
+    let mut next_start: Option<usize> = None;
+    for edit in edits {
+        let start = offset(edit.line, edit.column);
+        let end = offset(edit.end_line, edit.end_column);
+        let (Some(start), Some(end)) = (start, end) else {
+            skipped += 1;
+            continue;
+        };
+        if end < start || next_start.is_some_and(|next_start| end > next_start) {
+            skipped += 1;
+            continue;
+        }
+        new_contents.replace_range(start..end, &edit.replacement);
+        next_start = Some(start);
+        applied += 1;
+    }
+
+    if applied > 0 {
+        if let Err(e) = write(fullpath, format!("{bom}{new_contents}")) {
+            eprintln!("Could not write {}: {e:#}", fullpath.display());
+            return None;
+        }
+    }
+    Some((applied, skipped))
+}
+
+/// Return the location just after the definition `key = { ... }`, or `None` if the definition
+/// can't be found in the file as it was loaded.
+///
+/// If the definition is followed only by whitespace on its last line, the end of that line is
+/// included, so that removing the definition leaves no blank line behind.
+pub(crate) fn definition_end(key: &Token) -> Option<Loc> {
+    let loc = key.loc;
+    if loc.line == 0 || loc.link_idx.is_some() {
+        return None;
+    }
+    let source = Errors::get().cache.get_source(loc.fullpath())?;
+    let line_start: usize =
+        source.split_inclusive('\n').take(loc.line as usize - 1).map(str::len).sum();
+    let line = source.get(line_start..)?;
+    let text = &line[line.char_indices().nth((loc.column as usize).checked_sub(1)?)?.0..];
+    let value = text.strip_prefix(key.as_str())?.trim_start().strip_prefix('=')?.trim_start();
+    if !value.starts_with('{') {
+        return None;
+    }
+
+    let mut close = None;
+    let mut depth = 0;
+    let (mut in_string, mut in_comment, mut escaped) = (false, false, false);
+    for (i, c) in text.char_indices() {
+        if in_comment {
+            in_comment = c != '\n';
+        } else if in_string {
+            in_string = escaped || c != '"';
+            escaped = !escaped && c == '\\';
+        } else if c == '"' {
+            in_string = true;
+        } else if c == '#' {
+            in_comment = true;
+        } else if c == '{' {
+            depth += 1;
+        } else if c == '}' {
+            depth -= 1;
+            if depth == 0 {
+                close = Some(i);
+                break;
+            }
+        }
+    }
+
+    let (definition, after) = text.split_at(close? + 1);
+    let newlines = definition.matches('\n').count();
+    #[allow(clippy::cast_possible_truncation)]
+    let (line, column) = if newlines == 0 {
+        (loc.line, loc.column + definition.chars().count() as u32)
+    } else {
+        let last_line = definition.rsplit('\n').next().unwrap_or_default();
+        (loc.line + newlines as u32, last_line.chars().count() as u32 + 1)
+    };
+    match after.find('\n') {
+        Some(i) if after[..i].trim().is_empty() => Some(Loc { line: line + 1, column: 1, ..loc }),
+        _ => Some(Loc { line, column, ..loc }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(line: u32, column: u32, end_line: u32, end_column: u32, replacement: &str) -> Edit {
+        Edit { line, column, end_line, end_column, replacement: replacement.to_owned() }
+    }
+
+    #[test]
+    fn test_apply_file_edits() {
+        let path = std::env::temp_dir().join(format!("tiger-fix-edits-{}.txt", std::process::id()));
+        let contents = "a = {\n\told = 1\n}\nb = {\n\tc = 2\n}\n";
+        write(&path, format!("{BOM}{contents}")).unwrap();
+
+        // A file that changed since it was loaded is left alone.
+        assert_eq!(apply_file_edits(&path, Some("a = {}\n"), vec![edit(1, 1, 1, 2, "x")]), None);
+
+        // The edits are sorted, as by `apply_fixes`.
+        let edits = vec![
+            // Past the end of the line.
+            edit(1, 5, 1, 9, "x"),
+            edit(2, 2, 2, 5, "new"),
+            // Overlaps the change to `c` below, so it is skipped.
+            edit(4, 1, 7, 1, ""),
+            edit(5, 2, 5, 3, "d"),
+        ];
+        assert_eq!(apply_file_edits(&path, Some(contents), edits), Some((2, 2)));
+        let expected = format!("{BOM}a = {{\n\tnew = 1\n}}\nb = {{\n\td = 2\n}}\n");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+
+        // A definition is removed up to the start of the next line.
+        assert_eq!(apply_file_edits(&path, None, vec![edit(4, 1, 7, 1, "")]), Some((1, 0)));
+        let expected = format!("{BOM}a = {{\n\tnew = 1\n}}\n");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+        _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_definition_end() {
+        crate::Session::new().unwrap().run(|| {
+            let fullpath = std::env::temp_dir().join("tiger-definition-end.txt");
+            let source = "a = {\n\tb = 1\n}\nc = { d = 2 }  \ne = 3\n";
+            Errors::get_mut().store_source_file(fullpath.clone(), source);
+            let file = Loc::for_file("test.txt".into(), FileKind::Mod, fullpath);
+            let end = |key: &str, line, column| {
+                definition_end(&Token::new(key, Loc { line, column, ..file }))
+                    .map(|loc| (loc.line, loc.column))
+            };
+
+            // The trailing whitespace and newline are removed with the definition.
+            assert_eq!(end("a", 1, 1), Some((4, 1)));
+            assert_eq!(end("c", 4, 1), Some((5, 1)));
+            // Not a block.
+            assert_eq!(end("e", 5, 1), None);
+            // Locations without a column can't be looked up.
+            assert_eq!(end("a", 1, 0), None);
+        });
+    }
+}
@@ -1,24 +1,39 @@
 //! Error report collection and printing facilities.
 
-pub(crate) use builder::{err, fatal, report, tips, untidy, warn, ReportBuilderFull};
-pub(crate) use error_key::ErrorKey;
+pub use baseline::{baseline_summary, load_baseline, write_baseline, BaselineSummary};
+pub(crate) use builder::{
+    err, fatal, report, tips, untidy, warn, with_validating_item, ReportBuilderFull,
+};
+pub use diff::{
+    diff_reports, saved_reports_from_json, take_saved_reports, ReportDiff, SavedLocation,
+    SavedReport,
+};
+pub use error_key::ErrorKey;
 pub(crate) use error_loc::ErrorLoc;
 pub use errors::*;
-pub(crate) use filter::FilterRule;
+pub(crate) use filter::{FileSource, FilterRule};
+pub(crate) use fix::definition_end;
+pub use fix::{applicable_fix, apply_fixes, FixSummary};
 pub(crate) use output_style::OutputStyle;
 pub use report_struct::{
     Confidence, LogReport, LogReportMetadata, LogReportPointers, LogReportStyle, PointedMessage,
     Severity,
 };
+pub use stats::{report_stats, ReportStats};
 pub use suppress::suppress_from_json;
 
+mod baseline;
 mod builder;
+mod diff;
 mod error_key;
 mod error_loc;
 mod errors;
 mod filter;
+mod fix;
 mod output_style;
 mod report_struct;
+mod stats;
 mod suppress;
 mod writer;
 mod writer_json;
+mod writer_sarif;
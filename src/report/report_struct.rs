@@ -1,6 +1,9 @@
-use serde::Serialize;
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
 use strum_macros::{Display, EnumCount, EnumIter, EnumString, IntoStaticStr};
 
+use crate::item::Item;
 use crate::report::ErrorKey;
 use crate::token::Loc;
 
@@ -13,7 +16,10 @@ pub enum LogReportStyle {
 }
 
 /// Describes a report about a potentially problematic situation that can be logged.
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+///
+/// The `item` is not part of its identity, so that a report made from the body of a scripted
+/// effect or trigger is stored once no matter what kind of item called it.
+#[derive(Debug, Clone)]
 pub struct LogReportMetadata {
     /// Used for choosing output colors and for filtering reports.
     pub severity: Severity,
@@ -27,10 +33,37 @@ pub struct LogReportMetadata {
     pub info: Option<String>,
     /// Optional wiki link to be printed at the end.
     pub wiki: Option<String>,
+    /// The type of item that was being validated when the report was made, if known.
+    /// Used for filtering reports.
+    pub item: Option<Item>,
     // /// Output style for this report
     pub style: LogReportStyle,
 }
 
+impl LogReportMetadata {
+    #[allow(clippy::type_complexity)]
+    fn identity(
+        &self,
+    ) -> (Severity, Confidence, ErrorKey, &str, Option<&str>, Option<&str>, LogReportStyle) {
+        let Self { severity, confidence, key, msg, info, wiki, item: _, style } = self;
+        (*severity, *confidence, *key, msg, info.as_deref(), wiki.as_deref(), *style)
+    }
+}
+
+impl PartialEq for LogReportMetadata {
+    fn eq(&self, other: &Self) -> bool {
+        self.identity() == other.identity()
+    }
+}
+
+impl Eq for LogReportMetadata {}
+
+impl Hash for LogReportMetadata {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.identity().hash(state);
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct PointedMessage {
     /// Which file and where in the file the error occurs.
@@ -45,11 +78,17 @@ pub struct PointedMessage {
     pub length: usize,
     /// A short message that will be printed at the caret location.
     pub msg: Option<String>,
+    /// Optional replacement for the offending phrase, that would resolve the report.
+    /// The phrase is the `length` characters starting at `loc`, unless `fix_end` is set.
+    pub fix: Option<String>,
+    /// Where the text replaced by `fix` ends, if that is not the end of the offending phrase.
+    /// This lets a fix span several lines, for example to remove a whole definition.
+    pub fix_end: Option<Loc>,
 }
 
 impl PointedMessage {
     pub fn new(loc: Loc) -> Self {
-        Self { loc, msg: None, length: 0 }
+        Self { loc, msg: None, length: 0, fix: None, fix_end: None }
     }
 }
 
@@ -81,6 +120,7 @@ pub fn pointer_indentation(pointers: &LogReportPointers) -> usize {
     EnumCount,
     EnumIter,
     Serialize,
+    Deserialize,
 )]
 #[serde(rename_all = "kebab-case")]
 #[strum(serialize_all = "kebab-case")]
@@ -134,6 +174,7 @@ impl Severity {
     EnumIter,
     EnumString,
     Serialize,
+    Deserialize,
 )]
 #[serde(rename_all = "kebab-case")]
 #[strum(serialize_all = "kebab-case")]
@@ -0,0 +1,109 @@
+//! Counting the reports by category, for an overview of where the problems are.
+
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Component, Path};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::report::errors::Errors;
+use crate::report::{Confidence, Severity};
+
+/// How many files are listed in the table output. The JSON output has all of them.
+const TABLE_MAX_FILES: usize = 20;
+
+/// The counts of the stored reports, broken down in several ways.
+///
+/// Reports are counted by their main location, which is the first one printed.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReportStats {
+    pub total: usize,
+    pub by_severity: BTreeMap<Severity, usize>,
+    pub by_confidence: BTreeMap<Confidence, usize>,
+    pub by_key: BTreeMap<String, usize>,
+    /// Counted by the top-level folder of the file, such as `common` or `events`.
+    pub by_folder: BTreeMap<String, usize>,
+    pub by_file: BTreeMap<String, usize>,
+}
+
+/// Count the stored reports, with `#tiger-ignore` directives applied.
+///
+/// The stored reports are not changed, so they can still be printed afterward.
+pub fn report_stats() -> ReportStats {
+    let errors = Errors::get();
+    let mut stats = ReportStats::default();
+    for (report, pointers, _) in errors.flatten_reports(false) {
+        stats.total += 1;
+        *stats.by_severity.entry(report.severity).or_default() += 1;
+        *stats.by_confidence.entry(report.confidence).or_default() += 1;
+        *stats.by_key.entry(report.key.to_string()).or_default() += 1;
+        let path = pointers[0].loc.pathname();
+        *stats.by_folder.entry(top_folder(path)).or_default() += 1;
+        *stats.by_file.entry(path.to_string_lossy().into_owned()).or_default() += 1;
+    }
+    stats
+}
+
+fn top_folder(path: &Path) -> String {
+    let mut components = path.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(folder)), Some(_)) => folder.to_string_lossy().into_owned(),
+        _ => ".".to_owned(),
+    }
+}
+
+impl ReportStats {
+    /// Write the counts as human-readable tables, with the largest counts first.
+    pub fn write_table<O: Write>(&self, output: &mut O) -> Result<()> {
+        writeln!(output, "Total reports: {}", self.total)?;
+        let by_severity = self.by_severity.iter().rev().map(|(k, v)| (k.to_string(), *v));
+        write_section(output, "severity", &by_severity.collect::<Vec<_>>(), usize::MAX)?;
+        let by_confidence =
+            self.by_confidence.iter().rev().map(|(k, v)| (<&str>::from(k).to_owned(), *v));
+        write_section(output, "confidence", &by_confidence.collect::<Vec<_>>(), usize::MAX)?;
+        write_section(output, "key", &sorted_by_count(&self.by_key), usize::MAX)?;
+        write_section(output, "folder", &sorted_by_count(&self.by_folder), usize::MAX)?;
+        write_section(output, "file", &sorted_by_count(&self.by_file), TABLE_MAX_FILES)?;
+        Ok(())
+    }
+
+    /// Append the counts as a single line of JSON to a history file, together with the current
+    /// time and an optional label such as a release name. The file is created if needed.
+    pub fn append_to_history(&self, fullpath: &Path, label: Option<&str>) -> Result<()> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let mut entry = serde_json::to_value(self)?;
+        entry["timestamp"] = json!(timestamp);
+        entry["label"] = json!(label);
+        let mut file = OpenOptions::new().create(true).append(true).open(fullpath)?;
+        writeln!(file, "{entry}")?;
+        Ok(())
+    }
+}
+
+fn sorted_by_count(counts: &BTreeMap<String, usize>) -> Vec<(String, usize)> {
+    let mut vec: Vec<_> = counts.iter().map(|(k, v)| (k.clone(), *v)).collect();
+    vec.sort_by(|(k1, v1), (k2, v2)| v2.cmp(v1).then_with(|| k1.cmp(k2)));
+    vec
+}
+
+fn write_section<O: Write>(
+    output: &mut O,
+    what: &str,
+    rows: &[(String, usize)],
+    max_rows: usize,
+) -> Result<()> {
+    writeln!(output)?;
+    writeln!(output, "By {what}:")?;
+    let width = rows.iter().take(max_rows).map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, count) in rows.iter().take(max_rows) {
+        writeln!(output, "  {name:<width$}  {count:>7}")?;
+    }
+    if rows.len() > max_rows {
+        writeln!(output, "  ... and {} more", rows.len() - max_rows)?;
+    }
+    Ok(())
+}
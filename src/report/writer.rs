@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::fmt::Write as _;
 use std::io::Write;
 
 use ansiterm::{ANSIString, ANSIStrings};
@@ -8,10 +9,14 @@ use unicode_width::UnicodeWidthChar;
 
 use crate::fileset::FileKind;
 use crate::game::Game;
+use crate::macros::{MacroFrame, MACRO_MAP};
 use crate::report::errors::Errors;
 use crate::report::output_style::Styled;
 use crate::report::report_struct::pointer_indentation;
-use crate::report::{LogReportMetadata, LogReportPointers, OutputStyle, PointedMessage, Severity};
+use crate::report::{
+    ErrorKey, LogReportMetadata, LogReportPointers, OutputStyle, PointedMessage, Severity,
+};
+use crate::token::Loc;
 
 /// Source lines printed in the output have leading tab characters replaced by this number of spaces.
 const SPACES_PER_TAB: usize = 4;
@@ -43,6 +48,32 @@ pub fn log_report<O: Write + Send>(
         log_count(errors, output, indentation, additional);
     }
 
+    // Log the macro expansions that the report is in, if asked for.
+    if errors.macro_trace {
+        if let Some(pointer) = pointers.first() {
+            for (call_site, frame) in MACRO_MAP.trace(pointer.loc) {
+                log_line_trace(errors, output, indentation, call_site, &frame);
+            }
+        }
+    }
+
+    // Log the suggested fixes, if there are any.
+    for pointer in pointers {
+        if let Some(fix) = &pointer.fix {
+            let fix = match pointer.fix_end {
+                Some(end) if fix.is_empty() => {
+                    // The end is either in the last line or at the start of the next one.
+                    let last = if end.column == 1 { end.line - 1 } else { end.line };
+                    format!("remove lines {} to {last}", pointer.loc.line)
+                }
+                Some(end) => format!("replace everything up to line {} with `{fix}`", end.line),
+                None if fix.is_empty() => "remove it".to_owned(),
+                None => format!("replace with `{fix}`"),
+            };
+            log_line_fix(errors, output, indentation, &fix);
+        }
+    }
+
     // Log the info line, if one exists.
     if let Some(info) = &report.info {
         log_line_info(errors, output, indentation, info);
@@ -80,6 +111,33 @@ pub fn log_summary<O: Write + Send>(
     _ = writeln!(output, "{}", ANSIStrings(&line[..line.len() - 1]));
 }
 
+/// Log the long-form explanation of each key that occurs in the reports, in order of first
+/// occurrence.
+pub fn log_explanations<O: Write + Send>(
+    output: &mut O,
+    styles: &OutputStyle,
+    reports: &Vec<(&LogReportMetadata, Cow<'_, LogReportPointers>, usize)>,
+) {
+    let keys: Vec<ErrorKey> =
+        reports.iter().map(|(metadata, _, _)| metadata.key).unique().collect();
+    for key in keys {
+        _ = writeln!(
+            output,
+            "{}",
+            styles.style(Styled::InfoTag).paint(format!("Explanation of {key}:"))
+        );
+        _ = writeln!(output);
+        for line in key.explanation().lines() {
+            if line.is_empty() {
+                _ = writeln!(output);
+            } else {
+                _ = writeln!(output, "    {}", styles.style(Styled::Info).paint(line));
+            }
+        }
+        _ = writeln!(output);
+    }
+}
+
 fn log_pointer<O: Write + Send>(
     errors: &Errors,
     output: &mut O,
@@ -132,6 +190,53 @@ fn log_line_info<O: Write + Send>(errors: &Errors, output: &mut O, indentation:
     _ = writeln!(output, "{}", ANSIStrings(line_info));
 }
 
+/// Log one macro expansion that the report is in, with the arguments that were substituted.
+fn log_line_trace<O: Write + Send>(
+    errors: &Errors,
+    output: &mut O,
+    indentation: usize,
+    call_site: Loc,
+    frame: &MacroFrame,
+) {
+    let mut note = format!("in expansion of {} `{}`", frame.itype, frame.name);
+    for (i, (parm, arg)) in frame.args.iter().enumerate() {
+        let sep = if i == 0 { " with" } else { "," };
+        _ = write!(note, "{sep} ${parm}$ = `{arg}`");
+    }
+    _ = write!(
+        note,
+        " at [{}] {}:{}:{}",
+        kind_tag(errors, call_site.kind),
+        call_site.pathname().display(),
+        call_site.line,
+        call_site.column
+    );
+    let line_trace: &[ANSIString<'static>] = &[
+        errors.styles.style(Styled::Default).paint(format!("{:width$}", "", width = indentation)),
+        errors.styles.style(Styled::Default).paint(" "),
+        errors.styles.style(Styled::Location).paint("="),
+        errors.styles.style(Styled::Default).paint(" "),
+        errors.styles.style(Styled::InfoTag).paint("Note:"),
+        errors.styles.style(Styled::Default).paint(" "),
+        errors.styles.style(Styled::Info).paint(note),
+    ];
+    _ = writeln!(output, "{}", ANSIStrings(line_trace));
+}
+
+/// Log a description of a suggested change to the text pointed at by the report.
+fn log_line_fix<O: Write + Send>(errors: &Errors, output: &mut O, indentation: usize, fix: &str) {
+    let line_fix: &[ANSIString<'static>] = &[
+        errors.styles.style(Styled::Default).paint(format!("{:width$}", "", width = indentation)),
+        errors.styles.style(Styled::Default).paint(" "),
+        errors.styles.style(Styled::Location).paint("="),
+        errors.styles.style(Styled::Default).paint(" "),
+        errors.styles.style(Styled::InfoTag).paint("Fix:"),
+        errors.styles.style(Styled::Default).paint(" "),
+        errors.styles.style(Styled::Info).paint(fix.to_owned()),
+    ];
+    _ = writeln!(output, "{}", ANSIStrings(line_fix));
+}
+
 /// Log the optional wiki link line that is part of the overall report.
 fn log_line_wiki<O: Write + Send>(errors: &Errors, output: &mut O, indentation: usize, wiki: &str) {
     let line_info: &[ANSIString<'static>] = &[
@@ -2,6 +2,7 @@ use std::io::Write;
 
 use serde_json::json;
 
+use crate::macros::MACRO_MAP;
 use crate::report::errors::Errors;
 use crate::report::writer::kind_tag;
 use crate::report::{LogReportMetadata, LogReportPointers};
@@ -13,7 +14,7 @@ pub fn log_report_json<O: Write + Send>(
     report: &LogReportMetadata,
     pointers: &LogReportPointers,
 ) {
-    let pointers: Vec<_> = pointers
+    let locations: Vec<_> = pointers
         .iter()
         .map(|pointer| {
             let path = pointer.loc.pathname();
@@ -27,19 +28,48 @@ pub fn log_report_json<O: Write + Send>(
                 "length": if pointer.length == 0 { None } else { Some(pointer.length) },
                 "line": errors.cache.get_line(pointer.loc),
                 "tag": pointer.msg,
+                "fix": pointer.fix,
+                "fix_end": pointer.fix_end.map(|loc| json!({ "linenr": loc.line, "column": loc.column })),
             })
         })
         .collect();
-    let report = json!({
+    let mut report = json!({
         "severity": report.severity,
         "confidence": report.confidence,
         "key": report.key,
         "message": &report.msg,
         "info": &report.info,
         "wiki": &report.wiki,
-        "locations": pointers,
+        "locations": locations,
     });
 
+    // Add the macro expansions that the report is in, if asked for.
+    if errors.macro_trace {
+        if let Some(pointer) = pointers.first() {
+            let frames: Vec<_> = MACRO_MAP
+                .trace(pointer.loc)
+                .into_iter()
+                .map(|(call_site, frame)| {
+                    let args: serde_json::Map<_, _> = frame
+                        .args
+                        .iter()
+                        .map(|(parm, arg)| ((*parm).to_owned(), json!(arg)))
+                        .collect();
+                    json!({
+                        "type": <&str>::from(frame.itype),
+                        "name": frame.name,
+                        "args": args,
+                        "path": call_site.pathname(),
+                        "from": kind_tag(errors, call_site.kind),
+                        "linenr": call_site.line,
+                        "column": call_site.column,
+                    })
+                })
+                .collect();
+            report["macro_trace"] = json!(frames);
+        }
+    }
+
     if let Err(e) = serde_json::to_writer_pretty(output, &report) {
         eprintln!("JSON error: {e:#}");
     }
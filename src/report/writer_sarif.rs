@@ -0,0 +1,177 @@
+//! Output of reports in the SARIF 2.1.0 format, for use with code scanning tools.
+
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+use crate::helpers::{encode_uri_path, path_to_uri, TigerHashMap};
+use crate::report::errors::Errors;
+use crate::report::writer::kind_tag;
+use crate::report::{
+    Confidence, ErrorKey, LogReportMetadata, LogReportPointers, PointedMessage, Severity,
+};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Log all the reports as a single SARIF log with one run.
+pub fn log_reports_sarif<O: Write + Send>(
+    errors: &Errors,
+    output: &mut O,
+    reports: &[(&LogReportMetadata, Cow<'_, LogReportPointers>, usize)],
+    tool_name: &str,
+    tool_version: &str,
+) {
+    let mut rules: Vec<(ErrorKey, Option<&str>)> = Vec::new();
+    let mut rule_index: TigerHashMap<ErrorKey, usize> = TigerHashMap::default();
+    let mut base_ids = Map::new();
+    let mut results = Vec::new();
+
+    for (report, pointers, _) in reports {
+        let index = *rule_index.entry(report.key).or_insert_with(|| {
+            rules.push((report.key, None));
+            rules.len() - 1
+        });
+        if rules[index].1.is_none() {
+            rules[index].1 = report.wiki.as_deref();
+        }
+
+        for pointer in pointers.iter() {
+            let tag = kind_tag(errors, pointer.loc.kind);
+            if !base_ids.contains_key(tag) {
+                if let Some(root) = root_dir(pointer) {
+                    base_ids.insert(tag.to_owned(), json!({ "uri": dir_uri(root) }));
+                }
+            }
+        }
+
+        let mut text = report.msg.clone();
+        if let Some(info) = &report.info {
+            text.push('\n');
+            text.push_str(info);
+        }
+        let related: Vec<_> = pointers
+            .iter()
+            .skip(1)
+            .enumerate()
+            .map(|(id, pointer)| location(errors, pointer, Some(id)))
+            .collect();
+        results.push(json!({
+            "ruleId": report.key.to_string(),
+            "ruleIndex": index,
+            "level": level(report.severity),
+            "rank": rank(report.confidence),
+            "message": { "text": text },
+            "locations": pointers.first().map(|p| location(errors, p, None)).into_iter().collect::<Vec<_>>(),
+            "relatedLocations": related,
+            "properties": {
+                "severity": report.severity,
+                "confidence": report.confidence,
+            },
+        }));
+    }
+
+    let rules: Vec<_> = rules
+        .into_iter()
+        .map(|(key, wiki)| {
+            let mut rule = json!({ "id": key.to_string() });
+            if let Some(wiki) = wiki {
+                rule["helpUri"] = json!(wiki);
+            }
+            rule
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": tool_name,
+                    "version": tool_version,
+                    "informationUri": "https://github.com/amtep/tiger",
+                    "rules": rules,
+                },
+            },
+            "originalUriBaseIds": base_ids,
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    });
+
+    if let Err(e) = serde_json::to_writer_pretty(&mut *output, &log) {
+        eprintln!("JSON error: {e:#}");
+    }
+    _ = writeln!(output);
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Fatal | Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Untidy | Severity::Tips => "note",
+    }
+}
+
+/// SARIF ranks are from 0 to 100, with higher values for results that are more likely to be real
+/// problems.
+fn rank(confidence: Confidence) -> f64 {
+    match confidence {
+        Confidence::Weak => 30.0,
+        Confidence::Reasonable => 60.0,
+        Confidence::Strong => 90.0,
+    }
+}
+
+fn location(errors: &Errors, pointer: &PointedMessage, id: Option<usize>) -> Value {
+    let mut physical = json!({
+        "artifactLocation": {
+            "uri": relative_uri(pointer.loc.pathname()),
+            "uriBaseId": kind_tag(errors, pointer.loc.kind),
+        },
+    });
+    if pointer.loc.line != 0 {
+        let mut region = json!({ "startLine": pointer.loc.line });
+        if pointer.loc.column != 0 {
+            region["startColumn"] = json!(pointer.loc.column);
+            if pointer.length != 0 {
+                region["endColumn"] = json!(pointer.loc.column as usize + pointer.length);
+            }
+        }
+        if let Some(line) = errors.cache.get_line(pointer.loc) {
+            region["snippet"] = json!({ "text": line });
+        }
+        physical["region"] = region;
+    }
+    let mut location = json!({ "physicalLocation": physical });
+    if let Some(id) = id {
+        location["id"] = json!(id);
+    }
+    if let Some(msg) = &pointer.msg {
+        location["message"] = json!({ "text": msg });
+    }
+    location
+}
+
+/// Get the directory that the pointer's path is relative to.
+fn root_dir(pointer: &PointedMessage) -> Option<&Path> {
+    let mut root = pointer.loc.fullpath();
+    for _ in pointer.loc.pathname().components() {
+        root = root.parent()?;
+    }
+    Some(root)
+}
+
+fn relative_uri(path: &Path) -> String {
+    encode_uri_path(&path.to_string_lossy().replace('\\', "/"))
+}
+
+fn dir_uri(dir: &Path) -> String {
+    let mut uri = path_to_uri(dir);
+    if !uri.ends_with('/') {
+        uri.push('/');
+    }
+    uri
+}
@@ -0,0 +1,500 @@
+//! The network of rivers in the `rivers.png/bmp` file, as a graph of river segments and the
+//! special pixels that start, join, and split them.
+//!
+//! The graph can be written as `GeoJSON` or SVG, to get an overview of the rivers. Each problem in
+//! it comes with a suggestion of which pixels to change to fix it.
+
+use std::fmt::Write as _;
+use std::io::Write;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use crate::helpers::{TigerHashMap, TigerHashSet};
+use crate::rivers::{RiverPixels, Rivers};
+
+/// The kinds of special pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RiverNodeKind {
+    /// The start of a river (usually green).
+    Source,
+    /// Where a river joins another (usually red).
+    Tributary,
+    /// Where a river splits off from another (usually yellow).
+    Split,
+}
+
+impl RiverNodeKind {
+    fn from_pixel(pixel: u8) -> Option<Self> {
+        match pixel {
+            RiverPixels::SOURCE => Some(RiverNodeKind::Source),
+            RiverPixels::TRIBUTARY => Some(RiverNodeKind::Tributary),
+            RiverPixels::SPLIT => Some(RiverNodeKind::Split),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            RiverNodeKind::Source => "source",
+            RiverNodeKind::Tributary => "tributary",
+            RiverNodeKind::Split => "split",
+        }
+    }
+}
+
+/// A problem in the river network, with a suggested fix.
+#[derive(Clone, Debug)]
+pub struct RiverProblem {
+    pub msg: String,
+    /// The pixel changes that would fix the problem, such as "add a source pixel at (10, 20)".
+    pub fix: String,
+}
+
+/// A special pixel.
+#[derive(Clone, Debug)]
+pub struct RiverNode {
+    pub kind: RiverNodeKind,
+    pub x: u32,
+    pub y: u32,
+    pub problems: Vec<RiverProblem>,
+}
+
+/// A connected run of normal river pixels.
+#[derive(Clone, Debug)]
+pub struct RiverSegment {
+    /// The pixels of the segment. If `ordered` is true, they are in order from one end to the
+    /// other.
+    pub pixels: Vec<(u32, u32)>,
+    /// False if the pixels branch, so that they can't be put in order.
+    pub ordered: bool,
+    /// The lowest and highest pixel values in the segment. The values from 3 to 15 set the width
+    /// of the river.
+    pub min_width: u8,
+    pub max_width: u8,
+    /// The indexes in [`RiverGraph::nodes`] of the special pixels at the ends of the segment.
+    pub terminators: Vec<usize>,
+    pub problems: Vec<RiverProblem>,
+}
+
+/// The river network of the map.
+#[derive(Clone, Debug, Default)]
+pub struct RiverGraph {
+    /// The size of the image.
+    pub width: u32,
+    pub height: u32,
+    pub nodes: Vec<RiverNode>,
+    pub segments: Vec<RiverSegment>,
+}
+
+impl RiverGraph {
+    /// Build the graph from the loaded image. Returns `None` if the image couldn't be loaded.
+    pub(crate) fn new(rivers: &Rivers) -> Option<Self> {
+        let (_, width, height) = rivers.image()?;
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let mut graph = RiverGraph { width, height, ..Default::default() };
+        let mut node_at = TigerHashMap::default();
+        let mut neighbors = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = rivers.pixel(x, y);
+                let Some(kind) = RiverNodeKind::from_pixel(pixel) else {
+                    continue;
+                };
+                rivers.river_neighbors(x, y, &mut neighbors);
+                let mut problems = Vec::new();
+                let misplaced = match kind {
+                    RiverNodeKind::Source => neighbors.len() != 1,
+                    RiverNodeKind::Tributary | RiverNodeKind::Split => neighbors.len() < 2,
+                };
+                if misplaced {
+                    problems.push(RiverProblem {
+                        msg: format!(
+                            "{} pixel has {} river neighbors",
+                            kind.name(),
+                            neighbors.len()
+                        ),
+                        fix: fix_special(pixel, (x, y), &neighbors),
+                    });
+                }
+                node_at.insert((x, y), graph.nodes.len());
+                graph.nodes.push(RiverNode { kind, x, y, problems });
+            }
+        }
+
+        let mut seen = TigerHashSet::default();
+        for y in 0..height {
+            for x in 0..width {
+                if !RiverPixels::NORMAL.contains(&rivers.pixel(x, y)) || !seen.insert((x, y)) {
+                    continue;
+                }
+                let mut component = vec![(x, y)];
+                let mut i = 0;
+                while i < component.len() {
+                    let (px, py) = component[i];
+                    i += 1;
+                    rivers.river_neighbors(px, py, &mut neighbors);
+                    for &coords in &neighbors {
+                        if seen.insert(coords) {
+                            component.push(coords);
+                        }
+                    }
+                }
+                let segment = graph.segment(rivers, component, &node_at);
+                graph.segments.push(segment);
+            }
+        }
+
+        // A special pixel can terminate only one segment.
+        let mut users: TigerHashMap<usize, Vec<usize>> = TigerHashMap::default();
+        for (i, segment) in graph.segments.iter().enumerate() {
+            if let [node] = segment.terminators[..] {
+                users.entry(node).or_default().push(i);
+            }
+        }
+        for (node, segments) in users {
+            let node = &graph.nodes[node];
+            for &i in &segments[1..] {
+                let segment = &mut graph.segments[i];
+                let near = segment
+                    .ends()
+                    .into_iter()
+                    .find(|&(x, y)| x.abs_diff(node.x) + y.abs_diff(node.y) == 1);
+                segment.problems.push(RiverProblem {
+                    msg: format!(
+                        "segment ends at the same {} pixel ({}, {}) as another segment",
+                        node.kind.name(),
+                        node.x,
+                        node.y
+                    ),
+                    fix: fix_shared_terminator(
+                        segment.pixels.len(),
+                        near.unwrap_or(segment.pixels[0]),
+                        (node.x, node.y),
+                    ),
+                });
+            }
+        }
+        Some(graph)
+    }
+
+    /// Build a segment from the connected river pixels in `component`.
+    fn segment(
+        &self,
+        rivers: &Rivers,
+        component: Vec<(u32, u32)>,
+        node_at: &TigerHashMap<(u32, u32), usize>,
+    ) -> RiverSegment {
+        let mut neighbors = Vec::new();
+        let mut ends = Vec::new();
+        let mut branch = None;
+        for &(x, y) in &component {
+            rivers.river_neighbors(x, y, &mut neighbors);
+            match neighbors.len() {
+                0 | 1 => ends.push((x, y)),
+                2 => (),
+                _ => {
+                    branch.get_or_insert((x, y));
+                }
+            }
+        }
+        let widths = component.iter().map(|&(x, y)| rivers.pixel(x, y));
+        let min_width = widths.clone().min().unwrap_or(RiverPixels::FIRST_NORMAL);
+        let max_width = widths.max().unwrap_or(RiverPixels::FIRST_NORMAL);
+
+        let mut problems = Vec::new();
+        if let Some((x, y)) = branch {
+            problems.push(RiverProblem {
+                msg: format!("river pixel ({x}, {y}) has more than two river neighbors"),
+                fix: fix_branch((x, y)),
+            });
+            return RiverSegment {
+                pixels: component,
+                ordered: false,
+                min_width,
+                max_width,
+                terminators: Vec::new(),
+                problems,
+            };
+        }
+
+        let start = ends.first().copied().unwrap_or(component[0]);
+        let pixels = walk(rivers, start);
+        let mut segment = RiverSegment {
+            pixels,
+            ordered: true,
+            min_width,
+            max_width,
+            terminators: Vec::new(),
+            problems,
+        };
+        if ends.is_empty() {
+            rivers.river_neighbors(start.0, start.1, &mut neighbors);
+            segment.problems.push(RiverProblem {
+                msg: "river forms a loop".to_owned(),
+                fix: fix_loop(start, neighbors[0]),
+            });
+            return segment;
+        }
+
+        for end in segment.ends() {
+            for coords in rivers.special_neighbors(end) {
+                let node = node_at[&coords];
+                if !segment.terminators.contains(&node) {
+                    segment.terminators.push(node);
+                }
+            }
+        }
+        let (first, last) = (segment.pixels[0], segment.pixels[segment.pixels.len() - 1]);
+        if segment.terminators.is_empty() {
+            segment.problems.push(RiverProblem {
+                msg: "river segment has no source, tributary, or split pixel".to_owned(),
+                fix: fix_orphan(rivers, first, last),
+            });
+        } else if segment.terminators.len() > 1 {
+            let specials: Vec<_> = segment
+                .terminators
+                .iter()
+                .map(|&i| {
+                    (
+                        rivers.pixel(self.nodes[i].x, self.nodes[i].y),
+                        (self.nodes[i].x, self.nodes[i].y),
+                    )
+                })
+                .collect();
+            segment.problems.push(RiverProblem {
+                msg: "river segment has two terminators".to_owned(),
+                fix: fix_two_terminators(&specials),
+            });
+        }
+        segment
+    }
+
+    /// Write the graph as a `GeoJSON` `FeatureCollection`. The coordinates are pixel coordinates,
+    /// with y counted from the top of the image.
+    pub fn write_geojson<O: Write>(&self, output: &mut O) -> Result<()> {
+        let mut features = Vec::new();
+        for node in &self.nodes {
+            features.push(json!({
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": [node.x, node.y] },
+                "properties": {
+                    "kind": node.kind.name(),
+                    "problems": problems_json(&node.problems),
+                },
+            }));
+        }
+        for segment in &self.segments {
+            let coordinates: Vec<[u32; 2]> = segment.pixels.iter().map(|&(x, y)| [x, y]).collect();
+            let geometry = if !segment.ordered {
+                json!({ "type": "MultiPoint", "coordinates": coordinates })
+            } else if let [point] = &coordinates[..] {
+                json!({ "type": "Point", "coordinates": point })
+            } else {
+                json!({ "type": "LineString", "coordinates": coordinates })
+            };
+            let terminators: Vec<Value> = segment
+                .terminators
+                .iter()
+                .map(|&i| json!([self.nodes[i].x, self.nodes[i].y]))
+                .collect();
+            features.push(json!({
+                "type": "Feature",
+                "geometry": geometry,
+                "properties": {
+                    "kind": "segment",
+                    "length": segment.pixels.len(),
+                    "min_width": segment.min_width,
+                    "max_width": segment.max_width,
+                    "terminators": terminators,
+                    "problems": problems_json(&segment.problems),
+                },
+            }));
+        }
+        let collection = json!({ "type": "FeatureCollection", "features": features });
+        serde_json::to_writer_pretty(&mut *output, &collection)?;
+        writeln!(output)?;
+        Ok(())
+    }
+
+    /// Write the graph as an SVG image, the same size as the river image. Segments with problems
+    /// are drawn in red, and hovering over them shows the suggested fixes.
+    pub fn write_svg<O: Write>(&self, output: &mut O) -> Result<()> {
+        let (width, height) = (self.width, self.height);
+        writeln!(
+            output,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {height}" width="{width}" height="{height}">"#
+        )?;
+        writeln!(output, r#"<rect width="{width}" height="{height}" fill="white"/>"#)?;
+        for segment in &self.segments {
+            let color = if segment.problems.is_empty() { "#3060c0" } else { "#d02020" };
+            let title = svg_title(&segment.problems);
+            if segment.ordered && segment.pixels.len() > 1 {
+                let mut points = String::new();
+                for (x, y) in &segment.pixels {
+                    _ = write!(points, "{x}.5,{y}.5 ");
+                }
+                writeln!(
+                    output,
+                    r#"<polyline points="{}" fill="none" stroke="{color}" stroke-width="1">{title}</polyline>"#,
+                    points.trim_end()
+                )?;
+            } else {
+                writeln!(output, r#"<g fill="{color}">{title}"#)?;
+                for (x, y) in &segment.pixels {
+                    writeln!(output, r#"<rect x="{x}" y="{y}" width="1" height="1"/>"#)?;
+                }
+                writeln!(output, "</g>")?;
+            }
+        }
+        for node in &self.nodes {
+            let color = match node.kind {
+                RiverNodeKind::Source => "#20a020",
+                RiverNodeKind::Tributary => "#d02020",
+                RiverNodeKind::Split => "#e0c000",
+            };
+            let stroke = if node.problems.is_empty() { "none" } else { "black" };
+            writeln!(
+                output,
+                r#"<circle cx="{}.5" cy="{}.5" r="1.5" fill="{color}" stroke="{stroke}" stroke-width="0.5">{}</circle>"#,
+                node.x,
+                node.y,
+                svg_title(&node.problems)
+            )?;
+        }
+        writeln!(output, "</svg>")?;
+        Ok(())
+    }
+}
+
+impl RiverSegment {
+    /// The end pixels of the segment. A single-pixel segment has one.
+    fn ends(&self) -> Vec<(u32, u32)> {
+        let first = self.pixels[0];
+        let last = self.pixels[self.pixels.len() - 1];
+        if first == last {
+            vec![first]
+        } else {
+            vec![first, last]
+        }
+    }
+}
+
+/// Return the pixels of a non-branching segment in order, starting at `start`.
+fn walk(rivers: &Rivers, start: (u32, u32)) -> Vec<(u32, u32)> {
+    let mut path = vec![start];
+    let mut visited = TigerHashSet::default();
+    visited.insert(start);
+    let mut neighbors = Vec::new();
+    let mut current = start;
+    loop {
+        rivers.river_neighbors(current.0, current.1, &mut neighbors);
+        let Some(&next) = neighbors.iter().find(|coords| !visited.contains(*coords)) else {
+            return path;
+        };
+        visited.insert(next);
+        path.push(next);
+        current = next;
+    }
+}
+
+fn problems_json(problems: &[RiverProblem]) -> Vec<Value> {
+    problems.iter().map(|p| json!({ "message": p.msg, "fix": p.fix })).collect()
+}
+
+fn svg_title(problems: &[RiverProblem]) -> String {
+    if problems.is_empty() {
+        return String::new();
+    }
+    let text =
+        problems.iter().map(|p| format!("{}: {}", p.msg, p.fix)).collect::<Vec<_>>().join("\n");
+    let text = text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    format!("<title>{text}</title>")
+}
+
+/// Find a pixel next to `coords` where a source pixel could be added without touching any other
+/// river.
+fn free_neighbor(rivers: &Rivers, coords: (u32, u32)) -> Option<(u32, u32)> {
+    let (x, y) = coords;
+    let (_, width, height) = rivers.image()?;
+    let candidates = [
+        (x > 0).then(|| (x - 1, y)),
+        (y > 0).then(|| (x, y - 1)),
+        (x + 1 < width).then(|| (x + 1, y)),
+        (y + 1 < height).then(|| (x, y + 1)),
+    ];
+    let mut neighbors = Vec::new();
+    candidates.into_iter().flatten().find(|&(nx, ny)| {
+        rivers.pixel(nx, ny) >= RiverPixels::FIRST_IGNORE && {
+            rivers.river_neighbors(nx, ny, &mut neighbors);
+            neighbors == [coords] && rivers.special_neighbors((nx, ny)).is_empty()
+        }
+    })
+}
+
+/// Suggest a fix for a source, tributary, or split pixel with the wrong number of river neighbors.
+pub(crate) fn fix_special(pixel: u8, coords: (u32, u32), neighbors: &[(u32, u32)]) -> String {
+    let (x, y) = coords;
+    if neighbors.is_empty() {
+        format!("remove the pixel at ({x}, {y}), or connect it to a river")
+    } else if pixel == RiverPixels::SOURCE {
+        format!("change the pixel at ({x}, {y}) to a tributary (index 1) if rivers join here, or to a normal river pixel and add a source at the end of the river")
+    } else {
+        format!("change the pixel at ({x}, {y}) to a source (index 0)")
+    }
+}
+
+/// Suggest a fix for a river pixel that has more than two river neighbors.
+pub(crate) fn fix_branch(coords: (u32, u32)) -> String {
+    let (x, y) = coords;
+    format!("change the pixel at ({x}, {y}) to a tributary (index 1) if a river joins here, or to a split (index 2) if a river splits off here")
+}
+
+/// Suggest a fix for a river segment that loops back on itself.
+pub(crate) fn fix_loop(coords: (u32, u32), neighbor: (u32, u32)) -> String {
+    let ((x, y), (nx, ny)) = (coords, neighbor);
+    format!(
+        "remove the pixel at ({x}, {y}) and change the pixel at ({nx}, {ny}) to a source (index 0)"
+    )
+}
+
+/// Suggest a fix for a river segment without any special pixels at its ends.
+pub(crate) fn fix_orphan(rivers: &Rivers, start: (u32, u32), end: (u32, u32)) -> String {
+    if start != end {
+        let (x, y) = start;
+        format!("change the pixel at ({x}, {y}) to a source (index 0)")
+    } else if let Some((x, y)) = free_neighbor(rivers, start) {
+        format!("add a source pixel (index 0) at ({x}, {y})")
+    } else {
+        let (x, y) = start;
+        format!("remove the pixel at ({x}, {y})")
+    }
+}
+
+/// Suggest a fix for a river segment with special pixels at both ends. `specials` has the pixel
+/// values and coordinates of the special pixels.
+pub(crate) fn fix_two_terminators(specials: &[(u8, (u32, u32))]) -> String {
+    // Tributaries and splits connect rivers, so prefer removing a source.
+    let (pixel, (x, y)) = specials
+        .iter()
+        .find(|(pixel, _)| *pixel == RiverPixels::SOURCE)
+        .or(specials.last())
+        .copied()
+        .unwrap_or_default();
+    let kind = RiverNodeKind::from_pixel(pixel).map_or("special", RiverNodeKind::name);
+    format!("remove the {kind} pixel at ({x}, {y})")
+}
+
+/// Suggest a fix for a river segment that ends at a special pixel that already terminates another
+/// segment. `near` is the end of the segment next to the special pixel.
+pub(crate) fn fix_shared_terminator(len: usize, near: (u32, u32), special: (u32, u32)) -> String {
+    let ((x, y), (sx, sy)) = (near, special);
+    if len > 1 {
+        format!("change the pixel at ({x}, {y}) to a source (index 0), so that this segment no longer ends at ({sx}, {sy})")
+    } else {
+        format!("remove the pixel at ({x}, {y})")
+    }
+}
@@ -16,6 +16,9 @@ use crate::fileset::{FileEntry, FileHandler};
 use crate::helpers::{TigerHashMap, TigerHashSet};
 use crate::parse::ParserMemory;
 use crate::report::{err, warn, will_maybe_log, ErrorKey};
+use crate::river_graph::{
+    fix_branch, fix_loop, fix_orphan, fix_shared_terminator, fix_special, fix_two_terminators,
+};
 use crate::Game;
 
 #[inline]
@@ -30,26 +33,27 @@ fn river_image_path() -> &'static str {
 /// The `rivers.png/bmp` has an indexed palette where the colors don't matter, only the index values
 /// used in the pixels matter. Pixels that are not among the values defined here are ignored when
 /// the game processes the `rivers.png/bmp`.
-struct RiverPixels {}
+pub(crate) struct RiverPixels {}
 impl RiverPixels {
     /// Normal rivers of various widths (usually blue through greenish).
     /// They are still all one pixel wide in the `rivers.png/bmp`; this just controls how they are painted on the map.
     /// River pixels must be adjacent to each other horizontally or vertically; together they form river segments.
     /// River widths go up to 15 even though the vanilla maps only use up to 11 (confirmed for CK3 and Hoi4).
-    const NORMAL: RangeInclusive<u8> = (RiverPixels::FIRST_NORMAL..=RiverPixels::LAST_NORMAL);
-    const FIRST_NORMAL: u8 = 3;
-    const LAST_NORMAL: u8 = 15;
+    pub(crate) const NORMAL: RangeInclusive<u8> =
+        (RiverPixels::FIRST_NORMAL..=RiverPixels::LAST_NORMAL);
+    pub(crate) const FIRST_NORMAL: u8 = 3;
+    pub(crate) const LAST_NORMAL: u8 = 15;
     /// "specials" are the starting and ending pixels of river segments
-    const SPECIAL: RangeToInclusive<u8> = (..=RiverPixels::LAST_SPECIAL);
-    const LAST_SPECIAL: u8 = 2;
+    pub(crate) const SPECIAL: RangeToInclusive<u8> = (..=RiverPixels::LAST_SPECIAL);
+    pub(crate) const LAST_SPECIAL: u8 = 2;
     /// A pixel at the start of a river segment (usually green)
-    const SOURCE: u8 = 0;
+    pub(crate) const SOURCE: u8 = 0;
     /// A pixel that joins one river segment into another (usually red)
-    const TRIBUTARY: u8 = 1;
+    pub(crate) const TRIBUTARY: u8 = 1;
     /// A pixel that is used where a river splits off from another (usually yellow)
-    const SPLIT: u8 = 2;
+    pub(crate) const SPLIT: u8 = 2;
     /// Noncoding pixels
-    const FIRST_IGNORE: u8 = 16;
+    pub(crate) const FIRST_IGNORE: u8 = 16;
 }
 
 #[derive(Clone, Debug, Default)]
@@ -159,7 +163,7 @@ impl Rivers {
         }
     }
 
-    fn river_neighbors(&self, x: u32, y: u32, output: &mut Vec<(u32, u32)>) {
+    pub(crate) fn river_neighbors(&self, x: u32, y: u32, output: &mut Vec<(u32, u32)>) {
         output.clear();
         if x > 0 && RiverPixels::NORMAL.contains(&self.pixel(x - 1, y)) {
             output.push((x - 1, y));
@@ -175,7 +179,7 @@ impl Rivers {
         }
     }
 
-    fn special_neighbors(&self, c: (u32, u32)) -> Vec<(u32, u32)> {
+    pub(crate) fn special_neighbors(&self, c: (u32, u32)) -> Vec<(u32, u32)> {
         let (x, y) = c;
         let mut vec = Vec::new();
         if x > 0 && RiverPixels::SPECIAL.contains(&self.pixel(x - 1, y)) {
@@ -193,8 +197,33 @@ impl Rivers {
         vec
     }
 
+    /// Return the loaded image file and its width and height.
+    pub(crate) fn image(&self) -> Option<(&FileEntry, u32, u32)> {
+        self.entry.as_ref().map(|entry| (entry, self.width, self.height))
+    }
+
+    /// Return the coordinates of all the pixels that are part of a river, including the special
+    /// pixels.
+    #[cfg(feature = "jomini")]
+    #[allow(clippy::integer_division)]
+    pub(crate) fn river_pixels(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let width = self.width;
+        self.pixels.iter().enumerate().filter(|(_, &pixel)| pixel < RiverPixels::FIRST_IGNORE).map(
+            move |(idx, _)| {
+                #[allow(clippy::cast_possible_truncation)]
+                let idx = idx as u32;
+                (idx % width, idx / width)
+            },
+        )
+    }
+
+    /// Pair the coordinates of special pixels with their pixel values.
+    fn with_pixels(&self, specials: &[(u32, u32)]) -> Vec<(u8, (u32, u32))> {
+        specials.iter().map(|&(x, y)| (self.pixel(x, y), (x, y))).collect()
+    }
+
     #[inline]
-    fn pixel(&self, x: u32, y: u32) -> u8 {
+    pub(crate) fn pixel(&self, x: u32, y: u32) -> u8 {
         let idx = (x + self.width * y) as usize;
         self.pixels[idx]
     }
@@ -221,16 +250,19 @@ impl Rivers {
                         "({}, {}) river pixel connects two special pixels",
                         start.0, start.1
                     );
-                    warn(ErrorKey::Rivers).msg(msg).loc(entry).push();
+                    let info = fix_two_terminators(&self.with_pixels(&special_neighbors));
+                    warn(ErrorKey::Rivers).msg(msg).info(info).loc(entry).push();
                 } else if special_neighbors.is_empty() {
                     let msg = format!("({}, {}) orphan river pixel", start.0, start.1);
-                    warn(ErrorKey::Rivers).msg(msg).loc(entry).push();
+                    let info = fix_orphan(self, start, end);
+                    warn(ErrorKey::Rivers).msg(msg).info(info).loc(entry).push();
                 } else {
                     let s = special_neighbors[0];
                     if specials[&s] {
                         let msg =
                             format!("({}, {}) pixel terminates multiple river segments", s.0, s.1);
-                        warn(ErrorKey::Rivers).msg(msg).loc(entry).push();
+                        let info = fix_shared_terminator(1, start, s);
+                        warn(ErrorKey::Rivers).msg(msg).info(info).loc(entry).push();
                     } else {
                         specials.insert(s, true);
                     }
@@ -243,19 +275,24 @@ impl Rivers {
                         "({}, {}) - ({}, {}) orphan river segment",
                         start.0, start.1, end.0, end.1
                     );
-                    warn(ErrorKey::Rivers).msg(msg).loc(entry).push();
+                    let info = fix_orphan(self, start, end);
+                    warn(ErrorKey::Rivers).msg(msg).info(info).loc(entry).push();
                 } else if special_neighbors.len() > 1 {
                     let msg = format!(
                         "({}, {}) - ({}, {}) river segment has two terminators",
                         start.0, start.1, end.0, end.1
                     );
-                    warn(ErrorKey::Rivers).msg(msg).loc(entry).push();
+                    let info = fix_two_terminators(&self.with_pixels(&special_neighbors));
+                    warn(ErrorKey::Rivers).msg(msg).info(info).loc(entry).push();
                 } else {
                     let s = special_neighbors[0];
                     if specials[&s] {
                         let msg =
                             format!("({}, {}) pixel terminates multiple river segments", s.0, s.1);
-                        warn(ErrorKey::Rivers).msg(msg).loc(entry).push();
+                        let near =
+                            if self.special_neighbors(start).contains(&s) { start } else { end };
+                        let info = fix_shared_terminator(2, near, s);
+                        warn(ErrorKey::Rivers).msg(msg).info(info).loc(entry).push();
                     } else {
                         specials.insert(s, true);
                     }
@@ -304,7 +341,8 @@ impl Rivers {
                         } else {
                             let msg =
                                 format!("({x}, {y}) river source (green) not at source of a river");
-                            warn(ErrorKey::Rivers).msg(msg).loc(entry).push();
+                            let info = fix_special(RiverPixels::SOURCE, (x, y), &river_neighbors);
+                            warn(ErrorKey::Rivers).msg(msg).info(info).loc(entry).push();
                             bad_problem = true;
                         }
                     }
@@ -316,7 +354,9 @@ impl Rivers {
                             let msg = format!(
                                 "({x}, {y}) river tributary (red) not joining another river",
                             );
-                            warn(ErrorKey::Rivers).msg(msg).loc(entry).push();
+                            let info =
+                                fix_special(RiverPixels::TRIBUTARY, (x, y), &river_neighbors);
+                            warn(ErrorKey::Rivers).msg(msg).info(info).loc(entry).push();
                             bad_problem = true;
                         }
                     }
@@ -328,7 +368,8 @@ impl Rivers {
                             let msg = format!(
                                 "({x}, {y}) river split (yellow) not splitting off from a river",
                             );
-                            warn(ErrorKey::Rivers).msg(msg).loc(entry).push();
+                            let info = fix_special(RiverPixels::SPLIT, (x, y), &river_neighbors);
+                            warn(ErrorKey::Rivers).msg(msg).info(info).loc(entry).push();
                             bad_problem = true;
                         }
                     }
@@ -346,7 +387,12 @@ impl Rivers {
                                         // though.
                                         if third_end == (x, y) {
                                             let msg = format!("({x}, {y}) river forms a loop");
-                                            warn(ErrorKey::Rivers).msg(msg).loc(entry).push();
+                                            let info = fix_loop((x, y), coords);
+                                            warn(ErrorKey::Rivers)
+                                                .msg(msg)
+                                                .info(info)
+                                                .loc(entry)
+                                                .push();
                                             bad_problem = true;
                                         } else {
                                             river_segments.insert(other_end, third_end);
@@ -371,7 +417,8 @@ impl Rivers {
                                 "({x}, {y}) river pixel has {} neighbors",
                                 river_neighbors.len()
                             );
-                            warn(ErrorKey::Rivers).msg(msg).loc(entry).push();
+                            let info = fix_branch((x, y));
+                            warn(ErrorKey::Rivers).msg(msg).info(info).loc(entry).push();
                             bad_problem = true;
                         }
                     }
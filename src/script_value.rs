@@ -360,6 +360,7 @@ pub fn validate_non_dynamic_script_value(bv: &BV, data: &Everything) {
             return;
         }
         if data.script_values.exists(token.as_str()) {
+            data.mark_used_by(Item::ScriptValue, token.as_str(), token);
             data.script_values.validate_non_dynamic_call(token, data);
             return;
         }
@@ -0,0 +1,98 @@
+//! Validation sessions, for programs that embed the validator and want to validate several mods,
+//! possibly at the same time, and get the reports back instead of having them printed.
+//!
+//! Reports are normally collected in one global store. A [`Session`] has its own store, with its
+//! own filter, suppressions, baseline, and file cache. Everything that runs inside
+//! [`Session::run`] uses that store: the reports logged during validation as well as functions
+//! such as [`set_show_vanilla`](crate::set_show_vanilla) or [`emit_reports`](crate::emit_reports).
+//!
+//! Sessions share the validator's other process-wide state, such as the game being validated
+//! (see [`Game`](crate::Game)), so all sessions in a process must validate the same game.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::everything::Everything;
+use crate::report::{
+    new_session_errors, release_session_errors, set_session_errors, take_sorted_reports, Errors,
+    LogReport,
+};
+
+/// A validation session with its own report store.
+///
+/// The work of a session is done on its own pool of threads, so that the reports logged from
+/// those threads end up in the session's store.
+#[allow(missing_debug_implementations)]
+pub struct Session {
+    errors: &'static Mutex<Errors<'static>>,
+    pool: ThreadPool,
+}
+
+impl Session {
+    /// Create a session with an empty report store and the default filter settings.
+    pub fn new() -> Result<Self> {
+        let errors = new_session_errors();
+        let pool = ThreadPoolBuilder::new()
+            .thread_name(|i| format!("tiger-session-{i}"))
+            .start_handler(move |_| set_session_errors(errors))
+            .build();
+        match pool {
+            Ok(pool) => Ok(Self { errors, pool }),
+            Err(e) => {
+                release_session_errors(errors);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Run `f` as part of this session. Reports logged by `f`, and report settings changed by
+    /// it, apply only to this session.
+    pub fn run<R: Send, F: FnOnce() -> R + Send>(&self, f: F) -> R {
+        self.pool.install(f)
+    }
+
+    /// Load and validate a mod, and return the reports that pass the session's filter.
+    ///
+    /// The arguments are the same as for [`Everything::new`]. The filter from the mod's config
+    /// file is used, as the command-line tool does.
+    pub fn validate(
+        &self,
+        config_filepath: Option<&Path>,
+        vanilla_dir: Option<&Path>,
+        workshop_dir: Option<&Path>,
+        paradox_dir: Option<&Path>,
+        mod_root: &Path,
+        replace_paths: Vec<PathBuf>,
+    ) -> Result<Vec<LogReport>> {
+        self.run(|| {
+            let mut everything = Everything::new(
+                config_filepath,
+                vanilla_dir,
+                workshop_dir,
+                paradox_dir,
+                mod_root,
+                replace_paths,
+            )?;
+            everything.load_config_filtering_rules();
+            everything.load_all();
+            everything.validate_all();
+            everything.check_rivers();
+            Ok(take_sorted_reports())
+        })
+    }
+
+    /// Extract the reports stored in this session so far, sorted in the order in which
+    /// [`emit_reports`](crate::emit_reports) would print them.
+    pub fn take_reports(&self) -> Vec<LogReport> {
+        self.run(take_sorted_reports)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        release_session_errors(self.errors);
+    }
+}
@@ -0,0 +1,137 @@
+//! Documentation of what scripted effects, scripted triggers, and script values expect of their
+//! callers, as inferred while validating them.
+
+use std::io::Write;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use crate::context::Expectations;
+use crate::fileset::FileKind;
+use crate::helpers::camel_case_to_separated_words;
+use crate::item::Item;
+use crate::scopes::Scopes;
+use crate::token::Token;
+
+/// What one scripted item expects of its callers.
+#[derive(Clone, Debug)]
+pub(crate) struct ScriptSignature {
+    pub(crate) itype: Item,
+    pub(crate) key: Token,
+    /// `None` if nothing could be inferred, such as for items with macro parameters.
+    pub(crate) expectations: Option<Expectations>,
+    pub(crate) macro_parms: Vec<&'static str>,
+    /// Whether the scopes come from a `scope_override` in the config instead of inference.
+    pub(crate) scope_override: bool,
+}
+
+/// The signatures of all scripted effects, scripted triggers, and script values.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptSignatures {
+    signatures: Vec<ScriptSignature>,
+}
+
+impl ScriptSignatures {
+    pub(crate) fn new(mut signatures: Vec<ScriptSignature>) -> Self {
+        signatures.sort_by(|a, b| a.itype.cmp(&b.itype).then(a.key.as_str().cmp(b.key.as_str())));
+        Self { signatures }
+    }
+
+    /// Keep only the items that are defined in the mod itself.
+    pub fn retain_mod_related(&mut self) {
+        self.signatures.retain(|sig| sig.key.loc.kind == FileKind::Mod);
+    }
+
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    /// Write the signatures as a JSON array. Scope types are written the way `scope_override`
+    /// in the config expects them, such as `character|province`.
+    pub fn write_json<O: Write>(&self, output: &mut O) -> Result<()> {
+        let items: Vec<Value> = self
+            .signatures
+            .iter()
+            .map(|sig| {
+                let exp = sig.expectations.as_ref();
+                let names = |names: &[(&str, Scopes)]| {
+                    names.iter().map(|(n, s)| ((*n).to_owned(), json!(config_scopes(*s)))).collect()
+                };
+                json!({
+                    "type": <&str>::from(sig.itype),
+                    "key": sig.key.as_str(),
+                    "file": sig.key.loc.pathname().to_string_lossy(),
+                    "line": sig.key.loc.line,
+                    "root": exp.map(|exp| config_scopes(exp.root)),
+                    "this": exp.map(|exp| config_scopes(exp.this)),
+                    "saved_scopes": exp.map(|exp| Value::Object(names(&exp.named_scopes))),
+                    "saved_lists": exp.map(|exp| Value::Object(names(&exp.lists))),
+                    "macro_parameters": sig.macro_parms,
+                    "scope_override": sig.scope_override,
+                })
+            })
+            .collect();
+        serde_json::to_writer_pretty(&mut *output, &items)?;
+        writeln!(output)?;
+        Ok(())
+    }
+
+    /// Write the signatures as a markdown document with a section per item type.
+    pub fn write_markdown<O: Write>(&self, output: &mut O) -> Result<()> {
+        let mut current = None;
+        for sig in &self.signatures {
+            if current != Some(sig.itype) {
+                if current.is_some() {
+                    writeln!(output)?;
+                }
+                current = Some(sig.itype);
+                writeln!(output, "# {}s", capitalize(&sig.itype.to_string()))?;
+            }
+            writeln!(output)?;
+            writeln!(output, "## `{}`", sig.key)?;
+            writeln!(output)?;
+            let path = sig.key.loc.pathname().display();
+            writeln!(output, "Defined in `{path}` line {}.", sig.key.loc.line)?;
+            writeln!(output)?;
+            if let Some(exp) = &sig.expectations {
+                writeln!(output, "* Root: {}", exp.root)?;
+                writeln!(output, "* This: {}", exp.this)?;
+                for (name, scopes) in &exp.named_scopes {
+                    writeln!(output, "* Saved scope `scope:{name}`: {scopes}")?;
+                }
+                for (name, scopes) in &exp.lists {
+                    writeln!(output, "* Saved list `{name}`: {scopes}")?;
+                }
+            } else {
+                writeln!(output, "* Scopes: not inferred, because it is only checked per call")?;
+            }
+            for parm in &sig.macro_parms {
+                writeln!(output, "* Macro parameter: `${parm}$`")?;
+            }
+            if sig.scope_override {
+                writeln!(output, "* The scopes are set by `scope_override` in the config.")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn config_scopes(scopes: Scopes) -> String {
+    if scopes == Scopes::all() {
+        return "all".to_owned();
+    }
+    let names: Vec<_> = scopes
+        .iter_names()
+        .map(|(name, _)| camel_case_to_separated_words(name).replace(' ', "_"))
+        .collect();
+    names.join("|")
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map_or_else(String::new, |c| c.to_uppercase().chain(chars).collect())
+}
@@ -376,6 +376,7 @@ pub fn validate_trigger_key_bv(
 
     // Scripted trigger?
     if let Some(trigger) = data.get_trigger(key) {
+        data.mark_used_by(Item::ScriptedTrigger, key.as_str(), key);
         match bv {
             BV::Value(token) => {
                 if !(token.is("yes") || token.is("no") || token.is("YES") || token.is("NO")) {
@@ -495,6 +496,8 @@ pub fn validate_trigger_key_bv(
                 } else if data.script_value_exists(part.as_str()) {
                     // TODO: check side_effects
                     #[cfg(feature = "jomini")]
+                    data.mark_used_by(Item::ScriptValue, part.as_str(), part);
+                    #[cfg(feature = "jomini")]
                     data.script_values.validate_call(part, data, sc);
                     sc.replace(Scopes::Value, part.clone());
                 } else if let Some((inscopes, outscope)) = scope_to_scope(part, sc.scopes()) {
@@ -1153,7 +1156,7 @@ fn match_trigger_bv(
         Trigger::FlagOrBlock(fields) => {
             if name.is("has_unit_leader_flag") {
                 let msg = "deprecated in favor of has_character_flag";
-                warn(ErrorKey::Deprecated).msg(msg).loc(name).push();
+                warn(ErrorKey::Deprecated).msg(msg).loc(name).fix("has_character_flag").push();
             }
 
             match bv {
@@ -1285,6 +1288,8 @@ pub fn validate_target_ok_this(
                 } else if data.script_value_exists(part.as_str()) {
                     // TODO: check side_effects
                     #[cfg(feature = "jomini")]
+                    data.mark_used_by(Item::ScriptValue, part.as_str(), part);
+                    #[cfg(feature = "jomini")]
                     data.script_values.validate_call(part, data, sc);
                     sc.replace(Scopes::Value, part.clone());
                 } else if let Some((inscopes, outscope)) = scope_to_scope(part, sc.scopes()) {
@@ -1330,15 +1335,22 @@ pub fn validate_target_ok_this(
                 } else {
                     // See if the user forgot a prefix like `faith:` or `culture:`
                     let mut opt_info = None;
+                    let mut opt_fix = None;
                     if part_flags.contains(PartFlags::First | PartFlags::Last) {
                         if let Some(prefix) = needs_prefix(part.as_str(), data, outscopes) {
                             opt_info = Some(format!("did you mean `{prefix}:{part}` ?"));
+                            opt_fix = Some(format!("{prefix}:{part}"));
                         }
                     }
 
                     // TODO: warn if trying to use iterator here
                     let msg = format!("unknown token `{part}`");
-                    err(ErrorKey::UnknownField).msg(msg).opt_info(opt_info).loc(part).push();
+                    err(ErrorKey::UnknownField)
+                        .msg(msg)
+                        .opt_info(opt_info)
+                        .loc(part)
+                        .opt_fix(opt_fix)
+                        .push();
                     sc.close();
                     return Scopes::all();
                 }
@@ -806,6 +806,7 @@ pub fn validate_scripted_modifier_calls(
 ) {
     vd.unknown_fields(|key, bv| {
         if let Some(modifier) = data.scripted_modifiers.get(key.as_str()) {
+            data.mark_used_by(Item::ScriptedModifier, key.as_str(), key);
             validate_scripted_modifier_call(key, bv, modifier, data, sc);
         } else {
             let msg = format!("unknown field `{key}`");
@@ -216,6 +216,17 @@ impl<'a> Validator<'a> {
         });
     }
 
+    /// Like [`Validator::replaced_field`], but for fields that were only renamed to `new_name`
+    /// and still take the same value. The report suggests the new name as a fix.
+    #[cfg(any(feature = "ck3", feature = "vic3"))]
+    pub fn renamed_field(&mut self, name: &str, new_name: &str) {
+        let sev = Severity::Error.at_most(self.max_severity);
+        self.multi_field_check(name, |key, _| {
+            let msg = format!("`{name}` has been renamed to `{new_name}`");
+            report(ErrorKey::Validation, sev).msg(msg).loc(key).fix(new_name).push();
+        });
+    }
+
     fn check_key(&mut self, name: &str) -> bool {
         for Field(key, _, _) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
@@ -474,6 +485,7 @@ impl<'a> Validator<'a> {
         self.field_check(name, |_, bv| {
             if let Some(token) = bv.expect_value() {
                 self.data.verify_exists_max_sev(Item::OnAction, token, sev);
+                self.data.call_graph.record(Item::OnAction, token, name, None);
                 if let Some(mut action_sc) = sc.root_for_action(token) {
                     self.data.on_actions.validate_call(token, data, &mut action_sc);
                 }
@@ -492,6 +504,7 @@ impl<'a> Validator<'a> {
         self.field_check(name, |_, bv| {
             if let Some(token) = bv.expect_value() {
                 self.data.verify_exists_max_sev(Item::Event, token, sev);
+                self.data.call_graph.record(Item::Event, token, name, None);
                 self.data.event_check_scope(token, sc);
                 if let Some(mut event_sc) = sc.root_for_event(token) {
                     self.data.event_validate_call(token, &mut event_sc);
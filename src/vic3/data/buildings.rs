@@ -116,7 +116,7 @@ impl DbKind for BuildingType {
         }
 
         vd.field_item("building_group", Item::BuildingGroup);
-        vd.replaced_field("texture", "icon");
+        vd.renamed_field("texture", "icon");
         vd.field_item("icon", Item::File);
 
         vd.field_bool("buildable");
@@ -56,7 +56,7 @@ impl DbKind for ModifierTypeDefinition {
         });
 
         vd.field_item("prefix", Item::Localization);
-        vd.replaced_field("postfix", "suffix");
+        vd.renamed_field("postfix", "suffix");
         vd.field_item("suffix", Item::Localization);
     }
 }
@@ -8,6 +8,7 @@ use crate::fileset::{FileEntry, FileHandler};
 use crate::helpers::TigerHashSet;
 use crate::item::Item;
 use crate::parse::ParserMemory;
+use crate::province_map::ProvinceMap;
 use crate::report::{err, report, ErrorKey, Severity};
 use crate::token::Token;
 
@@ -90,6 +91,7 @@ impl FileHandler<DynamicImage> for Vic3Provinces {
             for pixel in img.pixels().dedup() {
                 self.colors.insert(*pixel);
             }
+            ProvinceMap::analyze(&img, entry, &[]);
         }
     }
 }
@@ -0,0 +1,175 @@
+//! A cross-reference index of where items are defined and where they are used.
+//!
+//! The uses are recorded while validating, when references are resolved by
+//! [`Everything::verify_exists`](crate::Everything) and when scripted triggers, scripted effects,
+//! script values and scripted modifiers are called. Recording is off unless enabled with
+//! [`Everything::enable_xref`](crate::Everything::enable_xref), because it takes memory.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use crate::fileset::FileKind;
+use crate::helpers::{TigerHashMap, TigerHashSet};
+use crate::item::Item;
+use crate::token::{Loc, Token};
+
+/// The uses of items found during validation, keyed by item type and key.
+#[derive(Debug, Default)]
+pub(crate) struct XrefRecorder {
+    enabled: bool,
+    #[allow(clippy::type_complexity)]
+    uses: Mutex<TigerHashMap<(Item, String), TigerHashMap<Loc, Token>>>,
+}
+
+impl XrefRecorder {
+    pub(crate) fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Record that `token` refers to the item `key` of type `itype`.
+    /// The key can differ from the token, for example for localization keys that are derived from
+    /// an item's name.
+    pub(crate) fn record(&self, itype: Item, key: &str, token: &Token) {
+        if !self.enabled {
+            return;
+        }
+        let mut loc = token.loc;
+        // Uses in macro expansions are recorded once, at their place in the macro's definition.
+        loc.link_idx = None;
+        let mut uses = self.uses.lock().unwrap();
+        let entry = uses.entry((itype, key.to_owned())).or_default();
+        entry.entry(loc).or_insert_with(|| Token::from_static_str(token.as_str(), loc));
+    }
+
+    /// Return the files that use any of the given items.
+    pub(crate) fn files_using(
+        &self,
+        items: &TigerHashSet<(Item, &str)>,
+    ) -> TigerHashSet<&'static Path> {
+        let uses = self.uses.lock().unwrap();
+        let mut files = TigerHashSet::default();
+        for ((itype, key), tokens) in uses.iter() {
+            if items.contains(&(*itype, key.as_str())) {
+                files.extend(tokens.keys().map(|loc| loc.pathname()));
+            }
+        }
+        files
+    }
+
+    /// Forget the uses recorded in the given files, because they are about to be validated again.
+    pub(crate) fn forget_uses_in(&self, files: &TigerHashSet<&Path>) {
+        let mut uses = self.uses.lock().unwrap();
+        uses.retain(|_, tokens| {
+            tokens.retain(|loc, _| !files.contains(loc.pathname()));
+            !tokens.is_empty()
+        });
+    }
+
+    /// Return the recorded uses, sorted by location.
+    pub(crate) fn uses(&self) -> Vec<((Item, String), Vec<Token>)> {
+        let uses = self.uses.lock().unwrap();
+        uses.iter()
+            .map(|(key, tokens)| {
+                let mut tokens: Vec<_> = tokens.values().cloned().collect();
+                tokens.sort_unstable_by_key(|token| token.loc);
+                (key.clone(), tokens)
+            })
+            .collect()
+    }
+}
+
+/// The definitions and uses of one item.
+#[derive(Clone, Debug, Default)]
+pub struct XrefEntry {
+    /// Where the item is defined. There can be several, for example when a mod overrides a
+    /// vanilla item or when localization is defined for several languages.
+    pub definitions: Vec<Token>,
+    /// Where the item is referred to.
+    pub uses: Vec<Token>,
+}
+
+/// An index from items to where they are defined and used.
+#[derive(Clone, Debug, Default)]
+pub struct XrefIndex {
+    entries: BTreeMap<(Item, String), XrefEntry>,
+}
+
+impl XrefIndex {
+    pub(crate) fn add_definition(&mut self, itype: Item, token: &Token) {
+        let entry = self.entries.entry((itype, token.as_str().to_owned())).or_default();
+        entry.definitions.push(token.clone());
+    }
+
+    pub(crate) fn add_uses(&mut self, itype: Item, key: String, uses: Vec<Token>) {
+        self.entries.entry((itype, key)).or_default().uses = uses;
+    }
+
+    /// Look up the definitions and uses of the item `key` of type `itype`.
+    pub fn get(&self, itype: Item, key: &str) -> Option<&XrefEntry> {
+        self.entries.get(&(itype, key.to_owned()))
+    }
+
+    /// Iterate over all items in the index, sorted by item type and key.
+    pub fn iter(&self) -> impl Iterator<Item = (Item, &str, &XrefEntry)> {
+        self.entries.iter().map(|((itype, key), entry)| (*itype, key.as_str(), entry))
+    }
+
+    /// Find the item that is defined or used at the given position in a file.
+    /// The path is relative to the game or mod directory, and lines and columns start at 1.
+    pub fn symbol_at(&self, path: &Path, line: u32, column: u32) -> Option<(Item, &str)> {
+        #[allow(clippy::cast_possible_truncation)]
+        let covers = |token: &Token| {
+            token.loc.pathname() == path
+                && token.loc.line == line
+                && (token.loc.column..token.loc.column + token.as_str().chars().count() as u32)
+                    .contains(&column)
+        };
+        self.iter()
+            .find(|(_, _, entry)| entry.definitions.iter().chain(&entry.uses).any(covers))
+            .map(|(itype, key, _)| (itype, key))
+    }
+
+    /// Keep only the items that are defined or used in the mod itself, as opposed to only in the
+    /// base game or in other loaded mods.
+    pub fn retain_mod_related(&mut self) {
+        self.entries.retain(|_, entry| {
+            entry.definitions.iter().chain(&entry.uses).any(|token| token.loc.kind == FileKind::Mod)
+        });
+    }
+
+    /// Write the index as a JSON array with one object per item.
+    pub fn write_json<O: Write>(&self, output: &mut O) -> Result<()> {
+        let items: Vec<Value> = self
+            .iter()
+            .map(|(itype, key, entry)| {
+                json!({
+                    "type": <&str>::from(itype),
+                    "key": key,
+                    "definitions": entry.definitions.iter().map(token_json).collect::<Vec<_>>(),
+                    "uses": entry.uses.iter().map(token_json).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        serde_json::to_writer_pretty(&mut *output, &items)?;
+        writeln!(output)?;
+        Ok(())
+    }
+}
+
+fn token_json(token: &Token) -> Value {
+    json!({
+        "file": token.loc.pathname().to_string_lossy(),
+        "fullpath": token.loc.fullpath().to_string_lossy(),
+        "line": token.loc.line,
+        "column": token.loc.column,
+    })
+}
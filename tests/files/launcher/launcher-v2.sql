@@ -0,0 +1,37 @@
+-- A small subset of the Paradox launcher's database, with the tables and columns that are read
+-- for playsets. Positions 2 and 10 check that the load order is numeric.
+-- Regenerate the fixture with: sqlite3 launcher-v2.sqlite < launcher-v2.sql
+CREATE TABLE playsets (
+    id CHAR(36) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    isActive BOOLEAN
+);
+CREATE TABLE mods (
+    id CHAR(36) PRIMARY KEY,
+    steamId VARCHAR(255),
+    gameRegistryId TEXT,
+    name VARCHAR(255),
+    displayName VARCHAR(255),
+    dirPath TEXT,
+    status VARCHAR(255)
+);
+CREATE TABLE playsets_mods (
+    playsetId CHAR(36) NOT NULL,
+    modId CHAR(36) NOT NULL,
+    enabled BOOLEAN DEFAULT 1,
+    position INTEGER
+);
+
+INSERT INTO playsets VALUES ('p-main', 'Main', 1);
+INSERT INTO playsets VALUES ('p-other', 'Other', 0);
+
+INSERT INTO mods VALUES ('m-alpha', '1001', 'mod/ugc_1001.mod', 'alpha', 'Alpha', '/mods/alpha', 'ready_to_play');
+INSERT INTO mods VALUES ('m-beta', '1002', 'mod/ugc_1002.mod', 'beta', 'Beta', '/mods/beta', 'ready_to_play');
+INSERT INTO mods VALUES ('m-gamma', NULL, 'mod/gamma.mod', 'gamma', NULL, '/mods/gamma', 'ready_to_play');
+INSERT INTO mods VALUES ('m-missing', '1004', 'mod/ugc_1004.mod', 'missing', 'Missing', NULL, 'not_downloaded');
+
+INSERT INTO playsets_mods VALUES ('p-main', 'm-alpha', 1, 10);
+INSERT INTO playsets_mods VALUES ('p-main', 'm-beta', 1, 2);
+INSERT INTO playsets_mods VALUES ('p-main', 'm-gamma', 0, 11);
+INSERT INTO playsets_mods VALUES ('p-other', 'm-gamma', 1, 0);
+INSERT INTO playsets_mods VALUES ('p-other', 'm-missing', 1, 1);
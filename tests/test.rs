@@ -1,26 +1,58 @@
-use std::path::PathBuf;
-use std::sync::{LazyLock, Mutex};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
+use serde_json::json;
 use tiger_lib::{
-    take_reports, Everything, LogReportMetadata, LogReportPointers, TigerHashMap, TigerHashSet,
+    apply_fixes, baseline_summary, diff_reports, emit_reports, emit_reports_sarif, load_baseline,
+    path_to_uri, report_stats, set_keep_reports, set_macro_trace, take_reports, take_saved_reports,
+    take_sorted_reports, write_baseline, BaselineSummary, Confidence, Everything, FileKind, Item,
+    LogReport, LogReportMetadata, LogReportPointers, Playset, PlaysetMod, RiverNodeKind,
+    RiverSegment, SavedReport, Session, Severity, TigerHashMap, TigerHashSet, Token,
 };
 
-static TEST_MUTEX: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
-
 fn check_mod_helper(
     modname: &str,
 ) -> TigerHashMap<LogReportMetadata, TigerHashSet<LogReportPointers>> {
-    let _guard = TEST_MUTEX.lock().unwrap();
-
-    let vanilla_dir = PathBuf::from("tests/files/ck3");
     let mod_root = PathBuf::from(format!("tests/files/{}", modname));
+    validate_mod(&mod_root, |_| (), |_| take_reports())
+}
 
-    let mut everything =
-        Everything::new(None, Some(&vanilla_dir), None, None, &mod_root, Vec::new()).unwrap();
-    everything.load_all();
-    everything.validate_all();
+/// Load and validate the mod at `mod_root` on top of the test vanilla files, in a session of its
+/// own, and return what `f` makes of the result. `setup` runs before loading, for the settings
+/// that must be made before then.
+fn validate_mod<R: Send>(
+    mod_root: &Path,
+    setup: impl FnOnce(&mut Everything) + Send,
+    f: impl FnOnce(&mut Everything) -> R + Send,
+) -> R {
+    validate_mod_with_config(mod_root, None, setup, f)
+}
 
-    take_reports()
+/// Like [`validate_mod`], but with the config file at `config` instead of the mod's own.
+fn validate_mod_with_config<R: Send>(
+    mod_root: &Path,
+    config: Option<&Path>,
+    setup: impl FnOnce(&mut Everything) + Send,
+    f: impl FnOnce(&mut Everything) -> R + Send,
+) -> R {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let session = Session::new().unwrap();
+    session.run(|| {
+        let mut everything =
+            Everything::new(config, Some(&vanilla_dir), None, None, mod_root, Vec::new()).unwrap();
+        setup(&mut everything);
+        everything.load_all();
+        everything.validate_all();
+        f(&mut everything)
+    })
+}
+
+/// Return an empty directory for a test that writes its mod files itself, because it changes
+/// them while it runs.
+fn temp_mod_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tiger-{name}-{}", std::process::id()));
+    _ = std::fs::remove_dir_all(&dir);
+    dir
 }
 
 fn take_report_contains(
@@ -272,3 +304,892 @@ fn test_mod3() {
     dbg!(&reports);
     assert!(reports.is_empty());
 }
+
+#[cfg(feature = "launcher")]
+#[test]
+fn test_playset() {
+    let db = PathBuf::from("tests/files/launcher/launcher-v2.sqlite");
+    let mod_names = |playset: &Playset| -> Vec<String> {
+        playset.mods.iter().map(|playset_mod| playset_mod.name.clone()).collect()
+    };
+
+    let active = Playset::read(&db, None).unwrap();
+    assert_eq!(active.name, "Main");
+    assert_eq!(mod_names(&active), ["Beta", "Alpha"]);
+    assert_eq!(active.mods[0].path, PathBuf::from("/mods/beta"));
+
+    let main = Playset::read(&db, Some("Main")).unwrap();
+    assert_eq!(mod_names(&main), mod_names(&active));
+
+    let err = Playset::read(&db, Some("Other")).unwrap_err();
+    assert!(err.to_string().contains("`Missing`"), "{err}");
+
+    assert!(Playset::read(&db, Some("Nonexistent")).is_err());
+}
+
+#[test]
+fn test_concurrent_sessions() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let validate = |modname: &str| {
+        let mod_root = PathBuf::from(format!("tests/files/{modname}"));
+        let session = Session::new().unwrap();
+        session.validate(None, Some(&vanilla_dir), None, None, &mod_root, Vec::new()).unwrap()
+    };
+    let (reports1, reports2) = std::thread::scope(|s| {
+        let handle1 = s.spawn(|| validate("mod1"));
+        let handle2 = s.spawn(|| validate("mod2"));
+        (handle1.join().unwrap(), handle2.join().unwrap())
+    });
+
+    let has = |reports: &[LogReport], msg: &str| reports.iter().any(|(r, _)| r.msg == msg);
+    assert!(has(&reports1, "missing english localization key my_decision"));
+    assert!(!has(&reports1, "missing english localization key test_interaction"));
+    assert!(has(&reports2, "missing english localization key test_interaction"));
+    assert!(!has(&reports2, "missing english localization key my_decision"));
+}
+
+/// Write `contents` to `path` under `root`, creating the directories it needs.
+fn write_mod_file(root: &Path, path: &str, contents: &str) {
+    let fullpath = root.join(path);
+    std::fs::create_dir_all(fullpath.parent().unwrap()).unwrap();
+    std::fs::write(fullpath, contents).unwrap();
+}
+
+/// Print the stored reports as JSON, and return their messages with the path of their main
+/// location.
+fn emitted_reports() -> Vec<(String, String)> {
+    let mut output = Vec::new();
+    emit_reports(&mut output, true, false, false);
+    let reports: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    reports
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|report| {
+            let msg = report["message"].as_str().unwrap().to_owned();
+            let path = report["locations"][0]["path"].as_str().unwrap().to_owned();
+            (msg, path)
+        })
+        .collect()
+}
+
+fn watch_effect(comment: &str) -> String {
+    format!(
+        "watch_effect = {{\n\tif = {{\n\t\t{comment}\n\t\tlimit = {{ watch_trigger = yes }}\n\t\tadd_gold = 1\n\t}}\n}}\n"
+    )
+}
+
+#[test]
+fn test_reload_files() {
+    const TRIGGERS: &str = "common/scripted_triggers/watch.txt";
+    const EFFECTS: &str = "common/scripted_effects/watch.txt";
+    let mod_root = temp_mod_dir("reload");
+    write_mod_file(&mod_root, TRIGGERS, "watch_trigger = { always = yes }\n");
+    write_mod_file(&mod_root, EFFECTS, &watch_effect("# nothing"));
+
+    let unknown = ("unknown token `watch_trigger`".to_owned(), EFFECTS.to_owned());
+    let encoding = ("Expected UTF-8 BOM encoding".to_owned(), EFFECTS.to_owned());
+
+    let setup = |everything: &mut Everything| {
+        everything.enable_xref();
+        everything.enable_unused();
+    };
+    validate_mod(&mod_root, setup, |everything| {
+        set_keep_reports(true);
+        let reports = emitted_reports();
+        assert!(reports.contains(&encoding));
+        assert!(!reports.contains(&unknown));
+
+        // Renaming the trigger makes the unchanged effects file wrong. The reports that were
+        // made while loading the effects file are still there.
+        write_mod_file(&mod_root, TRIGGERS, "watch_trigger2 = { always = yes }\n");
+        assert!(everything.reload_files(&[mod_root.join(TRIGGERS)]));
+        everything.validate_changed();
+        let reports = emitted_reports();
+        assert!(reports.contains(&encoding));
+        assert!(reports.contains(&unknown));
+
+        write_mod_file(&mod_root, EFFECTS, &watch_effect("# tiger-ignore"));
+        assert!(everything.reload_files(&[mod_root.join(EFFECTS)]));
+        everything.validate_changed();
+        let reports = emitted_reports();
+        assert!(reports.contains(&encoding));
+        assert!(!reports.contains(&unknown));
+
+        // The directive is gone from the file, so it must not suppress the report anymore.
+        write_mod_file(&mod_root, EFFECTS, &watch_effect("# nothing"));
+        assert!(everything.reload_files(&[mod_root.join(EFFECTS)]));
+        everything.validate_changed();
+        assert!(emitted_reports().contains(&unknown));
+
+        write_mod_file(&mod_root, TRIGGERS, "watch_trigger = { always = yes }\n");
+        assert!(everything.reload_files(&[mod_root.join(TRIGGERS)]));
+        everything.validate_changed();
+        let reports = emitted_reports();
+        assert!(reports.contains(&encoding));
+        assert!(!reports.contains(&unknown));
+
+        // The only use of the trigger is edited away, so it is no longer used.
+        write_mod_file(&mod_root, EFFECTS, "watch_effect = { add_gold = 1 }\n");
+        assert!(everything.reload_files(&[mod_root.join(EFFECTS)]));
+        everything.validate_changed();
+        everything.check_unused();
+        let unused =
+            ("scripted trigger `watch_trigger` is never used".to_owned(), TRIGGERS.to_owned());
+        assert!(emitted_reports().contains(&unused));
+
+        // Adding a file can't be handled incrementally.
+        write_mod_file(&mod_root, "common/scripted_effects/new.txt", "");
+        assert!(!everything.reload_files(&[mod_root.join("common/scripted_effects/new.txt")]));
+    });
+    _ = std::fs::remove_dir_all(&mod_root);
+}
+
+#[test]
+fn test_diff_mods() {
+    let saved_reports = |modname: &str| {
+        let mod_root = PathBuf::from(format!("tests/files/{modname}"));
+        validate_mod(&mod_root, |_| (), |_| take_saved_reports())
+    };
+    let old = saved_reports("mod1");
+    let new = saved_reports("mod2");
+    let diff = diff_reports(old, new);
+
+    let has = |reports: &[SavedReport], msg: &str| reports.iter().any(|r| r.message == msg);
+    assert!(has(&diff.introduced, "missing english localization key test_interaction"));
+    assert!(has(&diff.resolved, "missing english localization key my_decision"));
+    assert!(!has(&diff.introduced, "missing english localization key my_decision"));
+    assert!(!has(&diff.resolved, "missing english localization key test_interaction"));
+
+    // Comparing a mod to itself finds no differences.
+    let diff = diff_reports(saved_reports("mod1"), saved_reports("mod1"));
+    assert!(diff.introduced.is_empty());
+    assert!(diff.resolved.is_empty());
+}
+
+#[test]
+fn test_unused_items() {
+    let mod_root = PathBuf::from("tests/files/mod4");
+    let setup = |everything: &mut Everything| {
+        everything.enable_call_graph();
+        everything.enable_unused();
+    };
+    let mut reports = validate_mod(&mod_root, setup, |everything| {
+        everything.check_unused();
+        take_reports()
+    });
+
+    let effects = "common/scripted_effects/effects.txt";
+    let triggers = "common/scripted_triggers/triggers.txt";
+    let values = "common/script_values/values.txt";
+    let events = "events/unused.txt";
+    for (path, msg) in [
+        (events, "event `unused.1` is never used"),
+        (effects, "scripted effect `unused_effect` is never used"),
+        (triggers, "scripted trigger `unused_trigger` is never used"),
+        (values, "script value `unused_value` is never used"),
+        // Used only by the chain of events that starts at unused.1
+        (events, "event `unused.2` is only used by unused items"),
+        (effects, "scripted effect `dead_effect` is only used by unused items"),
+        (triggers, "scripted trigger `dead_trigger` is only used by unused items"),
+        (values, "script value `dead_value` is only used by unused items"),
+    ] {
+        assert!(take_report(&mut reports, path, msg).is_some(), "{msg}");
+    }
+    // The decision is a root, so what it uses is reachable.
+    assert!(!reports
+        .keys()
+        .any(|report| report.msg.contains("`used_effect`") || report.msg.contains("`used_value`")));
+}
+
+fn duplicate_effect(name: &str) -> String {
+    format!("\u{feff}{name} = {{\n\tadd_gold = 1\n}}\n\n{name} = {{\n\tadd_gold = 1\n}}\n")
+}
+
+#[test]
+fn test_apply_fixes() {
+    const EFFECTS: &str = "common/scripted_effects/fix.txt";
+    const DECISIONS: &str = "common/decisions/fix.txt";
+    let root = temp_mod_dir("fix");
+    let (vanilla_dir, loaded_dir, mod_root) =
+        (root.join("vanilla"), root.join("loaded"), root.join("mod"));
+    let game_dir = vanilla_dir.join("game");
+    write_mod_file(&vanilla_dir, "clausewitz/dummy.txt", "");
+    write_mod_file(&vanilla_dir, "jomini/dummy.txt", "");
+    write_mod_file(&game_dir, EFFECTS, &duplicate_effect("vanilla_effect"));
+    write_mod_file(&loaded_dir, EFFECTS, &duplicate_effect("loaded_effect"));
+    write_mod_file(&loaded_dir, "descriptor.mod", "name = \"Loaded\"\n");
+    let mod_effects = format!(
+        "{}\nrenamed_effect = {{\n\tcreate_character = {{\n\t\tsave_event_target_as = new_character\n\t}}\n}}\n",
+        duplicate_effect("mod_effect")
+    );
+    write_mod_file(&mod_root, EFFECTS, &mod_effects);
+    write_mod_file(&mod_root, DECISIONS, "\u{feff}fix_decision = {\n\ttitle = Fix_Title\n}\n");
+    write_mod_file(
+        &mod_root,
+        "localization/english/fix_l_english.yml",
+        "\u{feff}l_english:\n fix_title: \"Title\"\n",
+    );
+
+    let playset = Playset {
+        name: "Fix".to_owned(),
+        mods: vec![PlaysetMod { name: "Loaded".to_owned(), path: loaded_dir.clone() }],
+    };
+    let session = Session::new().unwrap();
+    let summary = session.run(|| {
+        let mut everything = Everything::new_with_playset(
+            None,
+            Some(&vanilla_dir),
+            None,
+            None,
+            &mod_root,
+            Vec::new(),
+            Some(&playset),
+        )
+        .unwrap();
+        everything.load_all();
+        everything.validate_all();
+        apply_fixes()
+    });
+
+    let read = |dir: &Path, path: &str| std::fs::read_to_string(dir.join(path)).unwrap();
+    // The base game and the other loaded mod have the same duplicates, but are not touched.
+    assert_eq!(read(&game_dir, EFFECTS), duplicate_effect("vanilla_effect"));
+    assert_eq!(read(&loaded_dir, EFFECTS), duplicate_effect("loaded_effect"));
+    assert_eq!(
+        read(&mod_root, EFFECTS),
+        "\u{feff}mod_effect = {\n\tadd_gold = 1\n}\n\n\nrenamed_effect = {\n\tcreate_character = {\n\t\tsave_scope_as = new_character\n\t}\n}\n"
+    );
+    assert_eq!(read(&mod_root, DECISIONS), "\u{feff}fix_decision = {\n\ttitle = fix_title\n}\n");
+    assert_eq!(summary.applied, 3);
+    assert_eq!(summary.files, 2);
+    _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_history_timeline() {
+    let mut reports = check_mod_helper("mod5");
+    let titles = "history/titles/timeline.txt";
+    let bookmarks = "common/bookmarks/bookmarks/timeline.txt";
+
+    let msg = "liege loop on 1000.1.1: k_alpha -> k_beta -> k_alpha";
+    let (_, pointers) = take_report(&mut reports, titles, msg).unwrap();
+    assert_eq!(pointers[0].loc.line, 5);
+    assert!(take_report_contains(&mut reports, titles, "liege loop").is_none());
+    let msg = "on 1000.1.1, 3 (highest tier empire) is a vassal of 4 (highest tier duchy)";
+    assert!(take_report(&mut reports, titles, msg).is_some());
+    let msg = "5 holds c_tenure until 1100.1.1, but dies on 1050.1.1";
+    assert!(take_report(&mut reports, titles, msg).is_some());
+
+    // The bookmark only reports the holder whose death isn't reported already.
+    let msg = "holder 7 of c_last is not alive on the start date of bookmark bm_timeline";
+    let (_, pointers) = take_report(&mut reports, bookmarks, msg).unwrap();
+    assert_eq!(pointers[0].loc.line, 2);
+    assert_eq!(pointers[1].loc.pathname(), Path::new(titles));
+    assert!(take_report_contains(&mut reports, bookmarks, "holder 5").is_none());
+}
+
+#[test]
+fn test_province_map() {
+    let mut reports = check_mod_helper("mod6");
+    let provinces = "map_data/provinces.png";
+    let adjacencies = "map_data/adjacencies.csv";
+
+    let msg = "province color (255, 0, 0) is split into 2 separate parts in `provinces.png`";
+    let (report, _) = take_report(&mut reports, provinces, msg).unwrap();
+    assert_eq!(
+        report.info.as_deref(),
+        Some("the main part is at (0, 0) with 12 pixels; the other parts are at (6, 3) with 2 pixels")
+    );
+    let msg = "province color (0, 255, 0) has a stray pixel in `provinces.png`";
+    let (report, _) = take_report(&mut reports, provinces, msg).unwrap();
+    assert_eq!(
+        report.info.as_deref(),
+        Some("stray pixels are not connected to the rest of the province: (1, 5)")
+    );
+    let msg = "four provinces share a common corner in `provinces.png` around (3, 2)";
+    assert!(take_report(&mut reports, provinces, msg).is_some());
+    assert!(take_report_contains(&mut reports, provinces, "common corner").is_none());
+    assert!(take_report_contains(&mut reports, provinces, "province color").is_none());
+
+    // Only the colors at the adjacency coordinates are kept from the image.
+    let msg = "the start point (7, 5) is not in or next to province 1";
+    let (_, pointers) = take_report(&mut reports, adjacencies, msg).unwrap();
+    assert_eq!(pointers[0].loc.line, 3);
+    let msg = "the stop point (20, 20) is outside the map";
+    assert!(take_report(&mut reports, adjacencies, msg).is_some());
+    assert!(take_report_contains(&mut reports, adjacencies, " point ").is_none());
+    assert!(take_report_contains(&mut reports, adjacencies, "does not border").is_none());
+}
+
+#[test]
+fn test_call_graph() {
+    let mod_root = PathBuf::from("tests/files/mod7");
+    let edges = |enable: bool| {
+        let setup = |everything: &mut Everything| {
+            if enable {
+                everything.enable_call_graph();
+            }
+        };
+        validate_mod(&mod_root, setup, |everything| {
+            take_reports();
+            everything
+                .call_graph()
+                .edges()
+                .into_iter()
+                .map(|edge| {
+                    let (from, to) = (edge.from.id(), edge.to.id());
+                    (from, to, edge.context, edge.delay, edge.loc.line)
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    // Nothing is recorded unless asked for.
+    assert!(edges(false).is_empty());
+
+    let edge = |from: &str, to: &str, context: &str, delay: Option<&str>, line| {
+        (from.to_owned(), to.to_owned(), context.to_owned(), delay.map(str::to_owned), line)
+    };
+    let action = "on_action:graph_on_action";
+    let other_action = "on_action:graph_other_on_action";
+    let effect = "scripted_effect:graph_effect";
+    let expected = vec![
+        edge("event:graph.1", "event:graph.2", "trigger_event", Some("2-4 days"), 9),
+        edge("event:graph.1", effect, "effect", None, 12),
+        edge("event:graph.2", "event:graph.3", "trigger_event", None, 21),
+        edge(action, "event:graph.1", "events", None, 3),
+        edge(action, "event:graph.2", "events", Some("5 days"), 5),
+        edge(action, "event:graph.3", "random_events", None, 8),
+        edge(action, other_action, "on_actions", None, 11),
+        edge(effect, other_action, "trigger_event", Some("1 months"), 3),
+    ];
+    let mut actual = edges(true);
+    actual.sort();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_baseline() {
+    let mod_root = temp_mod_dir("baseline");
+    let baseline = mod_root.with_extension("json");
+    let effects = "common/scripted_effects/effects.txt";
+
+    write_mod_file(
+        &mod_root,
+        effects,
+        "first_effect = {\n\ttrigger_event = missing.1\n}\n\
+         second_effect = {\n\ttrigger_event = missing.1\n}\n",
+    );
+    let written = validate_mod(&mod_root, |_| (), |_| write_baseline(&baseline).unwrap());
+    assert!(written >= 2);
+
+    // Reindented and moved lines still match the baseline. The third copy of the same line is
+    // new, even though the missing event is reported only once when reports are consolidated.
+    write_mod_file(
+        &mod_root,
+        effects,
+        "\n\nsecond_effect = {\n    trigger_event   =   missing.1\n}\n\
+         third_effect = {\n\t\ttrigger_event = missing.1\n}\n\
+         first_effect = {\ntrigger_event = missing.1\n}\n",
+    );
+    let setup = |_: &mut Everything| load_baseline(&baseline).unwrap();
+    let (summary, output) = validate_mod(&mod_root, setup, |_| {
+        let mut output = Vec::new();
+        emit_reports(&mut output, false, true, false);
+        (baseline_summary().unwrap(), String::from_utf8(output).unwrap())
+    });
+    assert_eq!(summary, BaselineSummary { new: 1, fixed: 0, still_present: written });
+    assert!(output.contains("missing.1"), "{output}");
+
+    _ = std::fs::remove_dir_all(&mod_root);
+    _ = std::fs::remove_file(&baseline);
+}
+
+#[test]
+fn test_ignore() {
+    let mod_root = PathBuf::from("tests/files/mod10");
+    let setup = |everything: &mut Everything| everything.load_config_filtering_rules();
+    let reports: Vec<(String, u32)> = validate_mod(&mod_root, setup, |everything| {
+        everything.check_unused();
+        take_sorted_reports()
+            .into_iter()
+            .map(|(report, pointers)| (report.msg, pointers[0].loc.line))
+            .collect()
+    });
+    let has = |msg: &str, line: u32| reports.contains(&(msg.to_owned(), line));
+    let has_msg = |msg: &str| reports.iter().any(|(m, _)| m == msg);
+
+    // An ignore at the call site suppresses the report inside the expansion, but not the report
+    // from the next call.
+    assert!(!has_msg("event missing.1 not defined in events/"));
+    assert!(has_msg("event missing.2 not defined in events/"));
+
+    // An expired ignore no longer suppresses anything and is reported itself.
+    assert!(has_msg("event missing.3 not defined in events/"));
+    assert!(has("this tiger-ignore expired at 2000-01-01", 10));
+    assert!(!has_msg("event missing.4 not defined in events/"));
+
+    // The ignore of the report that the config filters out counts as used.
+    let unused = "this tiger-ignore did not suppress any reports";
+    assert_eq!(reports.iter().filter(|(msg, _)| msg == unused).count(), 1, "{reports:?}");
+    assert!(has(unused, 18));
+}
+
+#[test]
+fn test_xref_index() {
+    const EVENTS: &str = "events/graph.txt";
+    const ON_ACTIONS: &str = "common/on_action/graph.txt";
+    let mod_root = PathBuf::from("tests/files/mod7");
+    let mut index =
+        validate_mod(&mod_root, Everything::enable_xref, |everything| everything.xref_index());
+    let locations = |tokens: &[Token]| {
+        let mut locations: Vec<_> = tokens
+            .iter()
+            .map(|t| (t.loc.pathname().to_string_lossy().into_owned(), t.loc.line, t.loc.column))
+            .collect();
+        locations.sort();
+        locations
+    };
+    let loc = |path: &str, line, column| (path.to_owned(), line, column);
+
+    let entry = index.get(Item::Event, "graph.2").unwrap();
+    assert_eq!(locations(&entry.definitions), vec![loc(EVENTS, 16, 1)]);
+    assert_eq!(locations(&entry.uses), vec![loc(ON_ACTIONS, 5, 3), loc(EVENTS, 9, 9)]);
+    let entry = index.get(Item::ScriptedEffect, "graph_effect").unwrap();
+    assert_eq!(locations(&entry.definitions), vec![loc("common/scripted_effects/graph.txt", 1, 1)]);
+    assert_eq!(locations(&entry.uses), vec![loc(EVENTS, 12, 3)]);
+
+    // `id = graph.2` has the event id in columns 9 to 15.
+    let events = Path::new(EVENTS);
+    assert_eq!(index.symbol_at(events, 9, 8), None);
+    assert_eq!(index.symbol_at(events, 9, 9), Some((Item::Event, "graph.2")));
+    assert_eq!(index.symbol_at(events, 9, 15), Some((Item::Event, "graph.2")));
+    assert_eq!(index.symbol_at(events, 9, 16), None);
+    assert_eq!(index.symbol_at(events, 16, 3), Some((Item::Event, "graph.2")));
+    assert_eq!(index.symbol_at(Path::new(ON_ACTIONS), 5, 3), Some((Item::Event, "graph.2")));
+
+    // Vanilla items that the mod doesn't touch are dropped, the mod's items are kept.
+    assert!(index.get(Item::Event, "non-dup.0001").is_some());
+    index.retain_mod_related();
+    assert!(index.get(Item::Event, "non-dup.0001").is_none());
+    assert!(index.get(Item::Event, "graph.1").is_some());
+    assert!(index.get(Item::OnAction, "graph_other_on_action").is_some());
+    assert!(index.iter().all(|(_, _, entry)| entry
+        .definitions
+        .iter()
+        .chain(&entry.uses)
+        .any(|token| token.loc.kind == FileKind::Mod)));
+}
+
+#[test]
+fn test_script_signatures() {
+    let mod_root = PathBuf::from("tests/files/mod11");
+    let json = validate_mod(
+        &mod_root,
+        |_| (),
+        |everything| {
+            let mut signatures = everything.script_signatures();
+            signatures.retain_mod_related();
+            let mut output = Vec::new();
+            signatures.write_json(&mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        },
+    );
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let signature = |key: &str| {
+        let signature = json.as_array().unwrap().iter().find(|sig| sig["key"] == key).unwrap();
+        let field = |name| signature[name].clone();
+        (field("root"), field("this"), field("saved_scopes"), field("saved_lists"))
+    };
+    let none = serde_json::Value::Null;
+
+    // An effect on `this` leaves the root open. Saved scopes and lists that are used without being
+    // set are expected from the caller.
+    assert_eq!(
+        signature("sig_effect"),
+        (
+            json!("all"),
+            json!("character"),
+            json!({ "target": "character" }),
+            json!({ "sig_list": "character" })
+        )
+    );
+    assert_eq!(
+        signature("sig_root_effect"),
+        (json!("character"), json!("all"), json!({}), json!({}))
+    );
+    assert_eq!(
+        signature("sig_trigger"),
+        (json!("all"), json!("all"), json!({ "other": "character" }), json!({}))
+    );
+
+    // With macro parameters, the scopes are only known per call.
+    assert_eq!(signature("sig_macro_effect"), (none.clone(), none.clone(), none.clone(), none));
+    let macro_effect = json.as_array().unwrap().iter().find(|sig| sig["key"] == "sig_macro_effect");
+    assert_eq!(macro_effect.unwrap()["macro_parameters"], json!(["AMOUNT", "PRESTIGE"]));
+}
+
+#[test]
+fn test_macro_trace() {
+    let mod_root = PathBuf::from("tests/files/mod12");
+    let output = validate_mod(
+        &mod_root,
+        |_| (),
+        |_| {
+            set_macro_trace(true);
+            let mut output = Vec::new();
+            emit_reports(&mut output, true, false, false);
+            String::from_utf8(output).unwrap()
+        },
+    );
+    let reports: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let report = reports
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|report| report["message"] == "event missing.7 not defined in events/")
+        .unwrap();
+
+    // The innermost expansion comes first. Each frame has the call site and the values that were
+    // substituted for the parameters there.
+    let frame = |name: &str, args: serde_json::Value, linenr: u32| {
+        json!({
+            "type": "scripted_effect",
+            "name": name,
+            "args": args,
+            "path": "common/scripted_effects/trace.txt",
+            "from": "MOD",
+            "linenr": linenr,
+            "column": 2,
+        })
+    };
+    assert_eq!(
+        report["macro_trace"],
+        json!([
+            frame("trace_inner_effect", json!({ "EVENT": "missing.7" }), 5),
+            frame("trace_outer_effect", json!({ "TARGET": "missing.7" }), 8),
+        ])
+    );
+}
+
+#[test]
+fn test_map_images() {
+    let mod_root = PathBuf::from("tests/files/mod8");
+    let mut reports = validate_mod(
+        &mod_root,
+        |_| (),
+        |everything| {
+            everything.check_rivers();
+            take_reports()
+        },
+    );
+    let heightmap = "map_data/heightmap.png";
+    let rivers = "map_data/rivers.png";
+
+    // 3.9 / 51 * 255 = 19.5, so the water level is 19. Sea zone 2 is all at 19 and sea zone 3 has
+    // 7 of its 12 pixels at 20.
+    let msg = "sea or lake province 3 is mostly above the water level";
+    let (report, _) = take_report(&mut reports, heightmap, msg).unwrap();
+    let info = "7 of its 12 pixels are higher than the water level set by NJominiMap|WATERLEVEL";
+    assert_eq!(report.info.as_deref(), Some(info));
+    assert!(take_report_contains(&mut reports, heightmap, "sea or lake").is_none());
+
+    // The river pixel on the coast at (4, 1) has a land neighbor, the one at (6, 1) doesn't.
+    let msg = "1 river pixels are in the water according to the heightmap";
+    let (report, _) = take_report(&mut reports, rivers, msg).unwrap();
+    assert_eq!(report.info.as_deref(), Some("at (6, 1)"));
+    assert!(take_report_contains(&mut reports, rivers, "in the water").is_none());
+
+    // With a tile size of 3, each tile covers 2 pixels of the 8x6 map.
+    let msg = "image is 3x3 pixels, but 8x6 divided into tiles of 2 pixels is 4x3";
+    assert!(take_report(&mut reports, "map_data/indirection_heightmap.png", msg).is_some());
+    let msg = "image should have 8 bits per channel, but it has 16";
+    assert!(take_report(&mut reports, "map_data/packed_heightmap.png", msg).is_some());
+    assert!(take_report_contains(&mut reports, "map_data/provinces.png", "").is_none());
+    assert!(take_report_contains(&mut reports, heightmap, "").is_none());
+}
+
+#[test]
+fn test_river_graph() {
+    let mod_root = PathBuf::from("tests/files/mod9");
+    let graph = validate_mod(&mod_root, |_| (), |everything| everything.river_graph().unwrap());
+    assert_eq!((graph.width, graph.height), (10, 9));
+    let nodes: Vec<_> = graph.nodes.iter().map(|node| (node.kind, node.x, node.y)).collect();
+    assert_eq!(
+        nodes,
+        [
+            (RiverNodeKind::Source, 1, 5),
+            (RiverNodeKind::Source, 4, 5),
+            (RiverNodeKind::Tributary, 5, 7),
+        ]
+    );
+    assert!(graph.nodes.iter().all(|node| node.problems.is_empty()));
+    let problems = |segment: &RiverSegment| -> Vec<(String, String)> {
+        segment.problems.iter().map(|p| (p.msg.clone(), p.fix.clone())).collect()
+    };
+    let [ring, orphan, single, between, first, second] = &graph.segments[..] else {
+        panic!("expected 6 segments, got {}", graph.segments.len());
+    };
+
+    // The ring around (2, 2) is walked from its first pixel in scan order.
+    let pixels = [(1, 1), (2, 1), (3, 1), (3, 2), (3, 3), (2, 3), (1, 3), (1, 2)];
+    assert_eq!(ring.pixels, pixels);
+    assert!(ring.ordered && ring.terminators.is_empty());
+    let fix = "remove the pixel at (1, 1) and change the pixel at (2, 1) to a source (index 0)";
+    assert_eq!(problems(ring), [("river forms a loop".to_owned(), fix.to_owned())]);
+
+    let msg = "river segment has no source, tributary, or split pixel";
+    assert_eq!(orphan.pixels, [(6, 1), (7, 1), (8, 1)]);
+    assert_eq!((orphan.min_width, orphan.max_width), (3, 5));
+    assert!(orphan.terminators.is_empty());
+    let fix = "change the pixel at (6, 1) to a source (index 0)";
+    assert_eq!(problems(orphan), [(msg.to_owned(), fix.to_owned())]);
+    // A single orphan pixel can't become a source itself, so one is added next to it.
+    assert_eq!(single.pixels, [(8, 4)]);
+    let fix = "add a source pixel (index 0) at (7, 4)";
+    assert_eq!(problems(single), [(msg.to_owned(), fix.to_owned())]);
+
+    assert_eq!(between.pixels, [(2, 5), (3, 5)]);
+    assert_eq!(between.terminators, [0, 1]);
+    let msg = "river segment has two terminators";
+    let fix = "remove the source pixel at (1, 5)";
+    assert_eq!(problems(between), [(msg.to_owned(), fix.to_owned())]);
+
+    // Both segments end at the tributary, but only the second one is reported.
+    assert_eq!(first.pixels, [(3, 7), (4, 7)]);
+    assert_eq!(first.terminators, [2]);
+    assert!(first.problems.is_empty());
+    assert_eq!(second.pixels, [(6, 7), (7, 7)]);
+    assert_eq!(second.terminators, [2]);
+    let msg = "segment ends at the same tributary pixel (5, 7) as another segment";
+    let fix = "change the pixel at (6, 7) to a source (index 0), so that this segment no longer ends at (5, 7)";
+    assert_eq!(problems(second), [(msg.to_owned(), fix.to_owned())]);
+}
+
+#[test]
+fn test_severity_override() {
+    let mod_root = PathBuf::from("tests/files/mod13");
+    let setup = |everything: &mut Everything| everything.load_config_filtering_rules();
+    let mut reports = validate_mod(&mod_root, setup, |_| take_reports());
+    let levels = |report: &LogReportMetadata| (report.severity, report.confidence);
+
+    // A plain severity keeps the report's own confidence.
+    let msg = "missing english localization for name Bob";
+    let (report, _) = take_report(&mut reports, "history/characters/override.txt", msg).unwrap();
+    assert_eq!(levels(&report), (Severity::Warning, Confidence::Strong));
+    let msg = "possible brace error";
+    let (report, _) =
+        take_report(&mut reports, "common/scripted_effects/override.txt", msg).unwrap();
+    assert_eq!(levels(&report), (Severity::Tips, Confidence::Weak));
+    // Only the confidence is changed.
+    let msg = "required field `picture` missing";
+    let (report, _) = take_report(&mut reports, "common/decisions/override.txt", msg).unwrap();
+    assert_eq!(levels(&report), (Severity::Warning, Confidence::Weak));
+
+    // The `config` key and unknown keys are rejected, and their reports keep their severity.
+    let conf = "ck3-tiger.conf";
+    let msg = "the severity of `config` reports can't be changed";
+    let (report, pointers) = take_report(&mut reports, conf, msg).unwrap();
+    assert_eq!(report.severity, Severity::Error);
+    assert_eq!(pointers[0].loc.line, 5);
+    let (report, pointers) = take_report_contains(&mut reports, conf, "Invalid key.").unwrap();
+    assert_eq!(report.severity, Severity::Error);
+    assert_eq!(pointers[0].loc.line, 6);
+    assert!(take_report_contains(&mut reports, conf, "").is_none());
+}
+
+#[test]
+fn test_filter_rules() {
+    const EFFECTS: &str = "common/scripted_effects/filter.txt";
+    const DEP_EFFECTS: &str = "common/scripted_effects/dep.txt";
+    const EVENTS: &str = "events/filter.txt";
+    let mod_root = PathBuf::from("tests/files/mod14");
+    // Each config loads mod15 as DEP and shows the reports about vanilla and DEP too, so that
+    // only the rule in its trigger decides what is shown.
+    let filtered = |rule: &str| -> Vec<(String, u32, FileKind, usize)> {
+        let config = mod_root.join(format!("filters/{rule}.conf"));
+        let setup = |everything: &mut Everything| everything.load_config_filtering_rules();
+        let mut reports: Vec<_> = validate_mod_with_config(&mod_root, Some(&config), setup, |_| {
+            take_reports()
+                .into_values()
+                .flatten()
+                .map(|pointers| {
+                    let loc = pointers[0].loc;
+                    (loc.pathname().display().to_string(), loc.line, loc.kind, pointers.len())
+                })
+                .collect()
+        });
+        reports.sort_unstable_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+        reports
+    };
+    let ours = |reports: &[(String, u32, FileKind, usize)]| -> Vec<(String, u32)> {
+        reports
+            .iter()
+            .filter(|(_, _, kind, _)| matches!(kind, FileKind::Mod | FileKind::LoadedMod(_)))
+            .map(|(path, line, _, _)| (path.clone(), *line))
+            .collect()
+    };
+    let at = |path: &str, line: u32| (path.to_owned(), line);
+
+    // `mod = DEP` matches only the loaded mod with that label.
+    let reports = filtered("mod");
+    assert_eq!(ours(&reports), [at(DEP_EFFECTS, 3)]);
+    assert!(reports.iter().all(|(_, _, kind, _)| *kind == FileKind::LoadedMod(0)));
+
+    // `from = mod` is the mod being validated, not the other loaded mods.
+    let reports = filtered("from");
+    let expected = [at(EFFECTS, 1), at(EFFECTS, 5), at(EFFECTS, 6), at(EVENTS, 3), at(EVENTS, 6)];
+    assert_eq!(ours(&reports), expected);
+    assert!(reports.iter().all(|(_, _, kind, _)| *kind == FileKind::Mod));
+
+    // Only the duplicate points at both definitions.
+    let reports = filtered("pointers");
+    assert_eq!(ours(&reports), [at(EFFECTS, 1)]);
+    assert!(reports.iter().all(|(_, _, _, pointers)| *pointers >= 2));
+
+    // `line = 3-5` includes both ends of the range.
+    let reports = filtered("line");
+    assert_eq!(ours(&reports), [at(DEP_EFFECTS, 3), at(EFFECTS, 5), at(EVENTS, 3)]);
+    assert!(reports.iter().all(|(_, line, _, _)| (3..=5).contains(line)));
+
+    // The duplicate report isn't made while validating the scripted effect.
+    let reports = filtered("item_type");
+    assert_eq!(ours(&reports), [at(DEP_EFFECTS, 3), at(EFFECTS, 5), at(EFFECTS, 6)]);
+}
+
+#[test]
+fn test_sarif_output() {
+    // The base URI is only meaningful for an absolute mod root.
+    let mod_root = std::fs::canonicalize("tests/files/mod13").unwrap();
+    let setup = |everything: &mut Everything| everything.load_config_filtering_rules();
+    let output = validate_mod(&mod_root, setup, |_| {
+        let mut output = Vec::new();
+        assert!(emit_reports_sarif(&mut output, "tiger-test", "1.0"));
+        output
+    });
+    let log: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let run = &log["runs"][0];
+
+    let base = format!("{}/", path_to_uri(&mod_root));
+    assert_eq!(run["originalUriBaseIds"], json!({ "MOD": { "uri": base } }));
+
+    let result = |path: &str, line: u32| {
+        run["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|result| {
+                let location = &result["locations"][0]["physicalLocation"];
+                location["artifactLocation"] == json!({ "uri": path, "uriBaseId": "MOD" })
+                    && location["region"]["startLine"] == line
+            })
+            .unwrap_or_else(|| panic!("no result at {path}:{line}"))
+    };
+
+    // The levels follow the severities after the config's overrides.
+    assert_eq!(result("ck3-tiger.conf", 5)["level"], "error");
+    assert_eq!(result("history/characters/override.txt", 2)["level"], "warning");
+    assert_eq!(result("common/scripted_effects/override.txt", 5)["level"], "note");
+
+    // The region of the unlocalized name `Bob` ends after its three characters.
+    let region =
+        &result("history/characters/override.txt", 2)["locations"][0]["physicalLocation"]["region"];
+    assert_eq!(region["startColumn"], 10);
+    assert_eq!(region["endColumn"], 13);
+}
+
+#[test]
+fn test_report_stats() {
+    let mod_root = PathBuf::from("tests/files/mod14");
+    let (stats, remaining) =
+        validate_mod(&mod_root, |_| (), |_| (report_stats(), take_reports().len()));
+    // Counting leaves the reports to be printed.
+    assert_eq!(remaining, 5);
+
+    assert_eq!(stats.total, 5);
+    let counts = |pairs: &[(&str, usize)]| {
+        pairs.iter().map(|(k, v)| ((*k).to_owned(), *v)).collect::<BTreeMap<_, _>>()
+    };
+    assert_eq!(stats.by_severity, BTreeMap::from([(Severity::Warning, 1), (Severity::Error, 4)]));
+    assert_eq!(
+        stats.by_key,
+        counts(&[
+            ("duplicate-item", 1),
+            ("field-missing", 1),
+            ("missing-item", 2),
+            ("unknown-field", 1)
+        ])
+    );
+    assert_eq!(stats.by_folder, counts(&[("common", 3), ("events", 2)]));
+    assert_eq!(
+        stats.by_file,
+        counts(&[("common/scripted_effects/filter.txt", 3), ("events/filter.txt", 2)])
+    );
+
+    // The tables have the largest counts first, and ties in name order.
+    let mut table = Vec::new();
+    stats.write_table(&mut table).unwrap();
+    let table = String::from_utf8(table).unwrap();
+    assert!(table.starts_with("Total reports: 5\n"), "{table}");
+    assert!(table.contains("\nBy severity:\n  error          4\n  warning        1\n"), "{table}");
+    assert!(
+        table.contains(
+            "\nBy key:\n  missing-item          2\n  duplicate-item        1\n  \
+             field-missing         1\n  unknown-field         1\n"
+        ),
+        "{table}"
+    );
+    assert!(table.contains("\nBy folder:\n  common        3\n  events        2\n"), "{table}");
+
+    // Each append adds one line of JSON to the history.
+    let history = temp_mod_dir("stats").with_extension("jsonl");
+    _ = std::fs::remove_file(&history);
+    stats.append_to_history(&history, Some("first")).unwrap();
+    stats.append_to_history(&history, None).unwrap();
+    let lines: Vec<serde_json::Value> = std::fs::read_to_string(&history)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    _ = std::fs::remove_file(&history);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["label"], "first");
+    assert_eq!(lines[1]["label"], serde_json::Value::Null);
+    for line in &lines {
+        assert_eq!(line["total"], 5);
+        assert_eq!(line["by_key"]["missing-item"], 2);
+        assert_eq!(line["by_folder"], json!({ "common": 3, "events": 2 }));
+        assert!(line["timestamp"].as_u64().unwrap() > 0);
+    }
+}
+
+#[test]
+fn test_compat_report() {
+    let mod_root = PathBuf::from("tests/files/mod16");
+    let report = validate_mod(&mod_root, |_| (), |everything| everything.compat_report());
+    let pairs: Vec<_> = report.iter().collect();
+    assert_eq!(pairs.len(), 1);
+    let (earlier, later, conflicts) = pairs[0];
+    assert_eq!((earlier, later), (FileKind::LoadedMod(0), FileKind::Mod));
+    assert!(conflicts.files.is_empty());
+    assert!(conflicts.replaced_dirs.is_empty());
+
+    let place = |token: &Token| (token.loc.kind, token.loc.pathname().to_owned(), token.loc.line);
+    let early = |path: &str, line| (FileKind::LoadedMod(0), PathBuf::from(path), line);
+    let late = |path: &str, line| (FileKind::Mod, PathBuf::from(path), line);
+
+    // The later mod's scripted effect replaces the earlier one.
+    assert_eq!(conflicts.items.len(), 1);
+    let item = &conflicts.items[0];
+    assert_eq!((item.itype, item.winner.as_str()), (Item::ScriptedEffect, "compat_effect"));
+    assert_eq!(place(&item.winner), late("common/scripted_effects/later.txt", 1));
+    assert_eq!(place(&item.loser), early("common/scripted_effects/earlier.txt", 2));
+
+    // In localization, the first definition is the one that is used.
+    assert_eq!(conflicts.localization.len(), 1);
+    let loca = &conflicts.localization[0];
+    assert_eq!((loca.itype, loca.winner.as_str()), (Item::Localization, "compat_key"));
+    assert_eq!(place(&loca.winner), early("localization/english/earlier_l_english.yml", 3));
+    assert_eq!(place(&loca.loser), late("localization/english/later_l_english.yml", 2));
+}
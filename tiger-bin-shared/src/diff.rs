@@ -0,0 +1,107 @@
+//! The `diff` subcommand, which shows the reports that differ between two versions of a mod.
+
+use std::io::{stdout, Write};
+use std::mem::forget;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tiger_lib::{
+    diff_reports, saved_reports_from_json, set_show_loaded_mods, set_show_vanilla,
+    take_saved_reports, Everything, SavedReport, Session,
+};
+
+use crate::tiger::find_mod;
+
+/// The settings needed to validate a mod.
+pub struct DiffSettings {
+    pub game: Option<PathBuf>,
+    pub workshop: Option<PathBuf>,
+    pub paradox: Option<PathBuf>,
+    pub config: Option<PathBuf>,
+    pub show_vanilla: bool,
+    pub show_mods: bool,
+}
+
+/// Get the reports for one side of the comparison: either by reading them from a JSON report
+/// file, or by validating a mod.
+pub fn reports_for(settings: &DiffSettings, path: &Path) -> Result<Vec<SavedReport>> {
+    if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+        eprintln!("Reading reports from {}", path.display());
+        return saved_reports_from_json(path);
+    }
+    let (modpath, replace_paths) = find_mod(path.to_path_buf())?;
+    eprintln!("Validating mod in {}", modpath.display());
+    // Each side gets its own session, so that the loaded mod labels, `tiger-ignore` directives,
+    // and reports of one side don't affect the other.
+    let session = Session::new()?;
+    session.run(|| {
+        let mut everything = Everything::new(
+            settings.config.as_deref(),
+            settings.game.as_deref(),
+            settings.workshop.as_deref(),
+            settings.paradox.as_deref(),
+            &modpath,
+            replace_paths,
+        )?;
+        everything.load_config_filtering_rules();
+        if settings.show_vanilla {
+            set_show_vanilla(true);
+        }
+        if settings.show_mods {
+            set_show_loaded_mods(true);
+        }
+        everything.load_all();
+        everything.validate_all();
+        everything.check_rivers();
+        // Properly dropping `everything` takes a noticeable amount of time, and we're exiting soon.
+        forget(everything);
+        Ok(take_saved_reports())
+    })
+}
+
+/// Compare the two sets of reports and print the differences.
+pub fn print_diff(old: Vec<SavedReport>, new: Vec<SavedReport>, json: bool) -> Result<()> {
+    let diff = diff_reports(old, new);
+    let mut output = stdout().lock();
+    if json {
+        serde_json::to_writer_pretty(&mut output, &diff)?;
+        writeln!(output)?;
+    } else {
+        for report in &diff.introduced {
+            print_report(&mut output, '+', report)?;
+        }
+        for report in &diff.resolved {
+            print_report(&mut output, '-', report)?;
+        }
+    }
+    eprintln!(
+        "{} reports introduced, {} reports resolved.",
+        diff.introduced.len(),
+        diff.resolved.len()
+    );
+    Ok(())
+}
+
+fn print_report<O: Write>(output: &mut O, sign: char, report: &SavedReport) -> Result<()> {
+    writeln!(output, "{sign} {}({}): {}", report.severity, report.key, report.message)?;
+    for location in &report.locations {
+        write!(output, "    --> [{}] {}", location.from, location.path.display())?;
+        if let Some(linenr) = location.linenr {
+            write!(output, ":{linenr}")?;
+            if let Some(column) = location.column {
+                write!(output, ":{column}")?;
+            }
+        }
+        if let Some(tag) = &location.tag {
+            write!(output, " {tag}")?;
+        }
+        writeln!(output)?;
+        if let Some(line) = &location.line {
+            writeln!(output, "    {}", line.trim())?;
+        }
+    }
+    if let Some(info) = &report.info {
+        writeln!(output, "    = Info: {info}")?;
+    }
+    Ok(())
+}
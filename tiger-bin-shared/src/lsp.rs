@@ -0,0 +1,515 @@
+//! A minimal Language Server Protocol front end, so that editors can show tiger's reports as
+//! diagnostics while the mod is being edited.
+//!
+//! The server speaks JSON-RPC over stdin and stdout. It validates the whole mod once the editor
+//! has connected, and again every time a file is saved. The fixes that `--fix` would apply are
+//! offered as quick fix code actions.
+
+use std::fs::read_to_string;
+use std::io::{stdin, stdout, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+#[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
+use tiger_lib::ModFile;
+#[cfg(feature = "vic3")]
+use tiger_lib::ModMetadata;
+use tiger_lib::{
+    applicable_fix, path_to_uri, set_show_loaded_mods, set_show_vanilla, take_sorted_reports,
+    Everything, LogReport, PointedMessage, Session, Severity, TigerHashMap, TigerHashSet,
+};
+
+/// JSON-RPC error code for requests that this server does not support.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The directories and settings used for each validation run.
+#[derive(Debug, Clone, Default)]
+pub struct LspSettings {
+    pub game: Option<PathBuf>,
+    pub workshop: Option<PathBuf>,
+    pub paradox: Option<PathBuf>,
+    pub config: Option<PathBuf>,
+    pub show_vanilla: bool,
+    pub show_mods: bool,
+}
+
+/// Run the language server until the editor tells it to exit.
+pub fn run(settings: LspSettings, bin_name: &'static str, version: &'static str) -> Result<()> {
+    let mut input = stdin().lock();
+    let mut output = stdout().lock();
+    let mut server = Server::new(settings, bin_name);
+    let mut shutdown = false;
+
+    while let Some(message) = read_message(&mut input)? {
+        let method = message.get("method").and_then(Value::as_str);
+        let id = message.get("id").cloned();
+        match (method, id) {
+            (Some("initialize"), Some(id)) => {
+                server.root = root_from_params(&message["params"]);
+                let result = json!({
+                    "capabilities": {
+                        "textDocumentSync": {
+                            "openClose": true,
+                            "change": 0,
+                            "save": { "includeText": false },
+                        },
+                        "codeActionProvider": { "codeActionKinds": ["quickfix"] },
+                    },
+                    "serverInfo": { "name": bin_name, "version": version },
+                });
+                write_message(
+                    &mut output,
+                    &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                )?;
+            }
+            (Some("initialized" | "textDocument/didSave"), None) if !shutdown => {
+                server.validate(&mut output)?;
+            }
+            (Some("textDocument/codeAction"), Some(id)) => {
+                let result = code_actions(&message["params"]);
+                write_message(
+                    &mut output,
+                    &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                )?;
+            }
+            (Some("shutdown"), Some(id)) => {
+                shutdown = true;
+                write_message(&mut output, &json!({ "jsonrpc": "2.0", "id": id, "result": null }))?;
+            }
+            (Some("exit"), None) => {
+                if shutdown {
+                    return Ok(());
+                }
+                bail!("Editor exited the language server without shutting it down");
+            }
+            (Some(method), Some(id)) => {
+                let error = json!({
+                    "code": METHOD_NOT_FOUND,
+                    "message": format!("Unsupported request {method}"),
+                });
+                write_message(&mut output, &json!({ "jsonrpc": "2.0", "id": id, "error": error }))?;
+            }
+            // Other notifications, and responses to requests we never make.
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// The state kept by the language server between validation runs.
+#[derive(Debug)]
+struct Server {
+    settings: LspSettings,
+    bin_name: &'static str,
+    /// The workspace folder that the editor opened. It should be the mod's root.
+    root: Option<PathBuf>,
+    /// URIs that received diagnostics in the previous run. They must be cleared if they are clean now.
+    published: TigerHashSet<String>,
+}
+
+impl Server {
+    fn new(settings: LspSettings, bin_name: &'static str) -> Self {
+        Server { settings, bin_name, root: None, published: TigerHashSet::default() }
+    }
+
+    /// Validate the whole mod and publish the reports as diagnostics.
+    fn validate<O: Write>(&mut self, output: &mut O) -> Result<()> {
+        let Some(root) = self.root.clone() else {
+            log_message(
+                output,
+                1,
+                "No workspace folder was opened, so there is nothing to validate.",
+            )?;
+            return Ok(());
+        };
+        let reports = match self.run_validation(&root) {
+            Ok(reports) => reports,
+            Err(e) => {
+                log_message(output, 1, &format!("Validation failed: {e:#}"))?;
+                return Ok(());
+            }
+        };
+
+        let mut diagnostics: TigerHashMap<String, Vec<Value>> = TigerHashMap::default();
+        let mut lines = LineCache::default();
+        for report in &reports {
+            let uri = path_to_uri(report.1[0].loc.fullpath());
+            let diagnostic = self.diagnostic(report, &mut lines);
+            diagnostics.entry(uri).or_default().push(diagnostic);
+        }
+
+        let stale: Vec<_> =
+            self.published.iter().filter(|uri| !diagnostics.contains_key(*uri)).cloned().collect();
+        for uri in stale {
+            publish(output, &uri, &[])?;
+        }
+        self.published.clear();
+        for (uri, diagnostics) in diagnostics {
+            publish(output, &uri, &diagnostics)?;
+            self.published.insert(uri);
+        }
+        Ok(())
+    }
+
+    fn run_validation(&self, root: &Path) -> Result<Vec<LogReport>> {
+        // Each run gets its own session, so that the loaded mod labels and `tiger-ignore`
+        // directives of earlier runs don't pile up.
+        let session = Session::new()?;
+        let settings = &self.settings;
+        session.run(|| validate_mod(settings, root))
+    }
+
+    /// Convert a report to an LSP `Diagnostic`.
+    /// The first pointer becomes the range; the others, and the info and wiki lines, become
+    /// related information.
+    fn diagnostic(&self, (report, pointers): &LogReport, lines: &mut LineCache) -> Value {
+        let primary = &pointers[0];
+        let mut related: Vec<_> = pointers[1..]
+            .iter()
+            .map(|pointer| {
+                json!({
+                    "location": location(pointer, lines),
+                    "message": pointer.msg.as_deref().unwrap_or("related location"),
+                })
+            })
+            .collect();
+        if let Some(info) = &report.info {
+            related.push(
+                json!({ "location": location(primary, lines), "message": format!("Info: {info}") }),
+            );
+        }
+        if let Some(wiki) = &report.wiki {
+            related.push(
+                json!({ "location": location(primary, lines), "message": format!("Wiki: {wiki}") }),
+            );
+        }
+
+        let message = match &primary.msg {
+            Some(msg) => format!("{}\n{msg}", report.msg),
+            None => report.msg.clone(),
+        };
+        let mut diagnostic = json!({
+            "range": range(primary, lines),
+            "severity": match report.severity {
+                Severity::Fatal | Severity::Error => 1,
+                Severity::Warning => 2,
+                Severity::Untidy => 3,
+                Severity::Tips => 4,
+            },
+            "code": report.key.to_string(),
+            "source": self.bin_name,
+            "message": message,
+            "relatedInformation": related,
+        });
+        if let Some(wiki) = &report.wiki {
+            diagnostic["codeDescription"] = json!({ "href": wiki });
+        }
+        // Only offer the fixes that --fix would apply. The code action request will send them
+        // back.
+        let fixes: Vec<_> = pointers
+            .iter()
+            .filter_map(|pointer| {
+                applicable_fix(report, pointer).map(|fix| {
+                    json!({
+                        "uri": path_to_uri(pointer.loc.fullpath()),
+                        "range": fix_range(pointer, lines),
+                        "newText": fix,
+                    })
+                })
+            })
+            .collect();
+        if !fixes.is_empty() {
+            diagnostic["data"] = json!({ "fixes": fixes });
+        }
+        diagnostic
+    }
+}
+
+/// Load and validate the mod at `root`, and return its reports.
+fn validate_mod(settings: &LspSettings, root: &Path) -> Result<Vec<LogReport>> {
+    let everything;
+
+    #[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
+    {
+        let modfile = ModFile::read(&root.join("descriptor.mod"))?;
+        everything = Everything::new(
+            settings.config.as_deref(),
+            settings.game.as_deref(),
+            settings.workshop.as_deref(),
+            settings.paradox.as_deref(),
+            &modfile.modpath(),
+            modfile.replace_paths(),
+        )?;
+    }
+    #[cfg(feature = "vic3")]
+    {
+        let metadata = ModMetadata::read(root)?;
+        everything = Everything::new(
+            settings.config.as_deref(),
+            settings.game.as_deref(),
+            settings.workshop.as_deref(),
+            settings.paradox.as_deref(),
+            root,
+            metadata.replace_paths(),
+        )?;
+    }
+    let mut everything = everything;
+
+    everything.load_config_filtering_rules();
+    if settings.show_vanilla {
+        set_show_vanilla(true);
+    }
+    if settings.show_mods {
+        set_show_loaded_mods(true);
+    }
+
+    everything.load_all();
+    everything.validate_all();
+    everything.check_rivers();
+    Ok(take_sorted_reports())
+}
+
+/// Source lines of the files that reports point to, used to convert columns to UTF-16 offsets.
+#[derive(Debug, Default)]
+struct LineCache(TigerHashMap<PathBuf, Vec<String>>);
+
+impl LineCache {
+    /// Convert a 1-based character column on a 1-based line to a 0-based UTF-16 offset, which is
+    /// what LSP clients expect by default.
+    fn utf16_column(&mut self, fullpath: &Path, line: u32, column: u32) -> usize {
+        let lines = self.0.entry(fullpath.to_path_buf()).or_insert_with(|| {
+            read_to_string(fullpath)
+                .map(|contents| {
+                    let contents = contents.strip_prefix('\u{feff}').unwrap_or(&contents);
+                    contents.lines().map(str::to_owned).collect()
+                })
+                .unwrap_or_default()
+        });
+        let chars = column.saturating_sub(1) as usize;
+        match lines.get(line.saturating_sub(1) as usize) {
+            Some(text) => {
+                text.chars().take(chars).map(char::len_utf16).sum::<usize>()
+                    + chars.saturating_sub(text.chars().count())
+            }
+            None => chars,
+        }
+    }
+}
+
+fn range(pointer: &PointedMessage, lines: &mut LineCache) -> Value {
+    let loc = pointer.loc;
+    if loc.line == 0 {
+        // The report is about the file as a whole.
+        return json!({
+            "start": { "line": 0, "character": 0 },
+            "end": { "line": 0, "character": 0 },
+        });
+    }
+    let line = loc.line - 1;
+    let length = u32::try_from(pointer.length.max(1)).unwrap_or(u32::MAX);
+    let start = lines.utf16_column(loc.fullpath(), loc.line, loc.column);
+    let end = lines.utf16_column(loc.fullpath(), loc.line, loc.column.saturating_add(length));
+    json!({
+        "start": { "line": line, "character": start },
+        "end": { "line": line, "character": end },
+    })
+}
+
+/// The range of the text that the fix of `pointer` replaces, which may span several lines.
+fn fix_range(pointer: &PointedMessage, lines: &mut LineCache) -> Value {
+    let mut range = range(pointer, lines);
+    if let Some(end) = pointer.fix_end {
+        let character = lines.utf16_column(end.fullpath(), end.line, end.column);
+        range["end"] = json!({ "line": end.line - 1, "character": character });
+    }
+    range
+}
+
+fn location(pointer: &PointedMessage, lines: &mut LineCache) -> Value {
+    json!({ "uri": path_to_uri(pointer.loc.fullpath()), "range": range(pointer, lines) })
+}
+
+/// Turn the fixes stored in the diagnostics of a code action request into quick fixes.
+fn code_actions(params: &Value) -> Value {
+    let mut actions = Vec::new();
+    let diagnostics = params["context"]["diagnostics"].as_array().map_or(&[][..], Vec::as_slice);
+    for diagnostic in diagnostics {
+        let Some(fixes) = diagnostic["data"]["fixes"].as_array() else {
+            continue;
+        };
+        for fix in fixes {
+            let title = match fix["newText"].as_str().unwrap_or_default() {
+                "" => "Remove".to_owned(),
+                text => format!("Replace with `{text}`"),
+            };
+            let Some(uri) = fix["uri"].as_str() else {
+                continue;
+            };
+            let edit = json!({ "range": fix["range"], "newText": fix["newText"] });
+            actions.push(json!({
+                "title": title,
+                "kind": "quickfix",
+                "diagnostics": [diagnostic],
+                "isPreferred": fixes.len() == 1,
+                "edit": { "changes": { uri: [edit] } },
+            }));
+        }
+    }
+    Value::Array(actions)
+}
+
+fn publish<O: Write>(output: &mut O, uri: &str, diagnostics: &[Value]) -> Result<()> {
+    write_message(
+        output,
+        &json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        }),
+    )
+}
+
+/// Send a `window/logMessage` notification. `level` 1 is an error, 3 is informational.
+fn log_message<O: Write>(output: &mut O, level: u8, message: &str) -> Result<()> {
+    write_message(
+        output,
+        &json!({
+            "jsonrpc": "2.0",
+            "method": "window/logMessage",
+            "params": { "type": level, "message": message },
+        }),
+    )
+}
+
+/// Read one JSON-RPC message, framed by a `Content-Length` header.
+/// Returns `None` when the input is closed.
+fn read_message<I: BufRead>(input: &mut I) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = Some(value.trim().parse::<usize>().context("bad Content-Length")?);
+            }
+        }
+    }
+    let Some(length) = length else {
+        bail!("LSP message without Content-Length header");
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+fn write_message<O: Write>(output: &mut O, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()?;
+    Ok(())
+}
+
+/// Get the mod root from the `initialize` parameters, preferring the first workspace folder.
+fn root_from_params(params: &Value) -> Option<PathBuf> {
+    params["workspaceFolders"][0]["uri"]
+        .as_str()
+        .or_else(|| params["rootUri"].as_str())
+        .and_then(uri_to_path)
+        .or_else(|| params["rootPath"].as_str().map(PathBuf::from))
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // On Windows the uri looks like file:///C:/path, and the leading slash must go.
+    if cfg!(windows) && path.as_bytes().get(2) == Some(&b':') {
+        return Some(PathBuf::from(&path[1..]));
+    }
+    Some(PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_message_framing() {
+        let first = json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" });
+        // The length is in bytes, not characters.
+        let second = json!({ "jsonrpc": "2.0", "method": "log", "params": "Café ü" });
+        let mut output = Vec::new();
+        write_message(&mut output, &first).unwrap();
+        write_message(&mut output, &second).unwrap();
+        let body = serde_json::to_string(&first).unwrap();
+        assert!(
+            output.starts_with(format!("Content-Length: {}\r\n\r\n{body}", body.len()).as_bytes())
+        );
+
+        let mut input = Cursor::new(output);
+        assert_eq!(read_message(&mut input).unwrap(), Some(first));
+        assert_eq!(read_message(&mut input).unwrap(), Some(second));
+        assert_eq!(read_message(&mut input).unwrap(), None);
+
+        // Other headers are skipped, and the header name is not case sensitive.
+        let body = r#"{"id":2}"#;
+        let message = format!(
+            "Content-Type: application/vscode-jsonrpc; charset=utf-8\r\n\
+             content-length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        let mut input = Cursor::new(message.into_bytes());
+        assert_eq!(read_message(&mut input).unwrap(), Some(json!({ "id": 2 })));
+
+        let mut input = Cursor::new(b"Content-Type: text\r\n\r\n{}".to_vec());
+        assert!(read_message(&mut input).is_err());
+    }
+
+    #[test]
+    fn test_uri_round_trip() {
+        let path = Path::new("/home/modder/My Mods/über mod/common/100%.txt");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///home/modder/My%20Mods/%C3%BCber%20mod/common/100%25.txt");
+        assert_eq!(uri_to_path(&uri).as_deref(), Some(path));
+
+        assert_eq!(uri_to_path("https://example.com/mod"), None);
+        assert_eq!(uri_to_path("file:///mod/bad%2"), None);
+        assert_eq!(uri_to_path("file:///mod/bad%zz"), None);
+    }
+
+    #[test]
+    fn test_code_actions() {
+        let fix = |text: &str| json!({ "uri": "file:///mod/a.txt", "range": { "start": 1 }, "newText": text });
+        let diagnostic =
+            json!({ "message": "typo", "data": { "fixes": [fix("add_gold"), fix("")] } });
+        let params = json!({ "context": { "diagnostics": [diagnostic, { "message": "no fix" }] } });
+        let actions = code_actions(&params);
+        let actions = actions.as_array().unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0]["title"], "Replace with `add_gold`");
+        assert_eq!(actions[1]["title"], "Remove");
+        assert_eq!(actions[0]["isPreferred"], false);
+        assert_eq!(
+            actions[0]["edit"]["changes"]["file:///mod/a.txt"],
+            json!([{ "range": { "start": 1 }, "newText": "add_gold" }])
+        );
+    }
+}
@@ -1,22 +1,32 @@
-use std::io::stdout;
+use std::fs::File;
+use std::io::{stdout, Stdout, Write};
+use std::process::exit;
+use std::str::FromStr;
 use std::{mem::forget, path::PathBuf};
 
 use anyhow::{bail, Result};
-use clap::{error::ErrorKind, Args, Parser, Subcommand};
+use clap::{error::ErrorKind, Args, Parser, Subcommand, ValueEnum};
+use strum::IntoEnumIterator;
 #[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
 use tiger_lib::ModFile;
 #[cfg(feature = "vic3")]
 use tiger_lib::ModMetadata;
 use tiger_lib::{
-    disable_ansi_colors, emit_reports, get_version_from_launcher, set_show_loaded_mods,
-    set_show_vanilla, suppress_from_json, validate_config_file, Everything,
+    apply_fixes, baseline_summary, disable_ansi_colors, emit_reports, emit_reports_sarif,
+    emit_summary, get_version_from_launcher, load_baseline, report_stats, set_explain,
+    set_keep_reports, set_macro_trace, set_show_loaded_mods, set_show_vanilla, suppress_from_json,
+    take_reports, validate_config_file, write_baseline, BaselineSummary, EmitSummary, ErrorKey,
+    Everything, Playset, Severity, LAUNCHER_DATABASE,
 };
 
+use crate::diff::{print_diff, reports_for, DiffSettings};
 use crate::gamedir::{
     find_game_directory_steam, find_paradox_directory, find_workshop_directory_steam,
 };
+use crate::lsp::{self, LspSettings};
 use crate::update::update;
 use crate::version::warn_versions;
+use crate::watch::ModWatcher;
 use crate::GameConsts;
 
 #[derive(Parser)]
@@ -37,6 +47,79 @@ enum Commands {
         /// release version (e.g. 0.9.3)
         version: Option<String>,
     },
+    /// Run as a Language Server Protocol server on stdin and stdout, so that editors can show
+    /// the reports as diagnostics. The mod is revalidated every time a file is saved.
+    Lsp(LspArgs),
+    /// Compare the reports for two versions of a mod, and print only the reports that were
+    /// introduced or resolved. Each version can be a mod path or a report file saved with --json.
+    Diff(DiffArgs),
+    /// Print a long-form explanation of a report key, with examples of code that triggers it and
+    /// how to fix it. Without a key, list all the keys.
+    Explain {
+        /// The key, as shown in the reports (e.g. missing-item)
+        key: Option<String>,
+    },
+}
+
+#[derive(Args)]
+struct DiffArgs {
+    /// The old version: a mod path or a JSON report file.
+    old: PathBuf,
+    /// The new version: a mod path or a JSON report file.
+    new: PathBuf,
+    #[cfg_attr(feature = "ck3", clap(visible_alias = "ck3"))]
+    #[cfg_attr(feature = "vic3", clap(visible_alias = "vic3"))]
+    #[cfg_attr(feature = "imperator", clap(visible_alias = "imperator"))]
+    #[clap(long)]
+    /// Path to game main directory.
+    game: Option<PathBuf>,
+    #[cfg_attr(not(feature = "vic3"), clap(skip))]
+    #[cfg_attr(feature = "vic3", clap(long))]
+    /// Path to game workshop directory.
+    workshop: Option<PathBuf>,
+    #[cfg_attr(not(any(feature = "ck3", feature = "imperator", feature = "hoi4")), clap(skip))]
+    #[cfg_attr(any(feature = "ck3", feature = "imperator", feature = "hoi4"), clap(long))]
+    /// Path to paradox directory
+    paradox: Option<PathBuf>,
+    /// Path to custom .conf file.
+    #[clap(long)]
+    config: Option<PathBuf>,
+    /// Show errors in the base game script code as well
+    #[clap(long)]
+    show_vanilla: bool,
+    /// Show errors in other loaded mods as well
+    #[clap(long)]
+    show_mods: bool,
+    /// Output the differences in JSON format
+    #[clap(long)]
+    json: bool,
+}
+
+#[derive(Args)]
+struct LspArgs {
+    #[cfg_attr(feature = "ck3", clap(visible_alias = "ck3"))]
+    #[cfg_attr(feature = "vic3", clap(visible_alias = "vic3"))]
+    #[cfg_attr(feature = "imperator", clap(visible_alias = "imperator"))]
+    #[clap(long)]
+    /// Path to game main directory.
+    game: Option<PathBuf>,
+    #[cfg_attr(not(feature = "vic3"), clap(skip))]
+    #[cfg_attr(feature = "vic3", clap(long))]
+    /// Path to game workshop directory.
+    workshop: Option<PathBuf>,
+    #[cfg_attr(not(any(feature = "ck3", feature = "imperator", feature = "hoi4")), clap(skip))]
+    #[cfg_attr(any(feature = "ck3", feature = "imperator", feature = "hoi4"), clap(long))]
+    /// Path to paradox directory
+    paradox: Option<PathBuf>,
+    /// Path to custom .conf file.
+    #[clap(long)]
+    config: Option<PathBuf>,
+    /// Show errors in the base game script code as well
+    #[clap(long)]
+    show_vanilla: bool,
+    /// Show errors in other loaded mods as well
+    #[clap(long)]
+    show_mods: bool,
 }
 
 #[derive(Args)]
@@ -64,15 +147,26 @@ struct ValidateArgs {
     /// Path to custom .conf file.
     #[clap(long)]
     config: Option<PathBuf>,
+    /// Load the mods of this playset from the Paradox launcher before the mod, in the launcher's
+    /// load order. Without a name, the playset that is active in the launcher is used.
+    #[clap(long, num_args = 0..=1, default_missing_value = "")]
+    playset: Option<String>,
+    /// Path to the Paradox launcher's database, to read the playset from. By default it is
+    /// looked for in the paradox directory.
+    #[clap(long, requires = "playset")]
+    launcher_db: Option<PathBuf>,
     /// Show errors in the base game script code as well
     #[clap(long)]
     show_vanilla: bool,
     /// Show errors in other loaded mods as well
     #[clap(long)]
     show_mods: bool,
-    /// Output the reports in JSON format
+    /// Output the reports in JSON format. Same as --format json.
     #[clap(long)]
     json: bool,
+    /// Output format for the reports.
+    #[clap(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
     /// Consolidate certain repeated errors
     #[clap(long, short)]
     consolidate: bool,
@@ -90,6 +184,125 @@ struct ValidateArgs {
     /// Load a JSON file of reports to remove from the output.
     #[clap(long)]
     suppress: Option<PathBuf>,
+    /// Apply the fixes suggested by the reports to the mod's files. Files of the base game and of
+    /// other loaded mods are never changed.
+    #[clap(long)]
+    fix: bool,
+    /// Write all reports to a baseline file instead of printing them.
+    #[clap(long, conflicts_with = "watch")]
+    write_baseline: Option<PathBuf>,
+    /// Load a baseline file and print only the reports that are not in it. Exit with code 5 if
+    /// there are any.
+    #[clap(long, conflicts_with = "watch")]
+    baseline: Option<PathBuf>,
+    /// Exit with code 3 if any reports of this severity or higher were printed: tips, untidy,
+    /// warning, error, or fatal.
+    #[clap(
+        long,
+        value_parser = parse_severity,
+        conflicts_with_all = ["watch", "write_baseline", "stats", "graph", "signatures", "compat"]
+    )]
+    fail_on: Option<Severity>,
+    /// Exit with code 4 if more than this many reports were printed.
+    #[clap(
+        long,
+        conflicts_with_all = ["watch", "write_baseline", "stats", "graph", "signatures", "compat"]
+    )]
+    max_reports: Option<usize>,
+    /// Keep running, and revalidate the changed files and the files that use their items whenever
+    /// the mod's files change.
+    #[clap(long)]
+    watch: bool,
+    /// After the reports, print a long-form explanation of each key that occurred.
+    #[clap(long)]
+    explain: bool,
+    /// For reports inside scripted effects, triggers, or modifiers that were called with macro
+    /// arguments, print each expansion that led to the report with the arguments it got.
+    #[clap(long)]
+    macro_trace: bool,
+    /// Print counts of the reports by key, severity, confidence, folder, and file, instead of
+    /// the reports themselves. Uses JSON with --format json.
+    #[clap(long, conflicts_with_all = ["watch", "write_baseline", "baseline"])]
+    stats: bool,
+    /// Append the counts to this file as a line of JSON, to track them over time.
+    #[clap(long, requires = "stats")]
+    stats_history: Option<PathBuf>,
+    /// A label for the --stats-history entry, such as the mod's release version.
+    #[clap(long, requires = "stats_history")]
+    stats_label: Option<String>,
+    /// Print the graph of which events, `on_actions`, and scripted effects call each other,
+    /// instead of the reports. Events that nothing calls are marked as unreachable.
+    #[clap(long, value_enum, conflicts_with_all = ["watch", "write_baseline", "baseline", "stats"])]
+    graph: Option<GraphFormat>,
+    /// Print what each scripted effect, scripted trigger, and script value expects of its callers,
+    /// instead of the reports. Only the mod's own items are included, unless --show-vanilla is
+    /// also given.
+    #[clap(
+        long,
+        value_enum,
+        conflicts_with_all = ["watch", "write_baseline", "baseline", "stats", "graph"]
+    )]
+    signatures: Option<SignatureFormat>,
+    /// Print the files, items, and localization keys that the mods loaded with `load_mod` in the
+    /// config or with --playset and the mod itself override in each other, instead of the reports.
+    #[clap(
+        long,
+        value_enum,
+        conflicts_with_all = [
+            "watch", "write_baseline", "baseline", "stats", "graph", "signatures", "xref"
+        ]
+    )]
+    compat: Option<CompatFormat>,
+    /// Write an index of where items are defined and used to this file, as JSON. Only the items
+    /// defined or used in the mod are included, unless --show-vanilla is also given.
+    #[clap(long, conflicts_with = "watch")]
+    xref: Option<PathBuf>,
+    /// Write the graph of the river network to this file, as `GeoJSON`, or as SVG if the file name
+    /// ends in `.svg`. Problems with the rivers are included, with the pixel changes that would
+    /// fix them.
+    #[clap(long, conflicts_with = "watch")]
+    river_graph: Option<PathBuf>,
+}
+
+/// The exit code when reports at or above the `--fail-on` severity were printed.
+const EXIT_FAIL_ON: i32 = 3;
+/// The exit code when more reports than `--max-reports` were printed.
+const EXIT_MAX_REPORTS: i32 = 4;
+/// The exit code when reports that are not in the `--baseline` were printed.
+const EXIT_BASELINE: i32 = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Human-readable text
+    Text,
+    /// Tiger's own JSON format
+    Json,
+    /// SARIF 2.1.0, for code scanning tools
+    Sarif,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SignatureFormat {
+    /// A JSON array with an object per item
+    Json,
+    /// A markdown document, for use as documentation
+    Markdown,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CompatFormat {
+    /// Human-readable text
+    Text,
+    /// A JSON object with the mods and the conflicts between each pair of them
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GraphFormat {
+    /// The DOT language of Graphviz
+    Dot,
+    /// A JSON object with lists of nodes and edges
+    Json,
 }
 
 /// Run the main tiger application.
@@ -115,17 +328,84 @@ pub fn run(
             update(current_version, target_version.as_deref())?;
             Ok(())
         }
+        Some(Commands::Lsp(args)) => {
+            let mut settings = LspSettings {
+                game: args.game.or_else(|| find_game_directory_steam(app_id).ok()),
+                workshop: args.workshop.or_else(|| find_workshop_directory_steam(app_id).ok()),
+                paradox: args
+                    .paradox
+                    .or_else(|| find_paradox_directory(&PathBuf::from(paradox_dir))),
+                config: validate_config_file(args.config),
+                show_vanilla: args.show_vanilla,
+                show_mods: args.show_mods,
+            };
+            if let Some(ref mut game) = settings.game {
+                check_game_directory(game, name_short, signature_file)?;
+            } else {
+                bail!("Cannot find {name_short} directory. Please supply it as the --game option.");
+            }
+            // Colors would only garble the messages sent to the editor.
+            disable_ansi_colors();
+            lsp::run(settings, bin_name, current_version)
+        }
+        Some(Commands::Diff(args)) => {
+            let mut settings = DiffSettings {
+                game: args.game.or_else(|| find_game_directory_steam(app_id).ok()),
+                workshop: args.workshop.or_else(|| find_workshop_directory_steam(app_id).ok()),
+                paradox: args
+                    .paradox
+                    .or_else(|| find_paradox_directory(&PathBuf::from(paradox_dir))),
+                config: validate_config_file(args.config),
+                show_vanilla: args.show_vanilla,
+                show_mods: args.show_mods,
+            };
+            if let Some(ref mut game) = settings.game {
+                check_game_directory(game, name_short, signature_file)?;
+            } else {
+                bail!("Cannot find {name_short} directory. Please supply it as the --game option.");
+            }
+            disable_ansi_colors();
+            let old = reports_for(&settings, &args.old)?;
+            let new = reports_for(&settings, &args.new)?;
+            print_diff(old, new, args.json)
+        }
+        Some(Commands::Explain { key: None }) => {
+            for key in ErrorKey::iter() {
+                println!("{key}");
+            }
+            Ok(())
+        }
+        Some(Commands::Explain { key: Some(key) }) => {
+            let Ok(key) = ErrorKey::from_str(&key) else {
+                bail!("Unknown key `{key}`. Run `{bin_name} explain` for a list of keys.");
+            };
+            print!("{}", key.explanation());
+            Ok(())
+        }
         None => {
             let mut args = cli.validate_args.unwrap();
 
-            if args.json && args.consolidate {
+            if args.json {
+                args.format = Format::Json;
+            }
+            if args.format != Format::Text && args.consolidate {
                 Cli::command()
                     .error(
                         ErrorKind::ArgumentConflict,
-                        "Can't use report consolidation with JSON output.",
+                        "Can't use report consolidation with JSON or SARIF output.",
                     )
                     .exit();
             }
+            if args.format == Format::Sarif && args.stats {
+                Cli::command()
+                    .error(ErrorKind::ArgumentConflict, "Can't output report counts as SARIF.")
+                    .exit();
+            }
+            let emit = |output: &mut Stdout| match args.format {
+                Format::Text => emit_reports(output, false, args.consolidate, true),
+                Format::Json => emit_reports(output, true, false, true),
+                Format::Sarif => emit_reports_sarif(output, bin_name, current_version),
+            };
 
             #[cfg(windows)]
             if !args.no_color {
@@ -143,21 +423,7 @@ pub fn run(
                 args.paradox = find_paradox_directory(&PathBuf::from(paradox_dir));
             }
             if let Some(ref mut game) = args.game {
-                eprintln!("Using {name_short} directory: {}", game.display());
-                let mut sig = game.clone();
-                sig.push(signature_file);
-                if !sig.is_file() {
-                    eprintln!("That does not look like a {name_short} directory.");
-                    game.push("..");
-                    eprintln!("Trying: {}", game.display());
-                    sig.clone_from(game);
-                    sig.push(signature_file);
-                    if sig.is_file() {
-                        eprintln!("Ok.");
-                    } else {
-                        bail!("Cannot find {name_short} directory. Please supply it as the --game option.");
-                    }
-                }
+                check_game_directory(game, name_short, signature_file)?;
             } else {
                 bail!("Cannot find {name_short} directory. Please supply it as the --game option.");
             }
@@ -186,6 +452,14 @@ pub fn run(
                 suppress_from_json(&suppress)?;
             }
 
+            if let Some(baseline) = &args.baseline {
+                eprintln!(
+                    "Showing only reports that are not in the baseline: {}",
+                    baseline.display()
+                );
+                load_baseline(baseline)?;
+            }
+
             if args.show_vanilla {
                 eprintln!("Showing warnings for base game files too. There will be many false positives in those.");
             }
@@ -205,98 +479,384 @@ pub fn run(
                 eprintln!("Doing special checks for the Princes of Darkness mod.");
             }
 
+            if args.explain {
+                set_explain(true);
+            }
+
+            if args.macro_trace {
+                set_macro_trace(true);
+            }
+
             if args.no_color {
                 // Disable colors both here and after reading the config, because reading the modfile and config may emit errors.
                 disable_ansi_colors();
             }
 
-            let mut everything;
-
-            #[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
-            {
-                if args.modpath.is_dir() {
-                    args.modpath.push("descriptor.mod");
-                }
-
-                let modfile = ModFile::read(&args.modpath)?;
-                let modpath = modfile.modpath();
-                if !modpath.exists() {
-                    eprintln!("Looking for mod in {}", modpath.display());
-                    bail!("Cannot find mod directory. Please make sure the .mod file is correct.");
-                }
-                eprintln!("Using mod directory: {}", modpath.display());
+            let (modpath, replace_paths) = find_mod(args.modpath.clone())?;
+
+            let playset = if let Some(name) = &args.playset {
+                let Some(db) = args
+                    .launcher_db
+                    .clone()
+                    .or_else(|| args.paradox.as_ref().map(|dir| dir.join(LAUNCHER_DATABASE)))
+                else {
+                    bail!("Cannot find the launcher database. Please supply it as the --launcher-db option.");
+                };
+                let name = (!name.is_empty()).then_some(name.as_str());
+                let playset = Playset::read(&db, name)?;
+                eprintln!("Using playset \"{}\" from: {}", playset.name, db.display());
+                Some(playset)
+            } else {
+                None
+            };
 
-                everything = Everything::new(
+            // Create and load everything. This is done again in watch mode when starting over.
+            let load_everything = |output: &mut Stdout| -> Result<Everything> {
+                let mut everything = Everything::new_with_playset(
                     args.config.as_deref(),
                     args.game.as_deref(),
                     args.workshop.as_deref(),
                     args.paradox.as_deref(),
                     &modpath,
-                    modfile.replace_paths(),
+                    replace_paths.clone(),
+                    playset.as_ref(),
                 )?;
-            }
-            #[cfg(feature = "vic3")]
-            {
-                let metadata = ModMetadata::read(&args.modpath)?;
-                eprintln!("Using mod directory: {}", metadata.modpath().display());
 
-                everything = Everything::new(
-                    args.config.as_deref(),
-                    args.game.as_deref(),
-                    args.workshop.as_deref(),
-                    args.paradox.as_deref(),
-                    &args.modpath,
-                    metadata.replace_paths(),
-                )?;
-            }
+                everything.load_output_settings(true);
+                everything.load_config_filtering_rules();
+
+                if args.format == Format::Text
+                    && emit_reports(output, false, args.consolidate, false)
+                {
+                    bail!("Invalid config");
+                }
+
+                // We must apply the --no-color flag AFTER loading and applying the config,
+                // because we want it to override the config.
+                if args.no_color {
+                    disable_ansi_colors();
+                }
+                // Same logic applies to showing vanilla and other mods
+                if args.show_vanilla {
+                    set_show_vanilla(true);
+                }
+                if args.show_mods {
+                    set_show_loaded_mods(true);
+                }
+
+                // In watch mode, the recorded uses tell which files to validate again after a change.
+                if args.xref.is_some() || args.watch {
+                    everything.enable_xref();
+                }
+                if args.graph.is_some() || args.unused {
+                    everything.enable_call_graph();
+                }
+                if args.unused {
+                    everything.enable_unused();
+                }
+                everything.load_all();
+                Ok(everything)
+            };
+
+            // Run all the checks that were asked for. This is done again in watch mode when
+            // starting over.
+            let validate_everything = |everything: &mut Everything| {
+                everything.validate_all();
+                everything.check_rivers();
+
+                #[cfg(feature = "ck3")]
+                if args.pod {
+                    everything.check_pod();
+                }
+
+                if args.unused {
+                    everything.check_unused();
+                }
+            };
 
             // Print a blank line between the preamble and the first report:
             eprintln!();
 
-            everything.load_output_settings(true);
-            everything.load_config_filtering_rules();
-
             let mut output = stdout();
+            let mut everything = load_everything(&mut output)?;
+
+            // The conflicts are all known after loading, so validation can be skipped.
+            if let Some(format) = args.compat {
+                let report = everything.compat_report();
+                match format {
+                    CompatFormat::Text => report.write_text(&mut output)?,
+                    CompatFormat::Json => report.write_json(&mut output)?,
+                }
+                forget(everything);
+                return Ok(());
+            }
+
+            validate_everything(&mut everything);
 
-            if !args.json && emit_reports(&mut output, false, args.consolidate, false) {
-                bail!("Invalid config");
+            if args.fix {
+                let summary = apply_fixes();
+                eprintln!("Applied {} fixes to {} files.", summary.applied, summary.files);
+                if summary.skipped > 0 {
+                    eprintln!(
+                        "Skipped {} fixes that could not be applied safely.",
+                        summary.skipped
+                    );
+                }
             }
 
-            // We must apply the --no-color flag AFTER loading and applying the config,
-            // because we want it to override the config.
-            if args.no_color {
-                disable_ansi_colors();
+            if let Some(path) = &args.xref {
+                let mut index = everything.xref_index();
+                if !args.show_vanilla {
+                    index.retain_mod_related();
+                }
+                index.write_json(&mut File::create(path)?)?;
+                eprintln!("Wrote the cross-reference index to {}", path.display());
             }
-            // Same logic applies to showing vanilla and other mods
-            if args.show_vanilla {
-                set_show_vanilla(true);
+
+            if let Some(path) = &args.river_graph {
+                if let Some(graph) = everything.river_graph() {
+                    let mut file = File::create(path)?;
+                    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("svg")) {
+                        graph.write_svg(&mut file)?;
+                    } else {
+                        graph.write_geojson(&mut file)?;
+                    }
+                    eprintln!("Wrote the river graph to {}", path.display());
+                } else {
+                    eprintln!("Could not load the rivers image for the river graph");
+                }
             }
-            if args.show_mods {
-                set_show_loaded_mods(true);
+
+            if let Some(format) = args.signatures {
+                let mut signatures = everything.script_signatures();
+                if !args.show_vanilla {
+                    signatures.retain_mod_related();
+                }
+                match format {
+                    SignatureFormat::Json => signatures.write_json(&mut output)?,
+                    SignatureFormat::Markdown => signatures.write_markdown(&mut output)?,
+                }
+                forget(everything);
+                return Ok(());
             }
 
-            everything.load_all();
-            everything.validate_all();
-            everything.check_rivers();
+            if let Some(format) = args.graph {
+                match format {
+                    GraphFormat::Dot => everything.call_graph().write_dot(&mut output)?,
+                    GraphFormat::Json => everything.call_graph().write_json(&mut output)?,
+                }
+                forget(everything);
+                return Ok(());
+            }
 
-            #[cfg(feature = "ck3")]
-            if args.pod {
-                everything.check_pod();
+            if args.stats {
+                let stats = report_stats();
+                if args.format == Format::Json {
+                    serde_json::to_writer_pretty(&mut output, &stats)?;
+                    writeln!(output)?;
+                } else {
+                    stats.write_table(&mut output)?;
+                }
+                if let Some(path) = &args.stats_history {
+                    stats.append_to_history(path, args.stats_label.as_deref())?;
+                    eprintln!("Appended the counts to {}", path.display());
+                }
+                forget(everything);
+                return Ok(());
             }
 
-            if args.unused {
-                everything.check_unused();
+            if let Some(path) = &args.write_baseline {
+                let count = write_baseline(path)?;
+                eprintln!("Wrote {count} reports to baseline {}", path.display());
+                forget(everything);
+                return Ok(());
             }
 
-            let any_printed = emit_reports(&mut output, args.json, args.consolidate, true);
+            // In watch mode, the reports are kept so that the full set can be printed after each
+            // change, not just the reports about the files that were validated again.
+            if args.watch {
+                set_keep_reports(true);
+            }
+            let any_printed = emit(&mut output);
 
-            if !args.json && !any_printed {
+            let baseline = baseline_summary();
+            if let Some(summary) = &baseline {
+                eprintln!(
+                    "Compared to the baseline: {} new, {} fixed, {} still present.",
+                    summary.new, summary.fixed, summary.still_present
+                );
+            } else if args.format == Format::Text && !any_printed {
                 eprintln!("No problems found.");
             }
 
+            if let Some((code, msg)) =
+                failed_check(args.fail_on, args.max_reports, &emit_summary(), baseline.as_ref())
+            {
+                eprintln!("{msg}");
+                output.flush()?;
+                forget(everything);
+                exit(code);
+            }
+
+            if args.watch {
+                let mut watcher = ModWatcher::new(&modpath);
+                loop {
+                    let changed = watcher.wait_for_changes();
+                    eprintln!("\nChanges detected in {} file(s), revalidating.", changed.len());
+                    if everything.reload_files(&changed) {
+                        everything.validate_changed();
+                    } else {
+                        // Files were added or removed, or the config changed, so start over.
+                        take_reports();
+                        everything = load_everything(&mut output)?;
+                        validate_everything(&mut everything);
+                    }
+                    if !emit(&mut output) && args.format == Format::Text {
+                        eprintln!("No problems found.");
+                    }
+                }
+            }
+
             // Properly dropping `everything` takes a noticeable amount of time, and we're exiting anyway.
             forget(everything);
             Ok(())
         }
     }
 }
+
+/// Check the printed reports against `--fail-on`, `--max-reports`, and `--baseline`. If any of
+/// them fails, return the exit code and the message for the one with the lowest exit code.
+///
+/// These options conflict with `--watch`, so the process never gets to the watch loop after a
+/// failed check.
+fn failed_check(
+    fail_on: Option<Severity>,
+    max_reports: Option<usize>,
+    emitted: &EmitSummary,
+    baseline: Option<&BaselineSummary>,
+) -> Option<(i32, String)> {
+    if let Some(severity) = fail_on {
+        let count = emitted.at_least(severity);
+        if count > 0 {
+            return Some((
+                EXIT_FAIL_ON,
+                format!("Found {count} reports of severity {severity} or higher."),
+            ));
+        }
+    }
+    if let Some(max_reports) = max_reports {
+        if emitted.total() > max_reports {
+            return Some((
+                EXIT_MAX_REPORTS,
+                format!(
+                    "Found {} reports, which is more than the maximum of {max_reports}.",
+                    emitted.total()
+                ),
+            ));
+        }
+    }
+    if let Some(summary) = baseline {
+        if summary.new > 0 {
+            return Some((
+                EXIT_BASELINE,
+                format!("Found {} new reports that are not in the baseline.", summary.new),
+            ));
+        }
+    }
+    None
+}
+
+fn parse_severity(s: &str) -> Result<Severity, String> {
+    s.to_ascii_lowercase().parse().map_err(|_| {
+        let valid: Vec<&str> = Severity::iter().map(Severity::into).collect();
+        format!("valid values: {}", valid.join(", "))
+    })
+}
+
+/// Check that `game` really is the game directory, by looking for the `signature_file`.
+/// If it isn't, try the directory above it, and update `game` if that one is correct.
+fn check_game_directory(
+    game: &mut PathBuf,
+    name_short: &'static str,
+    signature_file: &'static str,
+) -> Result<()> {
+    eprintln!("Using {name_short} directory: {}", game.display());
+    let mut sig = game.clone();
+    sig.push(signature_file);
+    if !sig.is_file() {
+        eprintln!("That does not look like a {name_short} directory.");
+        game.push("..");
+        eprintln!("Trying: {}", game.display());
+        sig.clone_from(game);
+        sig.push(signature_file);
+        if sig.is_file() {
+            eprintln!("Ok.");
+        } else {
+            bail!("Cannot find {name_short} directory. Please supply it as the --game option.");
+        }
+    }
+    Ok(())
+}
+
+/// Read the mod's `.mod` file or metadata, and return the mod's root directory and the paths
+/// that it replaces.
+#[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
+pub(crate) fn find_mod(mut modpath: PathBuf) -> Result<(PathBuf, Vec<PathBuf>)> {
+    if modpath.is_dir() {
+        modpath.push("descriptor.mod");
+    }
+
+    let modfile = ModFile::read(&modpath)?;
+    let modpath = modfile.modpath();
+    if !modpath.exists() {
+        eprintln!("Looking for mod in {}", modpath.display());
+        bail!("Cannot find mod directory. Please make sure the .mod file is correct.");
+    }
+    eprintln!("Using mod directory: {}", modpath.display());
+    Ok((modpath, modfile.replace_paths()))
+}
+
+/// Read the mod's `.mod` file or metadata, and return the mod's root directory and the paths
+/// that it replaces.
+#[cfg(feature = "vic3")]
+pub(crate) fn find_mod(modpath: PathBuf) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let metadata = ModMetadata::read(&modpath)?;
+    eprintln!("Using mod directory: {}", metadata.modpath().display());
+    Ok((modpath, metadata.replace_paths()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_check() {
+        let code = |fail_on, max_reports, emitted: &EmitSummary, new: Option<usize>| {
+            let baseline = new.map(|new| BaselineSummary { new, fixed: 0, still_present: 0 });
+            failed_check(fail_on, max_reports, emitted, baseline.as_ref()).map(|(code, _)| code)
+        };
+        let none = EmitSummary::default();
+        let untidy = [Severity::Untidy, Severity::Untidy].into_iter().collect();
+        let error = [Severity::Tips, Severity::Error].into_iter().collect();
+
+        // Without the options, nothing fails.
+        assert_eq!(code(None, None, &error, None), None);
+        assert_eq!(code(Some(Severity::Tips), Some(0), &none, Some(0)), None);
+
+        // `--fail-on` counts the reports at or above its severity.
+        assert_eq!(code(Some(Severity::Warning), None, &untidy, None), None);
+        assert_eq!(code(Some(Severity::Warning), None, &error, None), Some(EXIT_FAIL_ON));
+        assert_eq!(code(Some(Severity::Error), None, &error, None), Some(EXIT_FAIL_ON));
+
+        // `--max-reports` allows exactly that many.
+        assert_eq!(code(None, Some(2), &untidy, None), None);
+        assert_eq!(code(None, Some(1), &untidy, None), Some(EXIT_MAX_REPORTS));
+        assert_eq!(code(None, None, &none, Some(1)), Some(EXIT_BASELINE));
+
+        // When several checks fail, the lowest exit code wins.
+        assert_eq!(code(Some(Severity::Tips), Some(1), &untidy, Some(2)), Some(EXIT_FAIL_ON));
+        assert_eq!(code(Some(Severity::Error), Some(1), &untidy, Some(2)), Some(EXIT_MAX_REPORTS));
+        let (_, msg) = failed_check(None, Some(1), &error, None).unwrap();
+        assert_eq!(msg, "Found 2 reports, which is more than the maximum of 1.");
+    }
+}
@@ -0,0 +1,79 @@
+//! Polling file watcher for the `--watch` option.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+/// How often to check the mod directory for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How long to wait for further changes after detecting one, so that a batch of saves is handled
+/// together.
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// Remembers the modification times of all the files under a directory.
+pub struct ModWatcher {
+    root: PathBuf,
+    mtimes: HashMap<PathBuf, SystemTime>,
+}
+
+impl ModWatcher {
+    pub fn new(root: &Path) -> Self {
+        Self { root: root.to_path_buf(), mtimes: scan(root) }
+    }
+
+    /// Block until files under the root directory have changed, then return their full paths.
+    /// Added and removed files are returned too.
+    pub fn wait_for_changes(&mut self) -> Vec<PathBuf> {
+        loop {
+            sleep(POLL_INTERVAL);
+            let mut changed = self.poll();
+            if !changed.is_empty() {
+                sleep(SETTLE_TIME);
+                for path in self.poll() {
+                    if !changed.contains(&path) {
+                        changed.push(path);
+                    }
+                }
+                return changed;
+            }
+        }
+    }
+
+    fn poll(&mut self) -> Vec<PathBuf> {
+        let mtimes = scan(&self.root);
+        let mut changed: Vec<PathBuf> = mtimes
+            .iter()
+            .filter(|(path, mtime)| self.mtimes.get(*path) != Some(mtime))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.extend(self.mtimes.keys().filter(|path| !mtimes.contains_key(*path)).cloned());
+        self.mtimes = mtimes;
+        changed
+    }
+}
+
+fn scan(root: &Path) -> HashMap<PathBuf, SystemTime> {
+    let mut mtimes = HashMap::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                // Version control directories change often and are never loaded.
+                if entry.file_name() != ".git" {
+                    dirs.push(entry.path());
+                }
+            } else if let Ok(mtime) = metadata.modified() {
+                mtimes.insert(entry.path(), mtime);
+            }
+        }
+    }
+    mtimes
+}